@@ -4,8 +4,8 @@ use criterion::{
 };
 use shiba_bench::asset;
 use shiba_preview::bench::{
-    modified_offset, modified_offset_scalar, DisplayText, History, MarkdownContent, MarkdownParser,
-    RawMessageWriter, SearchMatcher,
+    modified_offset, modified_offset_scalar, DisplayText, History, Markdown, MarkdownContent,
+    MarkdownParser, RawMessageWriter, SearchMatcher,
 };
 use std::hint::black_box;
 use std::io::sink;
@@ -13,7 +13,7 @@ use std::path::PathBuf;
 
 fn markdown_parse(g: &mut BenchmarkGroup<'_, WallTime>) {
     fn run(source: String, offset: Option<usize>) {
-        let target = MarkdownContent::new(source, None);
+        let target = MarkdownContent::new(source, None, &Markdown::default());
         let parser = MarkdownParser::new(&target, offset, ());
         let mut buf = Vec::new();
         let () = parser.write_to(&mut buf).unwrap();
@@ -46,7 +46,7 @@ fn markdown_parse(g: &mut BenchmarkGroup<'_, WallTime>) {
     });
 
     {
-        let content = MarkdownContent::new(middle.clone(), None);
+        let content = MarkdownContent::new(middle.clone(), None, &Markdown::default());
         let parser = MarkdownParser::new(&content, None, ());
         let text: DisplayText = parser.write_to(sink()).unwrap();
         let matches = text.search("a", SearchMatcher::CaseInsensitive).unwrap();
@@ -76,7 +76,7 @@ fn markdown_search(g: &mut BenchmarkGroup<'_, WallTime>) {
     let source = asset("actionlint.md");
     g.throughput(Throughput::Bytes(source.len() as u64));
 
-    let content = MarkdownContent::new(source, None);
+    let content = MarkdownContent::new(source, None, &Markdown::default());
     let parser = MarkdownParser::new(&content, None, ());
     let text: DisplayText = parser.write_to(sink()).unwrap();
 
@@ -107,6 +107,16 @@ fn markdown_search(g: &mut BenchmarkGroup<'_, WallTime>) {
             matcher: SearchMatcher::CaseInsensitive,
             query: "THIS-DOES-NOT-MATCH",
         },
+        Case { name: "whole_word_match", matcher: SearchMatcher::WholeWord, query: "a" },
+        Case { name: "whole_word_match", matcher: SearchMatcher::WholeWord, query: "actionlint" },
+        Case {
+            name: "whole_word_no_match",
+            matcher: SearchMatcher::WholeWord,
+            query: "this-does-not-match",
+        },
+        Case { name: "fuzzy_match", matcher: SearchMatcher::Fuzzy, query: "a" },
+        Case { name: "fuzzy_match", matcher: SearchMatcher::Fuzzy, query: "actnlnt" },
+        Case { name: "fuzzy_no_match", matcher: SearchMatcher::Fuzzy, query: "thisdoesnotmatch" },
         Case { name: "regex_match", matcher: SearchMatcher::CaseSensitiveRegex, query: "a" },
         Case {
             name: "regex_match",
@@ -118,6 +128,11 @@ fn markdown_search(g: &mut BenchmarkGroup<'_, WallTime>) {
             matcher: SearchMatcher::CaseSensitiveRegex,
             query: "\\bthisdoesnotmatch\\b",
         },
+        Case {
+            name: "case_insensitive_regex_match",
+            matcher: SearchMatcher::CaseInsensitiveRegex,
+            query: "\\bACTION\\w+\\b",
+        },
     ];
 
     for case in cases {
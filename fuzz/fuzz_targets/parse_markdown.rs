@@ -6,7 +6,7 @@ use libfuzzer_sys::fuzz_target;
 use oxc_allocator::Allocator;
 use oxc_parser::Parser;
 use oxc_span::SourceType;
-use shiba_preview::bench::{MarkdownContent, MarkdownParser, RawMessageWriter};
+use shiba_preview::bench::{Markdown, MarkdownContent, MarkdownParser, RawMessageWriter};
 
 // Offset must be on UTF-8 boundary. It is guaranteed by `MarkdownContent::modified_utf8_offset`.
 fn adjust_offset(offset: Option<usize>, src: &str) -> Option<usize> {
@@ -25,7 +25,7 @@ fuzz_target!(|data: &[u8]| {
         return;
     };
     let offset = adjust_offset(offset, &source);
-    let target = MarkdownContent::new(source, None);
+    let target = MarkdownContent::new(source, None, &Markdown::default());
     let parser = MarkdownParser::new(&target, offset, ());
     let mut buf = Vec::new();
     let () = parser.write_to(&mut buf).unwrap();
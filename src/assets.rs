@@ -1,8 +1,10 @@
 use crate::config::{Config, PreviewHighlight};
+use crate::markdown::{cached_diagram_path, diagram_cache_dir};
 use phf::phf_map;
 use std::borrow::Cow;
 use std::fs;
 use std::io::Write;
+use std::path::PathBuf;
 
 #[cfg(debug_assertions)]
 const BUNDLE_JS: &[u8] = include_bytes!("assets/bundle.js");
@@ -150,10 +152,26 @@ fn load_hljs_css(hl: &PreviewHighlight) -> Cow<'static, [u8]> {
     buf.into()
 }
 
-fn load_user_css(config: &Config) -> Option<Vec<u8>> {
+pub fn is_hljs_theme(name: &str) -> bool {
+    HLJS_CSS_TABLE.contains_key(name)
+}
+
+pub fn hljs_theme_names() -> Vec<&'static str> {
+    let mut names: Vec<_> = HLJS_CSS_TABLE.keys().copied().collect();
+    names.sort_unstable();
+    names
+}
+
+// Path to the user CSS file which is relative to the config directory
+pub fn user_css_path(config: &Config) -> Option<PathBuf> {
     let config_dir = config.config_dir()?;
     let css_path = config.preview().css_path()?;
-    let css_path = config_dir.join(css_path);
+    Some(config_dir.join(css_path))
+}
+
+fn load_user_css(config: &Config) -> Option<Vec<u8>> {
+    let config_dir = config.config_dir()?;
+    let css_path = user_css_path(config)?;
 
     log::debug!("Loading user CSS at {:?}", css_path);
     match fs::read(&css_path) {
@@ -170,7 +188,7 @@ fn load_user_css(config: &Config) -> Option<Vec<u8>> {
     }
 }
 
-fn guess_mime(path: &str) -> &'static str {
+pub fn guess_mime(path: &str) -> &'static str {
     if let Some(idx) = path.rfind('.')
         && let Some(mime) = MIME_TABLE.get(&path[idx + 1..])
     {
@@ -183,6 +201,7 @@ fn guess_mime(path: &str) -> &'static str {
 pub struct Assets {
     hljs_css: Cow<'static, [u8]>,
     markdown_css: Cow<'static, [u8]>,
+    diagram_cache_dir: Option<PathBuf>,
 }
 
 impl Assets {
@@ -194,14 +213,37 @@ impl Assets {
             Cow::Borrowed(GITHUB_MARKDOWN_CSS)
         };
 
+        let diagram_cache_dir = diagram_cache_dir(config.data_dir());
+
         // Note: We don't keep bundle.js payload on memory because it's large.
 
-        Self { hljs_css, markdown_css }
+        Self { hljs_css, markdown_css, diagram_cache_dir }
+    }
+
+    pub fn markdown_css(&self) -> &[u8] {
+        &self.markdown_css
+    }
+
+    pub fn hljs_css(&self) -> &[u8] {
+        &self.hljs_css
     }
 
     pub fn load(&self, path: &str) -> (Option<Cow<'static, [u8]>>, &'static str) {
         let mime = guess_mime(path);
 
+        // Diagrams rendered by local commands are cached in the data directory
+        if let Some(dir) = &self.diagram_cache_dir
+            && let Some(file) = cached_diagram_path(dir, path)
+        {
+            return match fs::read(&file) {
+                Ok(svg) => (Some(svg.into()), mime),
+                Err(err) => {
+                    log::error!("Could not read cached diagram {:?}: {}", file, err);
+                    (None, mime)
+                }
+            };
+        }
+
         #[rustfmt::skip]
         let body = match path {
             "/index.html"          => INDEX_HTML.into(),
@@ -330,6 +372,26 @@ mod tests {
         assert!(css.starts_with(b"/* this is test CSS file */"));
     }
 
+    #[test]
+    fn load_cached_diagram() {
+        let tmp = tempfile::tempdir().unwrap();
+        let config = Config::new(UserConfig::default(), tmp.path(), tmp.path());
+        let assets = Assets::new(&config);
+        let file = "0123456789abcdef0123456789abcdef.svg";
+        let path = format!("/diagrams/{file}");
+
+        let (svg, mime) = assets.load(&path);
+        assert!(svg.is_none());
+        assert_eq!(mime, "image/svg+xml");
+
+        let dir = tmp.path().join("Diagrams");
+        fs::create_dir(&dir).unwrap();
+        fs::write(dir.join(file), "<svg></svg>").unwrap();
+        let (svg, mime) = assets.load(&path);
+        assert_eq!(svg.unwrap().as_ref(), b"<svg></svg>");
+        assert_eq!(mime, "image/svg+xml");
+    }
+
     #[test]
     fn load_non_default_hljs_themes() {
         let mut user = UserConfig::default();
@@ -0,0 +1,160 @@
+use crate::persistent::{DataDir, PersistentData};
+use crate::renderer::{InitFile, InitScroll, MessageToWindow, Window};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+// Bookmark points to a file or a heading in the file with the label given by user. The fragment is preferred to the
+// heading index on opening the bookmark because it is more robust against adding or removing headings in the file.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+pub struct Bookmark {
+    pub label: String,
+    pub path: PathBuf,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub heading: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fragment: Option<String>,
+}
+
+impl Bookmark {
+    fn is_same_position(&self, other: &Self) -> bool {
+        self.path == other.path && self.heading == other.heading && self.fragment == other.fragment
+    }
+
+    pub fn init_file(&self) -> InitFile {
+        let scroll = match (&self.fragment, self.heading) {
+            (Some(fragment), _) => InitScroll::Fragment(fragment.clone()),
+            (None, Some(index)) => InitScroll::Heading(index),
+            (None, None) => InitScroll::Nop,
+        };
+        InitFile { path: self.path.clone(), scroll }
+    }
+}
+
+#[derive(Serialize, Deserialize, Default, Debug)]
+pub struct Bookmarks {
+    items: Vec<Bookmark>,
+}
+
+impl PersistentData for Bookmarks {
+    const FILE: &str = "bookmarks.json";
+}
+
+// Unlike history, bookmarks are saved immediately on being modified since they are explicitly added by user
+impl Bookmarks {
+    pub fn load(data_dir: &DataDir) -> Self {
+        let loaded: Self = data_dir.load().unwrap_or_default();
+        log::debug!("Loaded {} bookmarks from persistent data", loaded.items.len());
+        loaded
+    }
+
+    pub fn get(&self, index: usize) -> Option<&Bookmark> {
+        self.items.get(index)
+    }
+
+    // Adding a bookmark at the same position as the existing one only updates its label
+    pub fn add(&mut self, bookmark: Bookmark, data_dir: &DataDir) -> Result<()> {
+        log::debug!("Add bookmark: {bookmark:?}");
+        if let Some(existing) = self.items.iter_mut().find(|b| b.is_same_position(&bookmark)) {
+            existing.label = bookmark.label;
+        } else {
+            self.items.push(bookmark);
+        }
+        data_dir.save(self)
+    }
+
+    pub fn remove(&mut self, index: usize, data_dir: &DataDir) -> Result<()> {
+        if index >= self.items.len() {
+            anyhow::bail!(
+                "Bookmark at index {index} does not exist ({} bookmarks)",
+                self.items.len()
+            );
+        }
+        let removed = self.items.remove(index);
+        log::debug!("Removed bookmark: {removed:?}");
+        if self.items.is_empty() { data_dir.delete::<Self>() } else { data_dir.save(self) }
+    }
+
+    pub fn send_items<W: Window>(&self, window: &W) -> Result<()> {
+        log::debug!("Send {} bookmarks to window", self.items.len());
+        window.send_message(MessageToWindow::Bookmarks { bookmarks: &self.items })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::TestWindow;
+
+    fn bookmark(
+        label: &str,
+        path: &str,
+        heading: Option<usize>,
+        fragment: Option<&str>,
+    ) -> Bookmark {
+        Bookmark {
+            label: label.into(),
+            path: path.into(),
+            heading,
+            fragment: fragment.map(String::from),
+        }
+    }
+
+    #[test]
+    fn add_remove_and_load_bookmarks() {
+        let dir = tempfile::tempdir().unwrap();
+        let data_dir = DataDir::new(dir.path());
+
+        let mut bookmarks = Bookmarks::load(&data_dir);
+        assert!(bookmarks.items.is_empty());
+        bookmarks.add(bookmark("foo", "foo.md", None, None), &data_dir).unwrap();
+        bookmarks.add(bookmark("bar", "foo.md", Some(1), Some("bar")), &data_dir).unwrap();
+        bookmarks.add(bookmark("baz", "baz.md", Some(0), None), &data_dir).unwrap();
+        // Only the label is updated
+        bookmarks.add(bookmark("FOO", "foo.md", None, None), &data_dir).unwrap();
+
+        let want = [
+            bookmark("FOO", "foo.md", None, None),
+            bookmark("bar", "foo.md", Some(1), Some("bar")),
+            bookmark("baz", "baz.md", Some(0), None),
+        ];
+        assert_eq!(bookmarks.items, want);
+        assert_eq!(Bookmarks::load(&data_dir).items, want);
+
+        bookmarks.remove(1, &data_dir).unwrap();
+        assert_eq!(bookmarks.get(1), Some(&want[2]));
+        assert_eq!(Bookmarks::load(&data_dir).items, [want[0].clone(), want[2].clone()]);
+        bookmarks.remove(2, &data_dir).unwrap_err();
+
+        bookmarks.remove(0, &data_dir).unwrap();
+        bookmarks.remove(0, &data_dir).unwrap();
+        assert!(!dir.path().join(Bookmarks::FILE).exists());
+        assert!(Bookmarks::load(&data_dir).items.is_empty());
+    }
+
+    #[test]
+    fn open_bookmark_position() {
+        let file = bookmark("a", "a.md", None, None).init_file();
+        assert!(matches!(file.scroll, InitScroll::Nop), "{file:?}");
+        let file = bookmark("a", "a.md", Some(2), None).init_file();
+        assert!(matches!(file.scroll, InitScroll::Heading(2)), "{file:?}");
+        let file = bookmark("a", "a.md", Some(2), Some("foo")).init_file();
+        assert!(matches!(&file.scroll, InitScroll::Fragment(f) if f == "foo"), "{file:?}");
+        assert_eq!(file.path, PathBuf::from("a.md"));
+    }
+
+    #[test]
+    fn send_bookmarks_to_window() {
+        let bookmarks = Bookmarks {
+            items: vec![
+                bookmark("foo", "foo.md", None, None),
+                bookmark("bar", "bar.md", Some(1), Some("x")),
+            ],
+        };
+        let window = TestWindow::default();
+        bookmarks.send_items(&window).unwrap();
+        let msg = window.messages.take().pop().unwrap();
+        let json: serde_json::Value = serde_json::from_str(&msg).unwrap();
+        insta::assert_json_snapshot!(json);
+    }
+}
@@ -1,9 +1,10 @@
+use crate::remote::{HeadingTarget, RemoteCall, ZoomDirection};
 use anyhow::{Error, Result};
 use once_cell::unsync::OnceCell; // For OnceCell::get_or_try_init
 use serde::{Deserialize, Serialize};
 use std::env;
-use std::ffi::OsString;
-use std::path::PathBuf;
+use std::ffi::{OsStr, OsString};
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -31,13 +32,22 @@ impl FromStr for ThemeOption {
 #[derive(Debug)]
 pub enum Parsed {
     Options(Options),
+    Export(ExportOptions),
+    PrintConfig(Options),
+    CheckConfig(Options),
+    ConfigSchema,
+    Remote(Options, RemoteCall),
+    Search(Options, String),
     Help(&'static str),
     Version(&'static str),
 }
 
-#[derive(Default, Debug, PartialEq, Serialize, Deserialize)]
+#[derive(Default, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
 pub struct PathArgs {
     pub init: Option<PathBuf>,
+    // Line number of the `init` file to scroll the preview to. This is sent from editors to follow the cursor
+    pub line: Option<usize>,
     pub additional_windows: Vec<PathBuf>,
     pub watched: Vec<PathBuf>,
 }
@@ -46,6 +56,30 @@ impl PathArgs {
     pub fn is_empty(&self) -> bool {
         self.init.is_none() && self.additional_windows.is_empty() && self.watched.is_empty()
     }
+
+    fn push(&mut self, arg: &OsStr, cwd: &OnceCell<PathBuf>) -> Result<()> {
+        let (path, line) = match split_line_number(arg) {
+            Some((path, line)) => (path, Some(line)),
+            None => (PathBuf::from(arg), None),
+        };
+        let exists = path.exists();
+
+        // `path.canonicalize()` returns an error when the path does not exist. Instead, create the absolute path
+        // using current directory as a parent
+        let path = if exists {
+            path.canonicalize()?
+        } else {
+            cwd.get_or_try_init(|| env::current_dir()?.canonicalize())?.join(path)
+        };
+
+        if self.init.is_some() || !exists || path.is_dir() {
+            self.watched.push(path);
+        } else {
+            self.init = Some(path);
+            self.line = line;
+        }
+        Ok(())
+    }
 }
 
 #[non_exhaustive]
@@ -60,6 +94,7 @@ pub struct Options {
     pub config_dir: Option<PathBuf>,
     pub data_dir: Option<PathBuf>,
     pub process_singleton: bool,
+    pub session: Option<String>,
 }
 
 impl Default for Options {
@@ -74,12 +109,177 @@ impl Default for Options {
             config_dir: None,
             data_dir: None,
             process_singleton: true,
+            session: None,
         }
     }
 }
 
+#[non_exhaustive]
+#[derive(Debug, PartialEq)]
+pub struct ExportOptions {
+    pub input: PathBuf,
+    pub output: Option<PathBuf>,
+    pub config_dir: Option<PathBuf>,
+    pub debug: bool,
+}
+
+impl ExportOptions {
+    pub fn new(input: impl Into<PathBuf>) -> Self {
+        Self { input: input.into(), output: None, config_dir: None, debug: false }
+    }
+
+    fn parse(mut parser: lexopt::Parser) -> Result<Parsed> {
+        use lexopt::prelude::*;
+
+        let mut input = None;
+        let mut output = None;
+        let mut config_dir = None;
+        let mut debug = false;
+        while let Some(arg) = parser.next()? {
+            match arg {
+                Short('h') | Long("help") => return Ok(Parsed::Help(Options::USAGE)),
+                Short('o') | Long("output") => output = Some(path_value(&mut parser)?),
+                Long("config-dir") => config_dir = Some(path_value(&mut parser)?),
+                Long("debug") => debug = true,
+                Value(path) if input.is_none() => input = Some(PathBuf::from(path)),
+                Value(path) => anyhow::bail!(
+                    "Only one file can be exported at once but got another file {path:?}",
+                ),
+                _ => return Err(arg.unexpected().into()),
+            }
+        }
+
+        let Some(input) = input else {
+            anyhow::bail!("File to export is missing. Usage: shiba export [OPTIONS...] FILE");
+        };
+
+        let opts = Self { input, output, config_dir, debug };
+        log::debug!("Parsed command line options for export: {opts:?}");
+        Ok(Parsed::Export(opts))
+    }
+}
+
+fn path_value(parser: &mut lexopt::Parser) -> Result<PathBuf> {
+    use lexopt::ValueExt as _;
+
+    let v = parser.value()?.string()?;
+    if v.starts_with('-') {
+        anyhow::bail!("Expected option value but got option name {v}");
+    }
+    Ok(v.into())
+}
+
+// Split `FILE:LINE` argument like `file.md:42` into the file path and the line number. The argument is not split
+// when a file with the argument name exists.
+fn split_line_number(arg: &OsStr) -> Option<(PathBuf, usize)> {
+    if Path::new(arg).exists() {
+        return None;
+    }
+    let (path, line) = arg.to_str()?.rsplit_once(':')?;
+    let line = line.parse().ok().filter(|&l| l > 0)?;
+    let path = PathBuf::from(path);
+    path.is_file().then_some((path, line))
+}
+
+// Parse `--remote COMMAND [ARGS...]`. All arguments following the command are for the command.
+fn parse_remote_command(
+    parser: &mut lexopt::Parser,
+    cwd: &OnceCell<PathBuf>,
+) -> Result<RemoteCall> {
+    use lexopt::prelude::*;
+
+    let command = parser.value()?.string()?;
+    let mut window = None;
+    let mut args = vec![];
+    while let Some(arg) = parser.next()? {
+        match arg {
+            Short('w') | Long("window") => window = Some(parser.value()?.parse()?),
+            Value(arg) => args.push(arg),
+            _ => return Err(arg.unexpected().into()),
+        }
+    }
+
+    let arg = |name: &str| -> Result<String> {
+        match args.as_slice() {
+            [arg] => arg.clone().into_string().map_err(|arg| {
+                anyhow::anyhow!("{name} argument of remote command is not UTF-8: {arg:?}")
+            }),
+            [] => anyhow::bail!("{name} argument is missing for remote command {command:?}"),
+            [_, arg, ..] => {
+                anyhow::bail!("Unexpected argument {arg:?} for remote command {command:?}")
+            }
+        }
+    };
+    let no_arg = || -> Result<()> {
+        match args.first() {
+            Some(arg) => {
+                anyhow::bail!("Unexpected argument {arg:?} for remote command {command:?}")
+            }
+            None => Ok(()),
+        }
+    };
+    let no_window = || -> Result<()> {
+        if window.is_some() {
+            anyhow::bail!("--window is not available for remote command {command:?}");
+        }
+        Ok(())
+    };
+
+    let call = match command.as_str() {
+        "open" => {
+            no_window()?;
+            let mut paths = PathArgs::default();
+            for path in &args {
+                paths.push(path, cwd)?;
+            }
+            RemoteCall::Open(paths)
+        }
+        "close-window" => {
+            no_arg()?;
+            RemoteCall::CloseWindow { window }
+        }
+        "reload" => {
+            no_arg()?;
+            RemoteCall::Reload { window }
+        }
+        "scroll-to-heading" => {
+            let heading = arg("HEADING")?;
+            let heading = match heading.parse() {
+                Ok(index) => HeadingTarget::Index(index),
+                Err(_) => HeadingTarget::Id(heading),
+            };
+            RemoteCall::ScrollToHeading { window, heading }
+        }
+        "search" => RemoteCall::Search { window, query: arg("QUERY")? },
+        "zoom" => {
+            let direction = match arg("DIRECTION")?.as_str() {
+                "in" => ZoomDirection::In,
+                "out" => ZoomDirection::Out,
+                dir => anyhow::bail!(r#"DIRECTION of zoom must be "in" or "out" but got {dir:?}"#),
+            };
+            RemoteCall::Zoom { window, direction }
+        }
+        "list-windows" => {
+            no_arg()?;
+            no_window()?;
+            RemoteCall::ListWindows {}
+        }
+        "current-path" => {
+            no_arg()?;
+            RemoteCall::CurrentPath { window }
+        }
+        _ => anyhow::bail!(
+            r#"Unknown remote command {command:?}. Available commands are "open", "close-window", "reload", "scroll-to-heading", "search", "zoom", "list-windows" and "current-path""#,
+        ),
+    };
+
+    Ok(call)
+}
+
 impl Options {
     const USAGE: &'static str = r#"Usage: shiba [OPTIONS...] [PATH...]
+       shiba export [EXPORT OPTIONS...] FILE
+       shiba [OPTIONS...] --remote COMMAND [ARGS...]
 
 Shiba is a markdown browser to preview documents with your favorite text editor, designed for
 simplicity, performance, and keyboard-friendly navigations.
@@ -91,13 +291,46 @@ Options:
         --no-watch              Disable to watch file changes
         --no-restore            Do not restore the previous window state
         --generate-config-file  Generate the default config file overwriting an existing file
+        --print-config          Print the effective config merged with the options as YAML and exit
+        --check-config          Validate the config file and exit. Exit non-zero when any problem is
+                                found
+        --print-config-schema   Print JSON Schema of the config file and exit
         --config-dir PATH       Change the config directory path
         --data-dir PATH         Change the application data directory path
         --no-proc-singleton     Don't reuse an existing application process
+        --session NAME          Save all windows and their documents as the named session on quit
+                                and restore them on launch. This is useful to switch the window
+                                layouts per project
+        --remote COMMAND        Control the running application process with the command and exit.
+                                All the following arguments are for the command
+        --search QUERY          Search Markdown files in PATH... (or the current directory) for the
+                                query, print the matches, and exit without opening a window
         --debug                 Enable debug features
     -h, --help                  Print this help
         --version               Print application version
 
+Export options:
+
+    -o, --output FILE           Write the exported HTML to the file instead of stdout
+        --config-dir PATH       Change the config directory path
+        --debug                 Enable debug logs
+
+Remote commands:
+
+    open PATH...                Open the paths in the running process as the same as PATH...
+                                arguments
+    close-window                Close the window
+    reload                      Reload the document in the window
+    scroll-to-heading HEADING   Scroll to the heading. HEADING is an index of the heading (0-based)
+                                or an ID of the heading element
+    search QUERY                Search the document for the query
+    zoom in|out                 Zoom in or out the window
+    list-windows                Print all windows with their numbers and paths as JSON
+    current-path                Print the path of the document in the window
+
+    -w, --window NUMBER         Window number printed by `list-windows` to be controlled. The focused
+                                window is controlled by default
+
 Arguments:
 
     PATH...                     Paths to the files and directories to watch. The first file is
@@ -105,6 +338,13 @@ Arguments:
                                 window when they are modified (e.g. edited by a text editor) next
                                 time. If you want to open them in multiple windows, use --open or -o
                                 option.
+                                The first file can be followed by a line number like `file.md:42`.
+                                The preview is scrolled to the block at the line.
+
+    FILE                        Markdown file to export as a standalone HTML file with `export`
+                                subcommand. No window is opened so it works without any display.
+                                `export` is a subcommand only when it is the first argument. To
+                                preview a file named `export`, specify it as a path like `./export`.
 
 Examples:
 
@@ -119,12 +359,28 @@ Examples:
         Tracks `file1.md`, `file2.md`, files in `dir1` directory, and files in `dir2` directory.
         `file1.md` file is opened in a preview window.
 
+    $ shiba file.md:42
+        Opens `file.md` file and scrolls the preview to the block at line 42. When Shiba is already
+        running, the existing window is scrolled. Editors can use this to sync the preview with the
+        cursor.
+
     $ shiba file1.md file2.md -o file3.md
         Opens file1.md and file3.md in windows and tracks changes of the three files.
 
     $ shiba
         Opens an empty window. You can open files from key shortcuts, menu items, file picker, etc.
 
+    $ shiba --remote search 'some text'
+        Searches the document in the focused window of the running Shiba for 'some text'. Scripts
+        can control Shiba with `--remote` or with JSON-RPC requests to its socket directly.
+
+    $ shiba --search 'some text' docs/
+        Prints the lines matching 'some text' in Markdown files under `docs` directory. The files are
+        ranked by relevance and the search index is saved in the data directory to be reused.
+
+    $ shiba export README.md -o README.html
+        Renders `README.md` and writes it to `README.html` with all the stylesheets inlined.
+
 Document:
 
     https://github.com/rhysd/Shiba/README.md
@@ -133,18 +389,22 @@ Document:
     pub fn parse(args: impl IntoIterator<Item = impl Into<OsString>>) -> Result<Parsed> {
         use lexopt::prelude::*;
 
-        fn path_value(parser: &mut lexopt::Parser) -> Result<PathBuf> {
-            let v = parser.value()?.string()?;
-            if v.starts_with('-') {
-                anyhow::bail!("Expected option value but got option name {v}");
-            }
-            Ok(v.into())
-        }
-
         let mut opts = Self::default();
+        let mut print_config = false;
+        let mut check_config = false;
+        let mut search = None;
 
         let cwd = OnceCell::new();
         let mut parser = lexopt::Parser::from_iter(args);
+
+        // `export` subcommand is only recognized as the first argument. A file named `export` can be previewed with
+        // `shiba ./export`.
+        let mut args = parser.raw_args()?;
+        if args.peek().is_some_and(|arg| arg == "export") {
+            args.next();
+            return ExportOptions::parse(parser);
+        }
+
         while let Some(arg) = parser.next()? {
             match arg {
                 Short('h') | Long("help") => return Ok(Parsed::Help(Self::USAGE)),
@@ -153,9 +413,24 @@ Document:
                 Long("no-watch") => opts.watch = false,
                 Long("no-restore") => opts.restore = false,
                 Long("generate-config-file") => opts.gen_config_file = true,
+                Long("print-config") => print_config = true,
+                Long("check-config") => check_config = true,
+                Long("print-config-schema") => return Ok(Parsed::ConfigSchema),
                 Long("config-dir") => opts.config_dir = Some(path_value(&mut parser)?),
                 Long("data-dir") => opts.data_dir = Some(path_value(&mut parser)?),
                 Long("no-proc-singleton") => opts.process_singleton = false,
+                Long("session") => {
+                    let name = parser.value()?.string()?;
+                    if name.is_empty()
+                        || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+                    {
+                        anyhow::bail!(
+                            "Session name must consist of alphanumeric characters, '-' and '_' but got {name:?}",
+                        );
+                    }
+                    opts.session = Some(name);
+                }
+                Long("search") => search = Some(parser.value()?.string()?),
                 Long("debug") => opts.debug = true,
                 Short('o') | Long("open") => {
                     let path = path_value(&mut parser)?;
@@ -172,30 +447,31 @@ Document:
                     };
                     opts.paths.additional_windows.push(path)
                 }
-                Value(path) => {
-                    let path = PathBuf::from(path);
-                    let exists = path.exists();
-
-                    // `path.canonicalize()` returns an error when the path does not exist. Instead, create the absolute path
-                    // using current directory as a parent
-                    let path = if exists {
-                        path.canonicalize()?
-                    } else {
-                        cwd.get_or_try_init(|| env::current_dir()?.canonicalize())?.join(path)
-                    };
-
-                    if opts.paths.init.is_some() || !exists || path.is_dir() {
-                        opts.paths.watched.push(path);
-                    } else {
-                        opts.paths.init = Some(path);
-                    }
+                Value(path) => opts.paths.push(&path, &cwd)?,
+                Long("remote") => {
+                    let call = parse_remote_command(&mut parser, &cwd)?;
+                    log::debug!("Parsed command line options for remote: {opts:?} {call:?}");
+                    return Ok(Parsed::Remote(opts, call));
                 }
                 _ => return Err(arg.unexpected().into()),
             }
         }
 
         log::debug!("Parsed command line options: {opts:?}");
-        Ok(Parsed::Options(opts))
+        if let Some(query) = search {
+            if print_config || check_config {
+                anyhow::bail!("--search cannot be specified with --print-config or --check-config");
+            }
+            return Ok(Parsed::Search(opts, query));
+        }
+        match (print_config, check_config) {
+            (true, true) => {
+                anyhow::bail!("--print-config and --check-config cannot be specified at once")
+            }
+            (true, false) => Ok(Parsed::PrintConfig(opts)),
+            (false, true) => Ok(Parsed::CheckConfig(opts)),
+            (false, false) => Ok(Parsed::Options(opts)),
+        }
     }
 }
 
@@ -261,6 +537,43 @@ mod tests {
                     ..Default::default()
                 },
             ),
+            (
+                &["README.md:42"][..],
+                Options {
+                    paths: PathArgs {
+                        init: Some(cur.join("README.md")),
+                        line: Some(42),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+            ),
+            (
+                &["src", "README.md:1", "LICENSE:3"][..],
+                Options {
+                    paths: PathArgs {
+                        init: Some(cur.join("README.md")),
+                        line: Some(1),
+                        watched: vec![cur.join("src"), cur.join("LICENSE")],
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+            ),
+            (
+                &["README.md:0", "README.md:x", "file-not-existing.md:1"][..],
+                Options {
+                    paths: PathArgs {
+                        watched: vec![
+                            cur.join("README.md:0"),
+                            cur.join("README.md:x"),
+                            cur.join("file-not-existing.md:1"),
+                        ],
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+            ),
             (
                 &["--no-watch"][..],
                 Options { watch: false, ..Default::default() },
@@ -327,6 +640,7 @@ mod tests {
                             cur.join("LICENSE"),
                         ],
                         watched: vec![cur.join("CHANGELOG.md")],
+                        ..Default::default()
                     },
                     ..Default::default()
                 },
@@ -345,6 +659,10 @@ mod tests {
                     ..Default::default()
                 },
             ),
+            (
+                &["--session", "my-project_1"][..],
+                Options { session: Some("my-project_1".into()), ..Default::default() },
+            ),
             (
                 &["--no-proc-singleton"][..],
                 Options { process_singleton: false, ..Default::default() },
@@ -387,7 +705,9 @@ mod tests {
 
     #[test]
     fn parse_missing_option_arg() {
-        for arg in ["--config-dir", "--data-dir", "--theme", "--open", "-o"] {
+        for arg in
+            ["--config-dir", "--data-dir", "--theme", "--open", "-o", "--search", "--session"]
+        {
             let err = Options::parse(cmdline(&["--debug", arg])).unwrap_err();
             assert_eq!(
                 format!("{err}"),
@@ -419,6 +739,15 @@ mod tests {
         );
     }
 
+    #[test]
+    fn parse_invalid_session_name() {
+        for name in ["", "../foo", "foo.bar", "foo bar"] {
+            let err = Options::parse(cmdline(&["--session", name])).unwrap_err();
+            let msg = format!("{err}");
+            assert!(msg.contains("Session name must consist of"), "unexpected message {msg:?}");
+        }
+    }
+
     #[test]
     fn parse_invalid_open_arg() {
         for (arg, expected) in [
@@ -435,6 +764,181 @@ mod tests {
         }
     }
 
+    #[test]
+    fn parse_search_option() {
+        let cur = env::current_dir().unwrap().canonicalize().unwrap();
+        match Options::parse(cmdline(&["--search", "foo bar", "src", "--debug"])).unwrap() {
+            Parsed::Search(opts, query) => {
+                assert_eq!(query, "foo bar");
+                assert_eq!(opts.paths.watched, [cur.join("src")]);
+                assert!(opts.debug);
+            }
+            p => panic!("unexpected parse result: {p:?}"),
+        }
+
+        let err = Options::parse(cmdline(&["--search", "foo", "--print-config"])).unwrap_err();
+        let msg = format!("{err}");
+        assert!(msg.contains("--search cannot be specified with"), "unexpected message {msg:?}");
+    }
+
+    #[test]
+    fn parse_remote_commands() {
+        let cur = env::current_dir().unwrap().canonicalize().unwrap();
+
+        let tests = [
+            (
+                &["open", "README.md:3", "src"][..],
+                RemoteCall::Open(PathArgs {
+                    init: Some(cur.join("README.md")),
+                    line: Some(3),
+                    watched: vec![cur.join("src")],
+                    ..Default::default()
+                }),
+            ),
+            (&["close-window"][..], RemoteCall::CloseWindow { window: None }),
+            (&["reload", "--window", "2"][..], RemoteCall::Reload { window: Some(2) }),
+            (
+                &["scroll-to-heading", "3", "-w", "1"][..],
+                RemoteCall::ScrollToHeading { window: Some(1), heading: HeadingTarget::Index(3) },
+            ),
+            (
+                &["scroll-to-heading", "installation"][..],
+                RemoteCall::ScrollToHeading {
+                    window: None,
+                    heading: HeadingTarget::Id("installation".into()),
+                },
+            ),
+            (
+                &["search", "foo bar"][..],
+                RemoteCall::Search { window: None, query: "foo bar".into() },
+            ),
+            (
+                &["zoom", "out"][..],
+                RemoteCall::Zoom { window: None, direction: ZoomDirection::Out },
+            ),
+            (&["list-windows"][..], RemoteCall::ListWindows {}),
+            (&["current-path", "--window=3"][..], RemoteCall::CurrentPath { window: Some(3) }),
+        ];
+
+        for (args, want) in tests {
+            let mut cmd = vec!["--debug", "--remote"];
+            cmd.extend(args);
+            match Options::parse(cmdline(&cmd)).unwrap() {
+                Parsed::Remote(opts, call) => {
+                    assert!(opts.debug, "args={args:?}");
+                    assert_eq!(call, want, "args={args:?}");
+                }
+                p => panic!("unexpected parse result for {args:?}: {p:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn parse_invalid_remote_commands() {
+        for (args, expected) in [
+            (&[][..], "missing argument for option '--remote'"),
+            (&["foo"][..], "Unknown remote command \"foo\""),
+            (&["search"][..], "QUERY argument is missing"),
+            (&["search", "a", "b"][..], "Unexpected argument \"b\""),
+            (&["reload", "foo"][..], "Unexpected argument \"foo\""),
+            (&["zoom", "up"][..], "DIRECTION of zoom must be"),
+            (&["list-windows", "-w", "1"][..], "--window is not available"),
+            (&["reload", "--window", "x"][..], "invalid digit"),
+            (&["reload", "--debug"][..], "invalid option '--debug'"),
+        ] {
+            let mut cmd = vec!["--remote"];
+            cmd.extend(args);
+            let err = Options::parse(cmdline(&cmd)).unwrap_err();
+            let msg = format!("{err:#}");
+            assert!(msg.contains(expected), "args={args:?} expected={expected:?} msg={msg:?}");
+        }
+    }
+
+    #[test]
+    fn parse_config_mode_args() {
+        let dir = PathBuf::from("some-dir");
+        match Options::parse(cmdline(&["--print-config", "--config-dir", "some-dir"])).unwrap() {
+            Parsed::PrintConfig(opts) => assert_eq!(opts.config_dir.as_ref(), Some(&dir)),
+            p => panic!("unexpected parse result: {p:?}"),
+        }
+        match Options::parse(cmdline(&["--theme", "dark", "--check-config"])).unwrap() {
+            Parsed::CheckConfig(opts) => assert_eq!(opts.theme, Some(ThemeOption::Dark)),
+            p => panic!("unexpected parse result: {p:?}"),
+        }
+        assert!(matches!(
+            Options::parse(cmdline(&["--print-config-schema"])).unwrap(),
+            Parsed::ConfigSchema,
+        ));
+        let err = Options::parse(cmdline(&["--print-config", "--check-config"])).unwrap_err();
+        let msg = format!("{err}");
+        assert!(msg.contains("cannot be specified at once"), "message={msg:?}");
+    }
+
+    #[test]
+    fn parse_export_args_ok() {
+        #[rustfmt::skip]
+        let tests = [
+            (
+                &["export", "README.md"][..],
+                ExportOptions::new("README.md"),
+            ),
+            (
+                &["export", "-o", "out.html", "README.md"][..],
+                ExportOptions {
+                    output: Some("out.html".into()),
+                    ..ExportOptions::new("README.md")
+                },
+            ),
+            (
+                &["export", "README.md", "--output", "out.html", "--config-dir", "some-dir", "--debug"][..],
+                ExportOptions {
+                    output: Some("out.html".into()),
+                    config_dir: Some("some-dir".into()),
+                    debug: true,
+                    ..ExportOptions::new("README.md")
+                },
+            ),
+        ];
+
+        for (args, want) in tests {
+            match Options::parse(cmdline(args)).unwrap() {
+                Parsed::Export(opts) => assert_eq!(opts, want, "args={args:?}"),
+                p => panic!("unexpected parse result: {p:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn parse_export_args_error() {
+        for (args, expected) in [
+            (&["export"][..], "File to export is missing"),
+            (&["export", "a.md", "b.md"][..], "Only one file can be exported at once"),
+            (&["export", "--output"][..], "missing argument for option '--output'"),
+            (&["export", "-o", "--debug"][..], "Expected option value but got option name"),
+            (&["export", "--no-watch", "a.md"][..], "invalid option '--no-watch'"),
+        ] {
+            let err = Options::parse(cmdline(args)).unwrap_err();
+            let msg = format!("{err}");
+            assert!(msg.contains(expected), "args={args:?} message={msg:?}");
+        }
+    }
+
+    #[test]
+    fn export_is_not_subcommand_after_options() {
+        match Options::parse(cmdline(&["--debug", "export"])).unwrap() {
+            Parsed::Options(opts) => assert!(opts.paths.init.is_none() && opts.debug, "{opts:?}"),
+            p => panic!("unexpected parse result: {p:?}"),
+        }
+    }
+
+    #[test]
+    fn export_file_as_path() {
+        match Options::parse(cmdline(&["./export"])).unwrap() {
+            Parsed::Options(opts) => assert_eq!(opts.paths.watched.len(), 1, "{opts:?}"),
+            p => panic!("unexpected parse result: {p:?}"),
+        }
+    }
+
     #[test]
     fn path_args_is_empty() {
         assert!(PathArgs::default().is_empty());
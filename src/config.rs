@@ -1,9 +1,11 @@
 use crate::cli::{Options, ThemeOption};
+use crate::config_schema::config_schema;
 use crate::persistent::DataDir;
 use anyhow::{Context, Result};
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::borrow::Cow;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::ffi::OsString;
 use std::fs;
 use std::mem;
 use std::num::NonZeroU32;
@@ -42,10 +44,14 @@ pub enum KeyAction {
     Search,
     NextSearch,
     PrevSearch,
+    SearchAll,
     ScrollNextSection,
     ScrollPrevSection,
     Outline,
+    ToggleSplitView,
     History,
+    Bookmark,
+    Bookmarks,
     Help,
     ZoomIn,
     ZoomOut,
@@ -58,10 +64,67 @@ pub enum KeyAction {
     DuplicateWindow,
     CloseWindow,
     CloseAllOtherWindows,
+    NewTab,
+    NextTab,
+    PreviousTab,
+    CloseTab,
     EditConfig,
     Quit,
 }
 
+impl KeyAction {
+    // All actions listed in the JSON schema of the config file
+    pub const ALL: &[Self] = {
+        use KeyAction::*;
+        &[
+            GoForward,
+            GoBack,
+            GoTop,
+            Reload,
+            OpenFile,
+            OpenFileInNewWindow,
+            OpenDir,
+            ScrollDown,
+            ScrollUp,
+            ScrollLeft,
+            ScrollRight,
+            ScrollPageDown,
+            ScrollPageUp,
+            ScrollTop,
+            ScrollBottom,
+            Search,
+            NextSearch,
+            PrevSearch,
+            SearchAll,
+            ScrollNextSection,
+            ScrollPrevSection,
+            Outline,
+            ToggleSplitView,
+            History,
+            Bookmark,
+            Bookmarks,
+            Help,
+            ZoomIn,
+            ZoomOut,
+            ShowMenu,
+            ToggleMenuBar,
+            ToggleAlwaysOnTop,
+            MinimizeWindow,
+            MaximizeWindow,
+            NewWindow,
+            DuplicateWindow,
+            CloseWindow,
+            CloseAllOtherWindows,
+            NewTab,
+            NextTab,
+            PreviousTab,
+            CloseTab,
+            EditConfig,
+            Quit,
+        ]
+    };
+}
+
 #[rustfmt::skip]
 const DEFAULT_KEY_MAPPINGS: &[(&str, KeyAction)] = {
     use KeyAction::*;
@@ -94,21 +157,39 @@ const DEFAULT_KEY_MAPPINGS: &[(&str, KeyAction)] = {
         ("ctrl+h",          History),
         ("ctrl+r",          Reload),
         ("o",               Outline),
+        ("v",               ToggleSplitView),
         ("s",               Search),
+        ("S",               SearchAll),
+        ("m",               Bookmark),
+        ("M",               Bookmarks),
         ("plus",            ZoomIn),
         ("-",               ZoomOut),
         ("ctrl+m",          MaximizeWindow),
         ("ctrl+n",          DuplicateWindow),
         ("ctrl+w",          CloseWindow),
+        ("ctrl+t",          NewTab),
+        ("ctrl+tab",        NextTab),
+        ("ctrl+shift+tab",  PreviousTab),
+        ("ctrl+shift+w",    CloseTab),
         ("mod+q",           Quit),
         ("?",               Help),
     ]
 };
 
 const DEFAULT_CONFIG_FILE_NAME: &str = "config.yml";
+const CONFIG_SCHEMA_FILE_NAME: &str = "config.schema.json";
 const CONFIG_FILE_NAMES: [&str; 2] = [DEFAULT_CONFIG_FILE_NAME, "config.yaml"];
 
-#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+// Serialize the map in the order of keys to make the output of --print-config stable
+fn serialize_sorted<S, V>(map: &HashMap<String, V>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+    V: Serialize,
+{
+    map.iter().collect::<BTreeMap<_, _>>().serialize(serializer)
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
 pub struct FileExtensions(Arc<[String]>); // Use `Arc` because this config is referred from watcher and dialog respectively
 
 impl Default for FileExtensions {
@@ -147,7 +228,7 @@ impl FileExtensions {
 }
 
 #[non_exhaustive]
-#[derive(Deserialize, Debug, PartialEq, Eq)]
+#[derive(Deserialize, Serialize, Debug, PartialEq, Eq)]
 #[serde(deny_unknown_fields)]
 pub struct Watch {
     pub file_extensions: FileExtensions,
@@ -173,7 +254,10 @@ pub enum SearchMatcher {
     SmartCase,
     CaseSensitive,
     CaseInsensitive,
+    WholeWord,
+    Fuzzy,
     CaseSensitiveRegex,
+    CaseInsensitiveRegex,
 }
 
 #[non_exhaustive]
@@ -253,7 +337,7 @@ impl Default for WindowSize {
 }
 
 #[non_exhaustive]
-#[derive(Deserialize, Debug, PartialEq, Eq)]
+#[derive(Deserialize, Serialize, Debug, PartialEq, Eq)]
 #[serde(deny_unknown_fields)]
 pub struct Window {
     pub restore: bool,
@@ -283,7 +367,7 @@ impl Window {
     }
 }
 
-#[derive(Deserialize, Debug, PartialEq, Eq)]
+#[derive(Deserialize, Serialize, Debug, PartialEq, Eq)]
 #[serde(deny_unknown_fields)]
 pub struct PreviewHighlight {
     pub dark: String,
@@ -297,17 +381,24 @@ impl Default for PreviewHighlight {
 }
 
 #[non_exhaustive]
-#[derive(Deserialize, Debug, PartialEq, Eq)]
+#[derive(Deserialize, Serialize, Debug, PartialEq, Eq)]
 #[serde(deny_unknown_fields)]
 pub struct Preview {
     pub highlight: PreviewHighlight,
     pub css: Option<PathBuf>,
     pub history_size: usize,
+    #[serde(default, serialize_with = "serialize_sorted")]
+    pub diagrams: HashMap<String, Vec<String>>,
 }
 
 impl Default for Preview {
     fn default() -> Self {
-        Self { highlight: PreviewHighlight::default(), css: None, history_size: 100 }
+        Self {
+            highlight: PreviewHighlight::default(),
+            css: None,
+            history_size: 100,
+            diagrams: HashMap::new(),
+        }
     }
 }
 
@@ -347,7 +438,7 @@ fn resolve_path<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Pat
     Ok(Some(path))
 }
 
-#[derive(Default, Deserialize, Debug, PartialEq, Eq)]
+#[derive(Default, Deserialize, Serialize, Debug, PartialEq, Eq)]
 #[serde(deny_unknown_fields)]
 pub struct Dialog {
     #[serde(deserialize_with = "resolve_path")]
@@ -369,7 +460,46 @@ impl Dialog {
     }
 }
 
-#[derive(Deserialize, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+#[derive(Clone, Copy, Deserialize, Serialize, Debug, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct Markdown {
+    pub tables: bool,
+    pub footnotes: bool,
+    pub strikethrough: bool,
+    pub task_lists: bool,
+    pub math: bool,
+    pub alerts: bool,
+    pub definition_lists: bool,
+    pub superscript: bool,
+    pub subscript: bool,
+    pub front_matter: bool,
+    pub smart_punctuation: bool,
+    pub heading_attributes: bool,
+    pub wikilinks: bool,
+}
+
+impl Default for Markdown {
+    fn default() -> Self {
+        Self {
+            tables: true,
+            footnotes: true,
+            strikethrough: true,
+            task_lists: true,
+            math: true,
+            alerts: true,
+            definition_lists: true,
+            superscript: true,
+            subscript: true,
+            front_matter: true,
+            smart_punctuation: false,
+            heading_attributes: false,
+            wikilinks: false,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, PartialEq, Eq)]
 #[serde(deny_unknown_fields)]
 pub struct Process {
     pub singleton: bool,
@@ -381,17 +511,48 @@ impl Default for Process {
     }
 }
 
+#[derive(Default, Deserialize, Serialize, Debug, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct Editor {
+    pub command: Vec<String>,
+}
+
+impl Editor {
+    // Arguments to open the file at the line. `{file}` and `{line}` in the command are replaced with them
+    pub fn args(&self, file: &Path, line: usize) -> Vec<OsString> {
+        let file = file.as_os_str();
+        let line = line.to_string();
+        self.command
+            .iter()
+            .map(|arg| {
+                let Some((before, after)) = arg.split_once("{file}") else {
+                    return arg.replace("{line}", &line).into();
+                };
+                let mut arg = OsString::from(before.replace("{line}", &line));
+                arg.push(file);
+                arg.push(after.replace("{line}", &line));
+                arg
+            })
+            .collect()
+    }
+}
+
 #[non_exhaustive]
-#[derive(Deserialize, Debug, PartialEq, Eq)]
+#[derive(Deserialize, Serialize, Debug, PartialEq, Eq)]
 #[serde(deny_unknown_fields)]
 pub struct UserConfig {
     pub watch: Watch,
+    #[serde(serialize_with = "serialize_sorted")]
     pub keymaps: HashMap<String, KeyAction>,
     pub search: Search,
     pub window: Window,
     pub preview: Preview,
+    #[serde(default)]
+    pub markdown: Markdown,
     pub dialog: Dialog,
     pub process: Process,
+    #[serde(default)]
+    pub editor: Editor,
 }
 
 impl Default for UserConfig {
@@ -402,8 +563,10 @@ impl Default for UserConfig {
             search: Search::default(),
             window: Window::default(),
             preview: Preview::default(),
+            markdown: Markdown::default(),
             dialog: Dialog::default(),
             process: Process::default(),
+            editor: Editor::default(),
         }
     }
 }
@@ -428,10 +591,17 @@ impl UserConfig {
 
                 match fs::read(file) {
                     Ok(bytes) => {
-                        let config = serde_yaml::from_slice(&bytes)
-                            .with_context(|| {
-                                format!("Could not parse a configuration file at {file:?}. To reset config file, try --generate-config-file")
-                            })?;
+                        let config = serde_yaml::from_slice(&bytes).map_err(|err| {
+                            // Point the error location like 'path/to/config.yml:3:5' so that text editors can jump to it
+                            let location = err
+                                .location()
+                                .map(|loc| format!(":{}:{}", loc.line(), loc.column()))
+                                .unwrap_or_default();
+                            anyhow::Error::new(err).context(format!(
+                                "Could not parse a configuration file at {}{location}. To reset config file, try --generate-config-file",
+                                file.display(),
+                            ))
+                        })?;
                         log::debug!("Loaded the user configuration from {file:?}");
                         return Ok(config);
                     }
@@ -458,16 +628,82 @@ impl UserConfig {
             .with_context(|| format!("Could not generate config file at {:?}", &config_path))?;
 
         log::info!("Generated the default config file at {:?}", config_path);
+
+        // The default config file refers this schema file so that YAML language servers can validate it
+        let schema_path = config_dir.join(CONFIG_SCHEMA_FILE_NAME);
+        fs::write(&schema_path, config_schema_json()).with_context(|| {
+            format!("Could not generate config schema file at {:?}", &schema_path)
+        })?;
+        log::debug!("Generated the config schema file at {:?}", schema_path);
+
         Ok(config_path)
     }
 }
 
+pub fn config_schema_json() -> String {
+    let mut json = serde_json::to_string_pretty(&config_schema()).unwrap();
+    json.push('\n');
+    json
+}
+
+// Command line options which override the user config. They are kept to apply them again on reloading the config.
+#[derive(Default, Clone, Copy, Debug)]
+struct Overrides {
+    theme: Option<ThemeOption>,
+    no_restore: bool,
+    no_process_singleton: bool,
+}
+
+impl Overrides {
+    fn apply(self, user_config: &mut UserConfig) {
+        if let Some(theme) = self.theme {
+            // CLI option has higher priority
+            user_config.window.theme = match theme {
+                ThemeOption::System => WindowTheme::System,
+                ThemeOption::Dark => WindowTheme::Dark,
+                ThemeOption::Light => WindowTheme::Light,
+            };
+        }
+
+        if self.no_restore {
+            user_config.window.restore = false;
+        }
+
+        if self.no_process_singleton {
+            user_config.process.singleton = false;
+        }
+    }
+}
+
+// Absolute paths of the config files which may be loaded. When the config file is a symbolic link, its target is
+// also included because modifying the target file doesn't notify any change of the link.
+fn config_file_paths(config_dir: Option<&Path>) -> Vec<PathBuf> {
+    let Some(dir) = config_dir else {
+        return vec![];
+    };
+    let dir = dir.canonicalize().unwrap_or_else(|_| dir.to_path_buf());
+    let mut paths = vec![];
+    for file in CONFIG_FILE_NAMES {
+        let path = dir.join(file);
+        if let Ok(target) = path.canonicalize()
+            && target != path
+        {
+            paths.push(target);
+        }
+        paths.push(path);
+    }
+    paths
+}
+
 #[derive(Default, Debug)]
 pub struct Config {
     user_config: UserConfig,
     path: Option<PathBuf>,
     data_dir: DataDir,
     debug: bool,
+    session: Option<String>,
+    overrides: Overrides,
+    config_files: Vec<PathBuf>,
 }
 
 impl Config {
@@ -477,11 +713,15 @@ impl Config {
         path: impl Into<PathBuf>,
         data_dir: impl Into<PathBuf>,
     ) -> Self {
+        let path = path.into();
         Self {
             user_config: user,
-            path: Some(path.into()),
+            config_files: config_file_paths(Some(&path)),
+            path: Some(path),
             data_dir: DataDir::new(data_dir),
             debug: false,
+            session: None,
+            overrides: Overrides::default(),
         }
     }
 
@@ -508,22 +748,12 @@ impl Config {
             UserConfig::default()
         };
 
-        if let Some(theme) = options.theme {
-            // CLI option has higher priority
-            user_config.window.theme = match theme {
-                ThemeOption::System => WindowTheme::System,
-                ThemeOption::Dark => WindowTheme::Dark,
-                ThemeOption::Light => WindowTheme::Light,
-            };
-        }
-
-        if !options.restore {
-            user_config.window.restore = false;
-        }
-
-        if !options.process_singleton {
-            user_config.process.singleton = false;
-        }
+        let overrides = Overrides {
+            theme: options.theme,
+            no_restore: !options.restore,
+            no_process_singleton: !options.process_singleton,
+        };
+        overrides.apply(&mut user_config);
 
         let data_dir = if let Some(dir) = mem::take(&mut options.data_dir) {
             DataDir::new(dir)
@@ -531,27 +761,74 @@ impl Config {
             DataDir::default()
         };
 
-        Ok(Self { user_config, path: config_dir, data_dir, debug: options.debug })
+        let config_files = config_file_paths(config_dir.as_deref());
+        Ok(Self {
+            user_config,
+            path: config_dir,
+            data_dir,
+            debug: options.debug,
+            session: options.session,
+            overrides,
+            config_files,
+        })
+    }
+
+    // Load the config file again with the same command line options. The current config is not modified so that it
+    // can be used continuously when the config file is broken.
+    pub fn reload(&self) -> Result<Self> {
+        let mut user_config =
+            if let Some(dir) = &self.path { UserConfig::load(dir)? } else { UserConfig::default() };
+        self.overrides.apply(&mut user_config);
+
+        Ok(Self {
+            user_config,
+            path: self.path.clone(),
+            data_dir: self.data_dir.clone(),
+            debug: self.debug,
+            session: self.session.clone(),
+            overrides: self.overrides,
+            config_files: config_file_paths(self.path.as_deref()),
+        })
+    }
+
+    pub fn config_files(&self) -> &[PathBuf] {
+        &self.config_files
+    }
+
+    pub fn is_config_file(&self, path: &Path) -> bool {
+        self.config_files.iter().any(|p| p == path)
     }
 
     pub fn config_dir(&self) -> Option<&Path> {
         self.path.as_deref()
     }
 
+    // Path to the existing config file. `None` means that the default config is used.
+    pub fn existing_config_file(&self) -> Option<PathBuf> {
+        let dir = self.config_dir()?;
+        CONFIG_FILE_NAMES.iter().find_map(|file| {
+            let path = dir.join(file);
+            path.is_file().then_some(path)
+        })
+    }
+
     pub fn config_file(&self) -> Result<PathBuf> {
         let Some(dir) = self.config_dir() else {
             anyhow::bail!("Configuration directory cannot be determined. Try --config-dir");
         };
-        if let Some(path) = CONFIG_FILE_NAMES.iter().find_map(|file| {
-            let path = dir.join(file);
-            path.is_file().then_some(path)
-        }) {
+        if let Some(path) = self.existing_config_file() {
             Ok(path)
         } else {
             UserConfig::generate_default_config(dir)
         }
     }
 
+    // The effective configuration merged with the command line options in YAML format
+    pub fn to_yaml(&self) -> Result<String> {
+        serde_yaml::to_string(&self.user_config)
+            .context("Could not serialize the configuration into YAML")
+    }
+
     pub fn data_dir(&self) -> &DataDir {
         &self.data_dir
     }
@@ -560,6 +837,11 @@ impl Config {
         self.debug
     }
 
+    // Name of the session specified by `--session`. `None` means the default session
+    pub fn session(&self) -> Option<&str> {
+        self.session.as_deref()
+    }
+
     // Note: `Config` instance is immutable. `Config` does not expose the internal `UserConfig` instance so that it
     // cannot be modified by user once `Config` instance is created. This is important not to unexpectedly modify the
     // configuration while the application is running.
@@ -586,6 +868,10 @@ impl Config {
         &self.user_config.preview
     }
 
+    pub fn markdown(&self) -> &Markdown {
+        &self.user_config.markdown
+    }
+
     pub fn dialog(&self) -> &Dialog {
         &self.user_config.dialog
     }
@@ -593,6 +879,10 @@ impl Config {
     pub fn process(&self) -> &Process {
         &self.user_config.process
     }
+
+    pub fn editor(&self) -> &Editor {
+        &self.user_config.editor
+    }
 }
 
 #[cfg(test)]
@@ -646,6 +936,75 @@ mod tests {
         assert_eq!(cfg, UserConfig::default());
     }
 
+    #[test]
+    fn serialize_config_to_yaml() {
+        let cfg = Config::default();
+        let yaml = cfg.to_yaml().unwrap();
+        let parsed: UserConfig = serde_yaml::from_str(&yaml).unwrap();
+        assert_eq!(parsed, cfg.user_config);
+        assert_eq!(yaml, cfg.to_yaml().unwrap(), "output is not stable");
+
+        let cfg: UserConfig = serde_yaml::from_str(CONFIG_OK).unwrap();
+        let yaml = serde_yaml::to_string(&cfg).unwrap();
+        let parsed: UserConfig = serde_yaml::from_str(&yaml).unwrap();
+        assert_eq!(parsed, cfg);
+    }
+
+    #[test]
+    fn omit_optional_sections() {
+        // Sections added after the first release can be omitted so that existing config files are still valid
+        let yaml = serde_yaml::to_string(&UserConfig::default()).unwrap();
+        let mut value: serde_yaml::Value = serde_yaml::from_str(&yaml).unwrap();
+        let root = value.as_mapping_mut().unwrap();
+        root.remove("markdown").unwrap();
+        root.remove("editor").unwrap();
+        let preview = root.get_mut("preview").unwrap().as_mapping_mut().unwrap();
+        preview.remove("diagrams").unwrap();
+        let yaml = serde_yaml::to_string(&value).unwrap();
+        let cfg: UserConfig = serde_yaml::from_str(&yaml).unwrap();
+        assert_eq!(cfg, UserConfig::default());
+    }
+
+    #[test]
+    fn generate_config_and_schema_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = UserConfig::generate_default_config(dir.path()).unwrap();
+        assert_eq!(fs::read_to_string(path).unwrap(), UserConfig::DEFAULT_CONFIG_YAML);
+
+        let schema = fs::read_to_string(dir.path().join(CONFIG_SCHEMA_FILE_NAME)).unwrap();
+        let schema: serde_json::Value = serde_json::from_str(&schema).unwrap();
+        assert_eq!(schema, config_schema());
+
+        // Default config file refers the schema file next to it
+        assert!(
+            UserConfig::DEFAULT_CONFIG_YAML
+                .starts_with("# yaml-language-server: $schema=config.schema.json\n"),
+        );
+    }
+
+    #[test]
+    fn editor_command_args() {
+        let editor = Editor {
+            command: ["code", "-g", "{file}:{line}", "--line={line}", "{file}"]
+                .into_iter()
+                .map(String::from)
+                .collect(),
+        };
+        let args = editor.args(Path::new("/path/to/file.md"), 42);
+        let want = ["code", "-g", "/path/to/file.md:42", "--line=42", "/path/to/file.md"];
+        assert_eq!(args, want.map(OsString::from));
+    }
+
+    #[test]
+    fn all_key_actions() {
+        for (i, action) in KeyAction::ALL.iter().enumerate() {
+            assert!(!KeyAction::ALL[i + 1..].contains(action), "duplicate action {action:?}");
+        }
+        for (_, action) in DEFAULT_KEY_MAPPINGS {
+            assert!(KeyAction::ALL.contains(action), "{action:?}");
+        }
+    }
+
     #[test]
     fn default_key_mappings() {
         let mut m = HashMap::new();
@@ -754,6 +1113,39 @@ mod tests {
         assert!(!cfg.process().singleton);
     }
 
+    #[test]
+    fn reload_config_file() {
+        let _lock = ENV_LOCK.read().unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        let opts = Options {
+            restore: false, // Config is overwritten by this option even after reload
+            config_dir: Some(dir.path().to_path_buf()),
+            data_dir: Some(dir.path().to_path_buf()),
+            ..Default::default()
+        };
+        let cfg = Config::load(opts).unwrap();
+        assert_eq!(cfg.user_config.watch, Watch::default());
+
+        let file = dir.path().join("config.yml");
+        assert!(cfg.is_config_file(&dir.path().canonicalize().unwrap().join("config.yml")));
+        assert!(!cfg.is_config_file(&dir.path().join("other.yml")));
+
+        fs::write(&file, CONFIG_OK).unwrap();
+        let reloaded = cfg.reload().unwrap();
+        let mut expected: UserConfig = serde_yaml::from_str(CONFIG_OK).unwrap();
+        expected.window.restore = false;
+        assert_eq!(reloaded.user_config, expected);
+        assert_eq!(reloaded.data_dir().path(), cfg.data_dir().path());
+        assert_eq!(reloaded.config_dir(), cfg.config_dir());
+
+        // Broken config file is reported as error
+        fs::write(&file, "watch: [").unwrap();
+        let err = reloaded.reload().unwrap_err();
+        let msg = format!("{err}");
+        assert!(msg.contains("Could not parse a configuration file"), "message={msg:?}");
+    }
+
     #[test]
     fn unknown_field_in_config() {
         let _lock = ENV_LOCK.read().unwrap();
@@ -763,6 +1155,8 @@ mod tests {
         let err = Config::load(opts).unwrap_err();
         let msg = format!("{}", err.source().unwrap());
         assert!(msg.contains("unknown field `unknown_field`"), "message={msg:?}");
+        let msg = format!("{err}");
+        assert!(msg.contains("config.yml:55:1."), "message={msg:?}");
     }
 
     #[test]
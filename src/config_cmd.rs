@@ -0,0 +1,140 @@
+use crate::assets::{is_hljs_theme, user_css_path};
+use crate::cli::Options;
+use crate::config::{Config, config_schema_json};
+use anyhow::Result;
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+
+// Semantic problems in the config which don't prevent the application from starting. The application only logs them
+// at runtime so they are easily overlooked.
+fn config_warnings(config: &Config) -> Vec<String> {
+    let mut warnings = vec![];
+
+    let highlight = &config.preview().highlight;
+    for (mode, name) in [("light", &highlight.light), ("dark", &highlight.dark)] {
+        if !is_hljs_theme(name) {
+            warnings.push(format!(
+                "preview.highlight.{mode}: Unknown highlight.js theme {name:?}. See https://highlightjs.org/static/demo/ to know the list",
+            ));
+        }
+    }
+
+    if let Some(css) = config.preview().css_path() {
+        match user_css_path(config) {
+            Some(path) if !path.is_file() => {
+                warnings.push(format!("preview.css: CSS file {path:?} does not exist"));
+            }
+            Some(_) => {}
+            None => warnings.push(format!(
+                "preview.css: CSS file {css:?} cannot be loaded because config directory cannot be determined",
+            )),
+        }
+    }
+
+    let diagrams: BTreeMap<_, _> = config.preview().diagrams.iter().collect();
+    for (lang, command) in diagrams {
+        if command.is_empty() {
+            warnings.push(format!("preview.diagrams.{lang}: Command to render diagram is empty"));
+        }
+    }
+
+    let extensions = config.watch().file_extensions.as_slice();
+    if extensions.is_empty() {
+        warnings.push("watch.file_extensions: No file extension is specified".to_string());
+    }
+    for ext in extensions {
+        if ext.starts_with('.') {
+            warnings.push(format!(
+                "watch.file_extensions: Extension {ext:?} should not start with \".\"",
+            ));
+        }
+    }
+
+    let editor = &config.editor().command;
+    if !editor.is_empty() && !editor.iter().any(|arg| arg.contains("{file}")) {
+        warnings.push(
+            "editor.command: Command does not contain \"{file}\" placeholder for file path"
+                .to_string(),
+        );
+    }
+
+    warnings
+}
+
+pub fn print_config(options: Options) -> Result<()> {
+    let config = Config::load(options)?;
+    let yaml = config.to_yaml()?;
+    let mut stdout = io::stdout().lock();
+    stdout.write_all(yaml.as_bytes())?;
+    stdout.flush()?;
+    Ok(())
+}
+
+pub fn print_config_schema() -> Result<()> {
+    let mut stdout = io::stdout().lock();
+    stdout.write_all(config_schema_json().as_bytes())?;
+    stdout.flush()?;
+    Ok(())
+}
+
+pub fn check_config(options: Options) -> Result<()> {
+    // Syntax errors and unknown fields are reported as errors with their locations by `Config::load`
+    let config = Config::load(options)?;
+    let name = match config.existing_config_file() {
+        Some(path) => path.display().to_string(),
+        None => "default config".to_string(),
+    };
+
+    let warnings = config_warnings(&config);
+    if !warnings.is_empty() {
+        let mut stderr = io::stderr().lock();
+        for warning in &warnings {
+            writeln!(stderr, "{name}: warning: {warning}")?;
+        }
+        anyhow::bail!("{} problem(s) found in {name}", warnings.len());
+    }
+
+    writeln!(io::stdout().lock(), "{name}: OK")?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::FileExtensions;
+    use crate::config::UserConfig;
+    use std::fs;
+
+    #[test]
+    fn no_warning_for_default_config() {
+        assert_eq!(config_warnings(&Config::default()), Vec::<String>::new());
+    }
+
+    #[test]
+    fn warnings_for_semantic_problems() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut user = UserConfig::default();
+        user.preview.highlight.light = "Unknown Theme".into();
+        user.preview.css = Some("missing.css".into());
+        user.preview.diagrams.insert("dot".into(), vec![]);
+        user.watch.file_extensions = FileExtensions::new([".md", "mkd"]);
+        user.editor.command = vec!["code".into(), "-g".into()];
+        let config = Config::new(user, dir.path(), dir.path());
+
+        let warnings = config_warnings(&config);
+        assert_eq!(warnings.len(), 5, "{warnings:#?}");
+        for (warning, want) in warnings.iter().zip([
+            "preview.highlight.light: Unknown highlight.js theme \"Unknown Theme\"",
+            "preview.css: CSS file",
+            "preview.diagrams.dot: Command to render diagram is empty",
+            "watch.file_extensions: Extension \".md\" should not start with \".\"",
+            "editor.command: Command does not contain \"{file}\" placeholder",
+        ]) {
+            assert!(warning.starts_with(want), "{warning:?} vs {want:?}");
+        }
+
+        // The warning is resolved when the CSS file exists
+        fs::write(dir.path().join("missing.css"), "").unwrap();
+        assert_eq!(config_warnings(&config).len(), 4, "{:#?}", config_warnings(&config));
+    }
+}
@@ -0,0 +1,262 @@
+use crate::assets::hljs_theme_names;
+use crate::config::{KeyAction, SearchMatcher, WindowTheme};
+use serde::Serialize;
+use serde_json::{Value, json};
+
+// JSON Schema of config.yml. YAML language servers use it for completion and validation while editing the config
+// file. The schema is written by hand because almost all fields of `UserConfig` are required and there are only a
+// few enums. It must be updated when a new config field is added to `UserConfig`.
+// https://json-schema.org/draft-07/json-schema-release-notes
+
+fn names<T: Serialize>(values: &[T]) -> Vec<Value> {
+    values.iter().map(|v| serde_json::to_value(v).expect("enum is serialized as string")).collect()
+}
+
+fn object(description: &str, properties: Value) -> Value {
+    let required: Vec<_> = properties.as_object().unwrap().keys().cloned().collect();
+    json!({
+        "description": description,
+        "type": "object",
+        "properties": properties,
+        "required": required,
+        "additionalProperties": false,
+    })
+}
+
+// Fields which were added later have default values so that existing config files are still valid
+fn optional(mut object: Value, fields: &[&str]) -> Value {
+    let required = object["required"].as_array_mut().unwrap();
+    required.retain(|name| !name.as_str().is_some_and(|name| fields.contains(&name)));
+    object
+}
+
+fn boolean(description: &str) -> Value {
+    json!({ "description": description, "type": "boolean" })
+}
+
+fn window_length(description: &str) -> Value {
+    json!({
+        "description": description,
+        "anyOf": [
+            { "type": "integer", "minimum": 1 },
+            { "enum": ["max"] },
+            { "type": "string", "pattern": "^[Mm][Aa][Xx]$" },
+        ],
+    })
+}
+
+pub fn config_schema() -> Value {
+    let watch = object(
+        "Configuration related to monitoring file changes",
+        json!({
+            "file_extensions": {
+                "description": "File extensions recognized as Markdown files when watching directories",
+                "type": "array",
+                "items": { "type": "string" },
+            },
+            "debounce_throttle": {
+                "description": "Throttle for debouncing file change events in milliseconds",
+                "type": "integer",
+                "minimum": 0,
+            },
+        }),
+    );
+
+    let keymaps = json!({
+        "description": "Key mappings from key binds to actions. Any keymap sequences supported by mousetrap are available for keys",
+        "type": "object",
+        "additionalProperties": { "enum": names(KeyAction::ALL) },
+    });
+
+    use SearchMatcher::*;
+    let search = object(
+        "Configuration related to text search",
+        json!({
+            "matcher": {
+                "description": "How search query matches to text",
+                "enum": names(&[
+                    SmartCase,
+                    CaseSensitive,
+                    CaseInsensitive,
+                    WholeWord,
+                    Fuzzy,
+                    CaseSensitiveRegex,
+                    CaseInsensitiveRegex,
+                ]),
+            },
+        }),
+    );
+
+    let window = object(
+        "Configuration related to application window",
+        json!({
+            "restore": boolean("Remember the state of all windows and their documents and restore them when opening Shiba at next time"),
+            "theme": {
+                "description": "Theme to apply to the application window",
+                "enum": names(&[WindowTheme::System, WindowTheme::Dark, WindowTheme::Light]),
+            },
+            "always_on_top": boolean("Application window is always on top of other windows"),
+            "default_size": object(
+                "Default window size in logical pixels or \"max\" to maximize the window in the direction",
+                json!({
+                    "width": window_length("Default window width"),
+                    "height": window_length("Default window height"),
+                }),
+            ),
+            "menu_bar": boolean("Show a menu bar at the top of application window on Linux and Windows"),
+            "vibrant": boolean("Enable platform-specific vibrant effects on the window"),
+        }),
+    );
+
+    let themes = hljs_theme_names();
+    let preview = object(
+        "Configuration related to Markdown preview content",
+        json!({
+            "highlight": object(
+                "Highlight theme for code blocks. See https://highlightjs.org/static/demo/ to know all themes",
+                json!({
+                    "light": { "description": "Theme in light mode", "enum": themes },
+                    "dark": { "description": "Theme in dark mode", "enum": themes },
+                }),
+            ),
+            "css": {
+                "description": "CSS file to override styles of Markdown preview. The path is relative to the configuration directory",
+                "type": ["string", "null"],
+            },
+            "history_size": {
+                "description": "Maximum number of files to remember as history",
+                "type": "integer",
+                "minimum": 0,
+            },
+            "diagrams": {
+                "description": "Commands to render code blocks as SVG diagrams. Keys are languages of code blocks",
+                "type": "object",
+                "additionalProperties": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "minItems": 1,
+                },
+            },
+        }),
+    );
+
+    let preview = optional(preview, &["diagrams"]);
+
+    let markdown = object(
+        "Configuration related to Markdown syntax. Each option enables or disables a Markdown extension",
+        json!({
+            "tables": boolean("Tables in GitHub Flavored Markdown"),
+            "footnotes": boolean("Footnotes like `[^1]` and their definitions"),
+            "strikethrough": boolean("Strikethrough text like `~~text~~`"),
+            "task_lists": boolean("Task list items like `- [x] done`"),
+            "math": boolean("Math expressions like `$a + b$` and `$$a + b$$`"),
+            "alerts": boolean("Alerts like `> [!NOTE]`"),
+            "definition_lists": boolean("Definition lists like `term` followed by `: definition` line"),
+            "superscript": boolean("Superscript like `^text^`"),
+            "subscript": boolean("Subscript like `~text~`"),
+            "front_matter": boolean("YAML or TOML front matter at the beginning of the document"),
+            "smart_punctuation": boolean("Replace quotes, dashes and ellipses with typographic ones"),
+            "heading_attributes": boolean("Attributes of headings like `# Heading {#custom-id .class}`"),
+            "wikilinks": boolean("Wiki-style links like `[[Page]]`"),
+        }),
+    );
+
+    let dialog = object(
+        "Configuration related to dialog to open a file or watch a directory",
+        json!({
+            "default_dir": {
+                "description": "Default directory path of the dialog. `~` is expanded to the home directory",
+                "type": ["string", "null"],
+            },
+        }),
+    );
+
+    let process = object(
+        "Configuration related to application process",
+        json!({
+            "singleton": boolean("Forward the file paths to the existing application process instead of launching a new one"),
+        }),
+    );
+
+    let editor = object(
+        "Configuration related to the text editor to open Markdown sources",
+        json!({
+            "command": {
+                "description": "Command to open the source of the block double-clicked in the preview. `{file}` and `{line}` in the arguments are replaced with the file path and the line number",
+                "type": "array",
+                "items": { "type": "string" },
+            },
+        }),
+    );
+
+    let schema = object(
+        "Configuration file of Shiba",
+        json!({
+            "watch": watch,
+            "keymaps": keymaps,
+            "search": search,
+            "window": window,
+            "preview": preview,
+            "markdown": markdown,
+            "dialog": dialog,
+            "process": process,
+            "editor": editor,
+        }),
+    );
+    let mut schema = optional(schema, &["markdown", "editor"]);
+    let root = schema.as_object_mut().unwrap();
+    root.insert("$schema".into(), "http://json-schema.org/draft-07/schema#".into());
+    root.insert("title".into(), "Shiba config.yml".into());
+    schema
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::UserConfig;
+
+    // Check the schema is in sync with the actual config structure
+    fn assert_fields(schema: &Value, config: &Value, path: &str) {
+        let Some(props) = schema.get("properties").and_then(Value::as_object) else {
+            return;
+        };
+        let fields = config.as_object().unwrap();
+        let mut want: Vec<_> = fields.keys().collect();
+        let mut have: Vec<_> = props.keys().collect();
+        want.sort();
+        have.sort();
+        assert_eq!(have, want, "properties of {path:?} mismatch");
+        for (name, value) in fields {
+            assert_fields(&props[name], value, &format!("{path}.{name}"));
+        }
+    }
+
+    #[test]
+    fn schema_matches_config_fields() {
+        let config = serde_json::to_value(UserConfig::default()).unwrap();
+        assert_fields(&config_schema(), &config, "");
+    }
+
+    #[test]
+    fn enum_values_in_schema() {
+        let schema = config_schema();
+        let props = &schema["properties"];
+
+        let actions = props["keymaps"]["additionalProperties"]["enum"].as_array().unwrap();
+        for (_, action) in UserConfig::default().keymaps {
+            assert!(actions.contains(&serde_json::to_value(action).unwrap()), "{action:?}");
+        }
+        for (i, action) in actions.iter().enumerate() {
+            assert!(!actions[i + 1..].contains(action), "duplicate action {action:?}");
+        }
+
+        let themes = props["preview"]["properties"]["highlight"]["properties"]["light"]["enum"]
+            .as_array()
+            .unwrap();
+        assert!(themes.contains(&json!("GitHub")), "{themes:?}");
+        assert!(themes.contains(&json!("GitHub Dark")), "{themes:?}");
+
+        let width = &props["window"]["properties"]["default_size"]["properties"]["width"];
+        assert!(width["anyOf"].as_array().unwrap().contains(&json!({ "enum": ["max"] })));
+    }
+}
@@ -0,0 +1,81 @@
+use crate::markdown::DiagramJob;
+use crate::renderer::{Event, RendererHandle};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{Receiver, Sender, channel};
+use std::thread::spawn;
+
+// Render diagrams in code blocks on a worker thread since the commands such as `dot` may take long time. The main
+// thread renders code blocks as-is until the diagrams are rendered. When they are ready, `Event::DiagramsRendered` is
+// sent with the path of the document and the preview is rendered again with the diagrams.
+
+fn run_worker<H: RendererHandle>(rx: Receiver<(PathBuf, Vec<DiagramJob>)>, handle: H) {
+    while let Ok((path, jobs)) = rx.recv() {
+        log::debug!("Rendering {} diagrams in {path:?} in background", jobs.len());
+        // Note: All jobs must run. Do not use `Iterator::any` which stops at the first `true`.
+        let rendered = jobs.iter().filter(|job| job.run()).count();
+        if rendered > 0 {
+            handle.send(Event::DiagramsRendered(path));
+        }
+    }
+    log::debug!("Diagram worker thread has stopped");
+}
+
+pub struct DiagramWorker {
+    tx: Sender<(PathBuf, Vec<DiagramJob>)>,
+}
+
+impl DiagramWorker {
+    pub fn new<H: RendererHandle>(handle: H) -> Self {
+        let (tx, rx) = channel();
+        spawn(move || run_worker(rx, handle));
+        Self { tx }
+    }
+
+    // Jobs are queued while parsing the document to send it to the window
+    pub fn render(&self, path: &Path, jobs: Vec<DiagramJob>) {
+        if jobs.is_empty() {
+            return;
+        }
+        if self.tx.send((path.to_path_buf(), jobs)).is_err() {
+            log::error!("Could not send diagrams to the worker since it has stopped");
+        }
+    }
+}
+
+#[cfg(all(test, not(target_os = "windows")))]
+mod tests {
+    use super::*;
+    use crate::config::Markdown;
+    use crate::markdown::{MarkdownContent, MarkdownParser};
+    use crate::persistent::DataDir;
+    use crate::renderer::{RawMessageWriter as _, Renderer as _, Request};
+    use crate::test::TestRenderer;
+    use std::collections::HashMap;
+    use std::io;
+    use std::time::Duration;
+
+    #[test]
+    fn render_diagrams_in_background() {
+        let tmp = tempfile::tempdir().unwrap();
+        let commands = HashMap::from([("svg".to_string(), vec!["cat".to_string()])]);
+        let source = "```svg\n<svg>worker</svg>\n```\n".to_string();
+        let content = MarkdownContent::new(source, None, &Markdown::default())
+            .with_diagrams(commands, &DataDir::new(tmp.path()));
+        let _: () = MarkdownParser::new(&content, None, ()).write_to(io::sink()).unwrap();
+
+        let renderer = TestRenderer::default();
+        let worker = DiagramWorker::new(renderer.create_handle());
+        worker.render(Path::new("doc.md"), content.diagram_jobs());
+        match renderer.recv_timeout(Duration::from_secs(5)) {
+            Request::Emit(Event::DiagramsRendered(path)) => assert_eq!(path, Path::new("doc.md")),
+            req => panic!("unexpected request: {req:?}"),
+        }
+
+        // The diagram is rendered and no job is queued anymore
+        let mut out = vec![];
+        let _: () = MarkdownParser::new(&content, None, ()).write_to(&mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.contains(r#""diagram""#), "{out}");
+        assert!(content.diagram_jobs().is_empty());
+    }
+}
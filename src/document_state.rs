@@ -0,0 +1,160 @@
+use crate::config::SearchMatcher;
+use crate::persistent::{DataDir, PersistentData};
+use anyhow::Result;
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+// Last state of the preview of each document. It is restored when the document is opened again. The number of
+// documents is limited to the history size and the least recently updated one is removed first.
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+pub struct SearchState {
+    pub query: String,
+    pub matcher: SearchMatcher,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, PartialEq, Eq, Debug)]
+pub struct DocumentState {
+    pub block: Option<usize>, // Index of the top-level block at the top of the viewport
+    pub search: Option<SearchState>,
+}
+
+#[derive(Serialize, Deserialize, Default, Debug)]
+pub struct DocumentStates {
+    #[serde(skip)]
+    max_items: usize,
+    states: IndexMap<PathBuf, DocumentState>,
+}
+
+impl PersistentData for DocumentStates {
+    const FILE: &str = "documents.json";
+}
+
+impl DocumentStates {
+    pub fn new(max_items: usize) -> Self {
+        Self { max_items, states: IndexMap::new() }
+    }
+
+    pub fn load(data_dir: &DataDir, max_items: usize) -> Self {
+        if max_items == 0 {
+            return Self::new(max_items);
+        }
+        let Some(mut loaded) = data_dir.load::<Self>() else {
+            return Self::new(max_items);
+        };
+        let len = loaded.states.len();
+        if len > max_items {
+            loaded.states.drain(..len - max_items);
+        }
+        log::debug!("Loaded states of {} documents from persistent data", loaded.states.len());
+        Self { max_items, ..loaded }
+    }
+
+    pub fn save(&self, data_dir: &DataDir) -> Result<()> {
+        if self.max_items == 0 {
+            return Ok(());
+        }
+        log::debug!("Saving states of {} documents as persistent data", self.states.len());
+        data_dir.save(self)
+    }
+
+    pub fn clear(&mut self, data_dir: &DataDir) -> Result<()> {
+        self.states.clear();
+        data_dir.delete::<Self>()
+    }
+
+    pub fn get(&self, path: &Path) -> Option<&DocumentState> {
+        self.states.get(path)
+    }
+
+    fn update(&mut self, path: &Path, f: impl FnOnce(&mut DocumentState)) {
+        if self.max_items == 0 {
+            return;
+        }
+        let mut state = self.states.shift_remove(path).unwrap_or_default();
+        f(&mut state);
+        if state == DocumentState::default() {
+            return;
+        }
+        if self.states.len() == self.max_items {
+            self.states.shift_remove_index(0);
+        }
+        self.states.insert(path.to_path_buf(), state);
+    }
+
+    pub fn set_block(&mut self, path: &Path, block: usize) {
+        log::debug!("Scroll position of {path:?} was updated to block {block}");
+        self.update(path, |state| state.block = Some(block));
+    }
+
+    pub fn set_search(&mut self, path: &Path, query: &str, matcher: SearchMatcher) {
+        self.update(path, |state| {
+            state.search =
+                (!query.is_empty()).then(|| SearchState { query: query.to_string(), matcher });
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn paths(states: &DocumentStates) -> Vec<&str> {
+        states.states.keys().map(|p| p.to_str().unwrap()).collect()
+    }
+
+    #[test]
+    fn update_states() {
+        let mut states = DocumentStates::new(2);
+        states.set_block(Path::new("a.md"), 3);
+        states.set_search(Path::new("a.md"), "foo", SearchMatcher::Fuzzy);
+        let search = SearchState { query: "foo".into(), matcher: SearchMatcher::Fuzzy };
+        let want = DocumentState { block: Some(3), search: Some(search) };
+        assert_eq!(states.get(Path::new("a.md")), Some(&want));
+
+        states.set_search(Path::new("a.md"), "", SearchMatcher::Fuzzy);
+        let want = DocumentState { block: Some(3), search: None };
+        assert_eq!(states.get(Path::new("a.md")), Some(&want));
+
+        // Empty state is not stored
+        states.set_search(Path::new("b.md"), "", SearchMatcher::SmartCase);
+        assert_eq!(states.get(Path::new("b.md")), None);
+
+        states.set_block(Path::new("b.md"), 1);
+        states.set_block(Path::new("a.md"), 4);
+        assert_eq!(paths(&states), ["b.md", "a.md"]);
+        states.set_block(Path::new("c.md"), 0);
+        assert_eq!(paths(&states), ["a.md", "c.md"]);
+        assert_eq!(states.get(Path::new("b.md")), None);
+    }
+
+    #[test]
+    fn disabled_with_zero_size() {
+        let mut states = DocumentStates::new(0);
+        states.set_block(Path::new("a.md"), 3);
+        assert_eq!(states.get(Path::new("a.md")), None);
+    }
+
+    #[test]
+    fn save_and_load_states() {
+        let tmp = tempfile::tempdir().unwrap();
+        let data_dir = DataDir::new(tmp.path());
+        let mut states = DocumentStates::new(3);
+        for (i, path) in ["a.md", "b.md", "c.md"].into_iter().enumerate() {
+            states.set_block(Path::new(path), i);
+        }
+        states.save(&data_dir).unwrap();
+
+        let loaded = DocumentStates::load(&data_dir, 3);
+        assert_eq!(loaded.states, states.states);
+        let loaded = DocumentStates::load(&data_dir, 2);
+        assert_eq!(paths(&loaded), ["b.md", "c.md"]);
+        assert_eq!(loaded.max_items, 2);
+
+        let mut loaded = DocumentStates::load(&data_dir, 0);
+        assert_eq!(loaded.get(Path::new("a.md")), None);
+        loaded.clear(&data_dir).unwrap();
+        assert_eq!(DocumentStates::load(&data_dir, 3).states.len(), 0);
+    }
+}
@@ -0,0 +1,212 @@
+use crate::assets::Assets;
+use crate::cli::{ExportOptions, Options};
+use crate::config::Config;
+use crate::markdown::{MarkdownContent, MarkdownHtml, Metadata, escape_html};
+use crate::renderer::RawMessageWriter;
+use anyhow::{Context as _, Result};
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Component, Path, PathBuf};
+
+// Layout of the document body suggested by github-markdown-css
+// https://github.com/sindresorhus/github-markdown-css#usage
+const BODY_CSS: &str = ".markdown-body {
+  box-sizing: border-box;
+  min-width: 200px;
+  max-width: 980px;
+  margin: 0 auto;
+  padding: 45px;
+}
+@media (max-width: 767px) {
+  .markdown-body {
+    padding: 15px;
+  }
+}
+";
+
+fn write_style(out: &mut impl Write, css: &[u8]) -> io::Result<()> {
+    out.write_all(b"<style>\n")?;
+    out.write_all(css)?;
+    out.write_all(b"\n</style>\n")
+}
+
+fn write_document(
+    mut out: impl Write,
+    html: MarkdownHtml<'_>,
+    title: &str,
+    assets: &Assets,
+) -> io::Result<()> {
+    out.write_all(b"<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n")?;
+    out.write_all(
+        b"<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n<title>",
+    )?;
+    escape_html(&mut out, title)?;
+    out.write_all(b"</title>\n")?;
+    write_style(&mut out, assets.markdown_css())?;
+    write_style(&mut out, assets.hljs_css())?;
+    write_style(&mut out, BODY_CSS.as_bytes())?;
+    out.write_all(b"</head>\n<body>\n<article class=\"markdown-body\">\n")?;
+    html.write_to(&mut out)?;
+    out.write_all(b"</article>\n</body>\n</html>\n")
+}
+
+fn parent_dir(path: &Path) -> &Path {
+    match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    }
+}
+
+// Relative path from `from` directory to `to` directory. `None` when they don't share any root like different drives
+// on Windows.
+fn relative_dir(from: &Path, to: &Path) -> Option<PathBuf> {
+    let (from, to) = (from.canonicalize().ok()?, to.canonicalize().ok()?);
+    let common = from.components().zip(to.components()).take_while(|(f, t)| f == t).count();
+    if common == 0 {
+        return None;
+    }
+    let mut rel: PathBuf = from.components().skip(common).map(|_| Component::ParentDir).collect();
+    rel.extend(to.components().skip(common));
+    Some(rel)
+}
+
+// Relative links in the document are resolved against the directory of the output file so that they still point to
+// the same files. Local images are embedded so that the HTML can be published without them.
+fn export_html(input: &Path, output: Option<&Path>, config: &Config) -> Result<Vec<u8>> {
+    let source = fs::read_to_string(input)
+        .with_context(|| format!("Could not read Markdown file to export: {input:?}"))?;
+    let content = MarkdownContent::new(source, input.parent(), config.markdown())
+        .with_wikilink_dirs(vec![], config.watch().file_extensions.clone())
+        .with_diagrams(config.preview().diagrams.clone(), config.data_dir());
+    let metadata = content.metadata();
+    let title = match metadata.as_ref().and_then(Metadata::title) {
+        Some(title) => title.into(),
+        None => input.file_name().unwrap_or(input.as_os_str()).to_string_lossy(),
+    };
+
+    let input_dir = parent_dir(input);
+    let mut html = MarkdownHtml::new(&content).with_embedded_images(input_dir);
+    if let Some(output) = output {
+        match relative_dir(parent_dir(output), input_dir) {
+            Some(base) => html = html.with_link_base(&base),
+            None => log::warn!(
+                "Relative links are not resolved since {output:?} is not reachable from {input:?}"
+            ),
+        }
+    }
+
+    let mut buf = vec![];
+    write_document(&mut buf, html, &title, &Assets::new(config))?;
+    Ok(buf)
+}
+
+pub fn export(options: ExportOptions) -> Result<()> {
+    let ExportOptions { input, output, config_dir, debug } = options;
+    let config = Config::load(Options { config_dir, debug, ..Default::default() })?;
+    let html = export_html(&input, output.as_deref(), &config)?;
+
+    if let Some(output) = output {
+        fs::write(&output, html)
+            .with_context(|| format!("Could not write the exported HTML to {output:?}"))?;
+        log::debug!("Exported {input:?} to {output:?}");
+    } else {
+        let mut stdout = io::stdout().lock();
+        stdout.write_all(&html)?;
+        stdout.flush()?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::UserConfig;
+    use tempfile::tempdir;
+
+    #[test]
+    fn export_standalone_html() {
+        let dir = tempdir().unwrap();
+        let input = dir.path().join("test <doc>.md");
+        fs::write(&input, "# Hello\n\n```rust\nfn main() {}\n```\n").unwrap();
+
+        let html = export_html(&input, None, &Config::default()).unwrap();
+        let html = String::from_utf8(html).unwrap();
+
+        assert!(html.starts_with("<!DOCTYPE html>"), "{html:?}");
+        assert!(html.contains("<title>test &lt;doc&gt;.md</title>"), "{html:?}");
+        assert!(html.contains(r#"<h1 id="hello">Hello</h1>"#), "{html:?}");
+        assert!(html.contains(r#"<code class="language-rust">"#), "{html:?}");
+        assert_eq!(html.matches("<style>").count(), 3, "{html:?}");
+        assert!(!html.contains("<script"), "{html:?}");
+    }
+
+    #[test]
+    fn export_with_user_css() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("test.css"), "/* user CSS */").unwrap();
+        let input = dir.path().join("test.md");
+        fs::write(&input, "hello").unwrap();
+
+        let mut user = UserConfig::default();
+        user.preview.css = Some("test.css".into());
+        let config = Config::new(user, dir.path(), dir.path());
+        let html = export_html(&input, None, &config).unwrap();
+        let html = String::from_utf8(html).unwrap();
+
+        assert!(html.contains("<style>\n/* user CSS */\n</style>"), "{html:?}");
+    }
+
+    #[test]
+    fn export_front_matter_title() {
+        let dir = tempdir().unwrap();
+        let input = dir.path().join("test.md");
+        fs::write(&input, "---\ntitle: Hello & Bye\n---\n\nbody\n").unwrap();
+
+        let html = export_html(&input, None, &Config::default()).unwrap();
+        let html = String::from_utf8(html).unwrap();
+
+        assert!(html.contains("<title>Hello &amp; Bye</title>"), "{html:?}");
+        assert!(!html.contains("title:"), "{html:?}");
+    }
+
+    #[test]
+    fn export_relative_links_and_images() {
+        let dir = tempdir().unwrap();
+        let (docs, out) = (dir.path().join("docs"), dir.path().join("out"));
+        fs::create_dir(&docs).unwrap();
+        fs::create_dir(&out).unwrap();
+        fs::write(docs.join("logo.png"), b"\x89PNG").unwrap();
+        let input = docs.join("test.md");
+        let source = "[other](other.md)\n\n![logo](logo.png)\n\n![missing](missing.png)\n\n<a href=\"sub/a.md\">raw</a>\n";
+        fs::write(&input, source).unwrap();
+
+        let html = export_html(&input, Some(&out.join("test.html")), &Config::default()).unwrap();
+        let html = String::from_utf8(html).unwrap();
+        assert!(html.contains(r#"<a href="../docs/other.md">other</a>"#), "{html:?}");
+        assert!(
+            html.contains(r#"<img src="data:image/png;base64,iVBORw==" alt="logo">"#),
+            "{html:?}"
+        );
+        assert!(html.contains(r#"<img src="../docs/missing.png" alt="missing">"#), "{html:?}");
+        assert!(html.contains(r#"href="../docs/sub/a.md""#), "{html:?}");
+        assert!(!html.contains(&*dir.path().to_string_lossy()), "{html:?}");
+
+        // Relative links are kept as-is when the output is in the same directory or is stdout
+        for output in [Some(docs.join("test.html")), None] {
+            let html = export_html(&input, output.as_deref(), &Config::default()).unwrap();
+            let html = String::from_utf8(html).unwrap();
+            assert!(html.contains(r#"<a href="other.md">other</a>"#), "{html:?}");
+            assert!(html.contains(r#"href="sub/a.md""#), "{html:?}");
+            assert!(html.contains("data:image/png;base64,"), "{html:?}");
+        }
+    }
+
+    #[test]
+    fn export_missing_file() {
+        let config = Config::default();
+        let err = export_html(Path::new("this-file-does-not-exist.md"), None, &config).unwrap_err();
+        let msg = format!("{err}");
+        assert!(msg.contains("Could not read Markdown file to export"), "{msg:?}");
+    }
+}
@@ -0,0 +1,286 @@
+use crate::persistent::{DataDir, PersistentData};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::Metadata;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+// Inverted index of the texts in Markdown documents. It is persisted in the data directory so that the documents don't
+// need to be parsed again on the next launch. Each entry is invalidated when the modified time or the size of the file
+// is changed.
+
+const INDEX_VERSION: u32 = 1;
+// Parameters of Okapi BM25 to rank documents
+const BM25_K1: f64 = 1.2;
+const BM25_B: f64 = 0.75;
+
+// Split the text into lowercase words. Note that languages which don't separate words with spaces such as Japanese are
+// indexed per sentence. This is OK since query terms are matched to the indexed terms as substrings.
+fn tokenize(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric()).filter(|w| !w.is_empty()).map(str::to_lowercase)
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct FileStamp {
+    modified: Option<SystemTime>, // `None` when the platform does not support the modified time
+    size: u64,
+}
+
+impl FileStamp {
+    pub fn new(md: &Metadata) -> Self {
+        Self { modified: md.modified().ok(), size: md.len() }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct IndexedDoc {
+    path: PathBuf,
+    stamp: FileStamp,
+    len: u32, // Number of terms in the document
+}
+
+#[derive(Serialize, Deserialize, Default, Debug)]
+pub struct FullTextIndex {
+    version: u32,
+    docs: Vec<Option<IndexedDoc>>, // `None` is a removed document. The slot is reused by the next document
+    postings: HashMap<String, Vec<(u32, u32)>>, // Term -> pairs of document ID and term frequency
+    #[serde(skip)]
+    ids: HashMap<PathBuf, u32>,
+    #[serde(skip)]
+    dirty: bool,
+}
+
+impl PersistentData for FullTextIndex {
+    const FILE: &str = "search_index.json.zst";
+    const COMPRESSED: bool = true;
+}
+
+impl FullTextIndex {
+    pub fn load(data_dir: &DataDir) -> Self {
+        let Some(mut index) = data_dir.load::<Self>() else {
+            return Self { version: INDEX_VERSION, ..Self::default() };
+        };
+        if index.version != INDEX_VERSION {
+            log::debug!("Discard search index with old version {}", index.version);
+            return Self { version: INDEX_VERSION, dirty: true, ..Self::default() };
+        }
+        for (id, doc) in index.docs.iter().enumerate() {
+            if let Some(doc) = doc {
+                index.ids.insert(doc.path.clone(), id as u32);
+            }
+        }
+        log::debug!("Loaded search index with {} documents", index.ids.len());
+        index
+    }
+
+    pub fn save(&mut self, data_dir: &DataDir) -> Result<()> {
+        if !self.dirty {
+            return Ok(());
+        }
+        data_dir.save(self)?;
+        self.dirty = false;
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    pub fn paths(&self) -> impl Iterator<Item = &Path> {
+        self.ids.keys().map(PathBuf::as_path)
+    }
+
+    pub fn contains(&self, path: &Path) -> bool {
+        self.ids.contains_key(path)
+    }
+
+    pub fn is_fresh(&self, path: &Path, stamp: FileStamp) -> bool {
+        let Some(doc) = self.ids.get(path).and_then(|&id| self.docs[id as usize].as_ref()) else {
+            return false;
+        };
+        stamp.modified.is_some() && doc.stamp == stamp
+    }
+
+    pub fn insert(&mut self, path: PathBuf, text: &str, stamp: FileStamp) {
+        self.remove(&path);
+
+        let mut freqs = HashMap::<String, u32>::new();
+        let mut len = 0;
+        for term in tokenize(text) {
+            *freqs.entry(term).or_default() += 1;
+            len += 1;
+        }
+
+        let doc = IndexedDoc { path: path.clone(), stamp, len };
+        let id = if let Some(id) = self.docs.iter().position(Option::is_none) {
+            self.docs[id] = Some(doc);
+            id as u32
+        } else {
+            self.docs.push(Some(doc));
+            self.docs.len() as u32 - 1
+        };
+        for (term, freq) in freqs {
+            self.postings.entry(term).or_default().push((id, freq));
+        }
+        self.ids.insert(path, id);
+        self.dirty = true;
+    }
+
+    pub fn remove(&mut self, path: &Path) -> bool {
+        let Some(id) = self.ids.remove(path) else {
+            return false;
+        };
+        self.docs[id as usize] = None;
+        self.postings.retain(|_, docs| {
+            docs.retain(|&(doc, _)| doc != id);
+            !docs.is_empty()
+        });
+        self.dirty = true;
+        true
+    }
+
+    // Rank the documents which contain all words in the query. Each word in the query matches to the indexed terms
+    // which contain it as a substring. `None` is returned when the query contains no word so the index is not
+    // available for the query.
+    pub fn query(
+        &self,
+        query: &str,
+        mut filter: impl FnMut(&Path) -> bool,
+    ) -> Option<Vec<(&Path, f64)>> {
+        let mut words: Vec<_> = tokenize(query).collect();
+        words.sort_unstable();
+        words.dedup();
+        if words.is_empty() {
+            return None;
+        }
+
+        let num_docs = self.ids.len() as f64;
+        let total_len: u64 = self.docs.iter().flatten().map(|d| d.len as u64).sum();
+        let avg_len = (total_len as f64 / num_docs).max(1.0);
+
+        let mut scores = HashMap::<u32, (f64, usize)>::new(); // Document ID -> (score, number of matched words)
+        for word in &words {
+            let mut freqs = HashMap::<u32, u32>::new();
+            for (_, docs) in self.postings.iter().filter(|(term, _)| term.contains(word.as_str())) {
+                for &(id, freq) in docs {
+                    *freqs.entry(id).or_default() += freq;
+                }
+            }
+            let df = freqs.len() as f64;
+            let idf = (1.0 + (num_docs - df + 0.5) / (df + 0.5)).ln();
+            for (id, freq) in freqs {
+                let Some(doc) = &self.docs[id as usize] else {
+                    continue;
+                };
+                let tf = freq as f64;
+                let norm = BM25_K1 * (1.0 - BM25_B + BM25_B * doc.len as f64 / avg_len);
+                let score = scores.entry(id).or_default();
+                score.0 += idf * tf * (BM25_K1 + 1.0) / (tf + norm);
+                score.1 += 1;
+            }
+        }
+
+        let mut ranked: Vec<_> = scores
+            .into_iter()
+            .filter(|(_, (_, matched))| *matched == words.len())
+            .filter_map(|(id, (score, _))| {
+                Some((self.docs[id as usize].as_ref()?.path.as_path(), score))
+            })
+            .filter(|(path, _)| filter(path))
+            .collect();
+        ranked.sort_by(|(lp, ls), (rp, rs)| rs.total_cmp(ls).then_with(|| lp.cmp(rp)));
+        Some(ranked)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn stamp(size: u64) -> FileStamp {
+        FileStamp { modified: Some(SystemTime::UNIX_EPOCH + Duration::from_secs(size)), size }
+    }
+
+    fn paths<'a>(ranked: &[(&'a Path, f64)]) -> Vec<&'a str> {
+        ranked.iter().map(|(p, _)| p.to_str().unwrap()).collect()
+    }
+
+    #[test]
+    fn tokenize_text() {
+        let have: Vec<_> = tokenize("Hello, World! foo_bar  2nd-Item 日本語").collect();
+        assert_eq!(have, ["hello", "world", "foo", "bar", "2nd", "item", "日本語"]);
+    }
+
+    #[test]
+    fn rank_documents() {
+        let mut index = FullTextIndex::default();
+        index.insert("a.md".into(), "apple banana", stamp(1));
+        index.insert("b.md".into(), "apple apple apple cherry", stamp(2));
+        index.insert("c.md".into(), "Cherry pie and banana split", stamp(3));
+        assert_eq!(index.len(), 3);
+
+        let all = |_: &Path| true;
+        assert_eq!(paths(&index.query("apple", all).unwrap()), ["b.md", "a.md"]);
+        assert_eq!(paths(&index.query("BANANA", all).unwrap()), ["a.md", "c.md"]);
+        assert_eq!(paths(&index.query("banana cherry", all).unwrap()), ["c.md"]);
+        assert_eq!(paths(&index.query("err", all).unwrap()), ["b.md", "c.md"]); // Substring of terms
+        assert_eq!(paths(&index.query("apple", |p| p != Path::new("b.md")).unwrap()), ["a.md"]);
+        assert!(index.query("durian", all).unwrap().is_empty());
+        assert!(index.query("!?", all).is_none());
+    }
+
+    #[test]
+    fn update_and_remove_documents() {
+        let mut index = FullTextIndex::default();
+        index.insert("a.md".into(), "foo", stamp(1));
+        index.insert("b.md".into(), "foo bar", stamp(2));
+        assert!(index.is_fresh(Path::new("a.md"), stamp(1)));
+        assert!(!index.is_fresh(Path::new("a.md"), stamp(2)));
+        assert!(!index.is_fresh(Path::new("c.md"), stamp(1)));
+        index.insert("d.md".into(), "foo", FileStamp { modified: None, size: 3 });
+        assert!(!index.is_fresh(Path::new("d.md"), FileStamp { modified: None, size: 3 }));
+        index.remove(Path::new("d.md"));
+
+        index.insert("a.md".into(), "bar", stamp(3));
+        assert_eq!(index.len(), 2);
+        assert!(index.is_fresh(Path::new("a.md"), stamp(3)));
+        assert_eq!(paths(&index.query("foo", |_| true).unwrap()), ["b.md"]);
+
+        assert!(index.remove(Path::new("b.md")));
+        assert!(!index.remove(Path::new("b.md")));
+        assert_eq!(paths(&index.query("bar", |_| true).unwrap()), ["a.md"]);
+        assert!(!index.postings.contains_key("foo"));
+
+        index.insert("c.md".into(), "foo", stamp(4));
+        assert_eq!(index.docs.len(), 3, "slot of the removed document is not reused");
+        let mut have: Vec<_> = index.paths().collect();
+        have.sort();
+        assert_eq!(have, [Path::new("a.md"), Path::new("c.md")]);
+    }
+
+    #[test]
+    fn save_and_load_index() {
+        let tmp = tempfile::tempdir().unwrap();
+        let data_dir = DataDir::new(tmp.path());
+        let mut index = FullTextIndex::load(&data_dir);
+        assert_eq!(index.len(), 0);
+        index.insert("a.md".into(), "foo bar", stamp(1));
+        index.insert("b.md".into(), "bar", stamp(2));
+        index.remove(Path::new("a.md"));
+        index.save(&data_dir).unwrap();
+        assert!(!index.dirty);
+
+        let index = FullTextIndex::load(&data_dir);
+        assert_eq!(index.len(), 1);
+        assert!(index.is_fresh(Path::new("b.md"), stamp(2)));
+        assert_eq!(paths(&index.query("bar", |_| true).unwrap()), ["b.md"]);
+
+        let mut old = FullTextIndex { dirty: true, ..FullTextIndex::default() };
+        old.save(&data_dir).unwrap(); // Version 0
+        let index = FullTextIndex::load(&data_dir);
+        assert_eq!(index.version, INDEX_VERSION);
+        assert!(index.dirty);
+    }
+}
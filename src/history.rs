@@ -15,6 +15,7 @@ pub enum Direction {
     Top,
 }
 
+#[derive(Clone)]
 pub struct History {
     max_items: usize,
     index: usize,
@@ -77,6 +78,10 @@ impl History {
         self.index = self.items.len() - 1; // Reset index to put focus on the new item
     }
 
+    pub fn max_items(&self) -> usize {
+        self.max_items
+    }
+
     pub fn current(&self) -> Option<&Path> {
         Some(self.items.get_index(self.index)?)
     }
@@ -150,20 +155,14 @@ impl History {
         impl PersistentData for Data {
             const FILE: &str = DATA_FILE_NAME;
         }
-        self.index = 0;
-        self.items.clear();
+        self.clear_items();
         config.data_dir().delete::<Data>()
     }
 
-    pub fn absolute_path(&self, path: &Path) -> Option<PathBuf> {
-        if path.is_relative()
-            && let Some(current_file) = self.current()
-            && let Some(dir) = current_file.parent()
-        {
-            dir.join(path).canonicalize().ok()
-        } else {
-            None
-        }
+    // Clear the items without deleting the persistent data. This is used for the navigation history of each window
+    pub fn clear_items(&mut self) {
+        self.index = 0;
+        self.items.clear();
     }
 }
 
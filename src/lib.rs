@@ -7,9 +7,16 @@
 #![allow(clippy::uninlined_format_args)]
 
 mod assets;
+mod bookmarks;
 mod cli;
 mod config;
+mod config_cmd;
+mod config_schema;
+mod diagram_worker;
 mod dialog;
+mod document_state;
+mod export;
+mod fulltext;
 mod history;
 #[cfg(target_os = "macos")]
 mod macos;
@@ -18,10 +25,14 @@ mod opener;
 mod persistent;
 mod preview;
 mod process_singleton;
+mod remote;
 mod renderer;
 #[cfg(feature = "__sanity")]
 mod sanity;
+mod search_index;
+mod session;
 mod shiba;
+mod tabs;
 #[cfg(test)]
 mod test;
 mod watcher;
@@ -30,13 +41,17 @@ mod window;
 mod windows;
 mod wry;
 
-pub use cli::{Options, Parsed};
+pub use cli::{ExportOptions, Options, Parsed};
+pub use config_cmd::{check_config, print_config, print_config_schema};
+pub use export::export;
+pub use remote::remote;
+pub use search_index::search;
 #[cfg(target_os = "windows")]
 pub use windows::WindowsConsole;
 
 #[cfg(feature = "__bench")]
 pub mod bench {
-    pub use super::config::SearchMatcher;
+    pub use super::config::{Markdown, SearchMatcher};
     pub use super::history::History;
     pub use super::markdown::{
         DisplayText, MarkdownContent, MarkdownParser, modified_offset, modified_offset_scalar,
@@ -9,23 +9,50 @@
 use anyhow::Result;
 use env_logger::{Builder, Env};
 use log::LevelFilter;
-use shiba_preview::{Options, Parsed, run};
+use shiba_preview::{
+    Options, Parsed, check_config, export, print_config, print_config_schema, remote, run, search,
+};
 use std::env;
 use std::process;
 
+fn init_logger(debug: bool) {
+    let level = if debug { LevelFilter::Debug } else { LevelFilter::Info };
+    let env = Env::new().filter("SHIBA_LOG").write_style("SHIBA_LOG_STYLE");
+    Builder::new()
+        .filter_level(level)
+        .format_timestamp(None)
+        .filter_module("html5ever", LevelFilter::Off)
+        .parse_env(env)
+        .init();
+}
+
 fn try_main() -> Result<()> {
     match Options::parse(env::args_os())? {
         Parsed::Options(options) => {
-            let level = if options.debug { LevelFilter::Debug } else { LevelFilter::Info };
-            let env = Env::new().filter("SHIBA_LOG").write_style("SHIBA_LOG_STYLE");
-            Builder::new()
-                .filter_level(level)
-                .format_timestamp(None)
-                .filter_module("html5ever", LevelFilter::Off)
-                .parse_env(env)
-                .init();
+            init_logger(options.debug);
             run(options)
         }
+        Parsed::Export(options) => {
+            init_logger(options.debug);
+            export(options)
+        }
+        Parsed::PrintConfig(options) => {
+            init_logger(options.debug);
+            print_config(options)
+        }
+        Parsed::CheckConfig(options) => {
+            init_logger(options.debug);
+            check_config(options)
+        }
+        Parsed::ConfigSchema => print_config_schema(),
+        Parsed::Remote(options, call) => {
+            init_logger(options.debug);
+            remote(options, call)
+        }
+        Parsed::Search(options, query) => {
+            init_logger(options.debug);
+            search(options, query)
+        }
         Parsed::Help(help) => {
             println!("{help}");
             Ok(())
@@ -0,0 +1,397 @@
+use crate::persistent::DataDir;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, ErrorKind, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitStatus, Stdio};
+use std::sync::{LazyLock, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const CACHE_DIR_NAME: &str = "Diagrams";
+// URL path to load the cached SVG images via the custom protocol
+pub const DIAGRAM_URL_PREFIX: &str = "/diagrams/";
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(10);
+
+pub fn diagram_cache_dir(data_dir: &DataDir) -> Option<PathBuf> {
+    data_dir.path().map(|dir| dir.join(CACHE_DIR_NAME))
+}
+
+// Path of the cached SVG image file from the URL path. Only the file names generated by `DiagramRenderer` are
+// accepted.
+//   e.g. '/diagrams/0123...cdef.svg' -> '/path/to/data/Diagrams/0123...cdef.svg'
+pub fn cached_diagram_path(cache_dir: &Path, url_path: &str) -> Option<PathBuf> {
+    let file = url_path.strip_prefix(DIAGRAM_URL_PREFIX)?;
+    let hash = file.strip_suffix(".svg")?;
+    if hash.len() != 32 || !hash.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    Some(cache_dir.join(file))
+}
+
+// 128-bit FNV-1a hash. The hash must be stable across application versions since it is used for the cache key.
+fn content_hash<'a>(inputs: impl IntoIterator<Item = &'a str>) -> u128 {
+    const OFFSET_BASIS: u128 = 0x6c62272e07bb014262b821756295c58d;
+    const PRIME: u128 = 0x0000000001000000000000000000013b;
+    let mut hash = OFFSET_BASIS;
+    for input in inputs {
+        for b in input.bytes().chain([0]) {
+            hash ^= b as u128;
+            hash = hash.wrapping_mul(PRIME);
+        }
+    }
+    hash
+}
+
+pub enum Diagram {
+    // URL path and file path of the rendered SVG image
+    Svg { url: String, path: PathBuf },
+    // Error message from the command
+    Error(String),
+    // The command is not available on the system. The code block should be rendered as-is.
+    Unavailable,
+    // The diagram is being rendered on the worker thread. The code block is rendered as-is until it is done.
+    Pending,
+}
+
+// Results of the commands which did not output any image in this process. Unlike images, they are not saved in the
+// cache directory because the commands may succeed after they are installed or fixed. They are forgotten when the
+// config file is reloaded so that the commands are run again. `None` means the command is not available.
+static FAILURES: LazyLock<Mutex<HashMap<u128, Option<String>>>> = LazyLock::new(Mutex::default);
+
+fn record_failure(hash: u128, message: Option<String>) {
+    FAILURES.lock().unwrap().insert(hash, message);
+}
+
+pub fn clear_diagram_failures() {
+    let mut failures = FAILURES.lock().unwrap();
+    log::debug!("Clear {} failures of diagram commands", failures.len());
+    failures.clear();
+}
+
+// Request to render a diagram with the command. Running the command may take time so this is usually sent to the
+// worker thread.
+#[derive(Debug)]
+pub struct DiagramJob {
+    lang: String,
+    command: Vec<String>,
+    source: String,
+    hash: u128,
+    cache_dir: PathBuf,
+    base_dir: Option<PathBuf>,
+}
+
+impl DiagramJob {
+    // Result of the diagram which was already rendered
+    fn result(&self) -> Option<Diagram> {
+        let file_name = format!("{:032x}.svg", self.hash);
+        let path = self.cache_dir.join(&file_name);
+        if path.is_file() {
+            log::debug!("Using cached {:?} diagram at {path:?}", self.lang);
+            let url = format!("{DIAGRAM_URL_PREFIX}{file_name}");
+            return Some(Diagram::Svg { url, path });
+        }
+        let diagram = match FAILURES.lock().unwrap().get(&self.hash)? {
+            Some(message) => Diagram::Error(message.clone()),
+            None => Diagram::Unavailable,
+        };
+        Some(diagram)
+    }
+
+    fn render(&self) -> Diagram {
+        let Self { lang, command, source, hash, cache_dir, base_dir } = self;
+        log::debug!("Rendering {lang:?} diagram with command {command:?}");
+        let svg = match run_command(command, source, base_dir.as_deref()) {
+            Ok(svg) => svg,
+            Err(err) if err.kind() == ErrorKind::NotFound => {
+                log::warn!(
+                    "Command {command:?} to render {lang:?} diagram is not available: {err}"
+                );
+                record_failure(*hash, None);
+                return Diagram::Unavailable;
+            }
+            Err(err) => {
+                log::debug!("Could not render {lang:?} diagram with command {command:?}: {err}");
+                record_failure(*hash, Some(err.to_string()));
+                return Diagram::Error(err.to_string());
+            }
+        };
+
+        let file_name = format!("{hash:032x}.svg");
+        let path = cache_dir.join(&file_name);
+        if let Err(err) = fs::create_dir_all(cache_dir).and_then(|()| fs::write(&path, svg)) {
+            let msg = format!("Could not save the rendered diagram at {path:?}: {err}");
+            log::error!("{msg}");
+            record_failure(*hash, Some(msg.clone()));
+            return Diagram::Error(msg);
+        }
+        log::debug!("Saved {lang:?} diagram to cache {path:?}");
+
+        let url = format!("{DIAGRAM_URL_PREFIX}{file_name}");
+        Diagram::Svg { url, path }
+    }
+
+    // Returns whether the diagram should be shown in the preview instead of the code block. The same diagram may be
+    // requested multiple times while it is being rendered. The command is not run again in the case.
+    pub fn run(&self) -> bool {
+        if self.result().is_some() {
+            return false;
+        }
+        !matches!(self.render(), Diagram::Unavailable)
+    }
+}
+
+// Renders the content of code blocks as SVG diagrams with local commands such as `dot -Tsvg`. The rendered images
+// are cached in the data directory keyed by the hash of the content and the command.
+#[derive(Default, Debug)]
+pub struct DiagramRenderer {
+    commands: HashMap<String, Vec<String>>,
+    cache_dir: Option<PathBuf>,
+    base_dir: Option<PathBuf>,
+    jobs: RefCell<Vec<DiagramJob>>,
+}
+
+impl DiagramRenderer {
+    pub fn new(base_dir: Option<&Path>) -> Self {
+        Self { base_dir: base_dir.map(Path::to_path_buf), ..Default::default() }
+    }
+
+    pub fn set_commands(&mut self, commands: HashMap<String, Vec<String>>, data_dir: &DataDir) {
+        self.commands = commands;
+        self.cache_dir = diagram_cache_dir(data_dir);
+    }
+
+    pub fn is_diagram(&self, lang: &str) -> bool {
+        self.cache_dir.is_some() && self.commands.contains_key(lang)
+    }
+
+    fn job(&self, lang: &str, source: &str) -> Option<DiagramJob> {
+        let (Some(cache_dir), Some(command)) = (&self.cache_dir, self.commands.get(lang)) else {
+            return None;
+        };
+        Some(DiagramJob {
+            lang: lang.to_string(),
+            command: command.clone(),
+            source: source.to_string(),
+            hash: content_hash(command.iter().map(String::as_str).chain([lang, source])),
+            cache_dir: cache_dir.clone(),
+            base_dir: self.base_dir.clone(),
+        })
+    }
+
+    // Render the diagram by running the command on the current thread
+    pub fn render(&self, lang: &str, source: &str) -> Diagram {
+        let Some(job) = self.job(lang, source) else {
+            return Diagram::Unavailable;
+        };
+        job.result().unwrap_or_else(|| job.render())
+    }
+
+    // Render the diagram without running the command. When the diagram is not rendered yet, a job to render it is
+    // queued. The queued jobs are taken by `take_jobs` and run on the worker thread.
+    pub fn render_in_background(&self, lang: &str, source: &str) -> Diagram {
+        let Some(job) = self.job(lang, source) else {
+            return Diagram::Unavailable;
+        };
+        if let Some(diagram) = job.result() {
+            return diagram;
+        }
+        log::debug!("Queue {lang:?} diagram to render it in background");
+        self.jobs.borrow_mut().push(job);
+        Diagram::Pending
+    }
+
+    pub fn take_jobs(&self) -> Vec<DiagramJob> {
+        self.jobs.take()
+    }
+}
+
+fn read_all(mut r: impl Read + Send + 'static) -> thread::JoinHandle<io::Result<Vec<u8>>> {
+    thread::spawn(move || {
+        let mut buf = vec![];
+        r.read_to_end(&mut buf)?;
+        Ok(buf)
+    })
+}
+
+fn exit_error(command: &str, status: ExitStatus, stderr: &[u8]) -> io::Error {
+    let stderr = String::from_utf8_lossy(stderr);
+    let stderr = stderr.trim();
+    let msg = if stderr.is_empty() {
+        format!("{command:?} failed with {status}")
+    } else {
+        format!("{command:?} failed with {status}: {stderr}")
+    };
+    io::Error::other(msg)
+}
+
+fn run_command(command: &[String], input: &str, dir: Option<&Path>) -> io::Result<Vec<u8>> {
+    let Some((program, args)) = command.split_first() else {
+        return Err(io::Error::new(ErrorKind::InvalidInput, "Command to render diagram is empty"));
+    };
+
+    let mut cmd = Command::new(program);
+    cmd.args(args).stdin(Stdio::piped()).stdout(Stdio::piped()).stderr(Stdio::piped());
+    if let Some(dir) = dir {
+        cmd.current_dir(dir);
+    }
+    let mut child = cmd.spawn()?;
+
+    // Write stdin and read stdout/stderr in other threads to avoid deadlock when the pipe buffers are full
+    let mut stdin = child.stdin.take().unwrap();
+    let input = input.as_bytes().to_vec();
+    let writer = thread::spawn(move || stdin.write_all(&input));
+    let stdout = read_all(child.stdout.take().unwrap());
+    let stderr = read_all(child.stderr.take().unwrap());
+
+    let start = Instant::now();
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if start.elapsed() > COMMAND_TIMEOUT {
+            child.kill()?;
+            child.wait()?;
+            let msg = format!("{program:?} did not finish within {}s", COMMAND_TIMEOUT.as_secs());
+            return Err(io::Error::new(ErrorKind::TimedOut, msg));
+        }
+        thread::sleep(Duration::from_millis(5));
+    };
+
+    // Writing to stdin may fail when the command exits without reading all the input. It is reported by the exit
+    // status instead.
+    let _ = writer.join();
+    let stdout = stdout.join().unwrap()?;
+    let stderr = stderr.join().unwrap()?;
+    if !status.success() {
+        return Err(exit_error(program, status, &stderr));
+    }
+    if stdout.is_empty() {
+        return Err(io::Error::other(format!("{program:?} did not output any SVG image")));
+    }
+    Ok(stdout)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stable_content_hash() {
+        assert_eq!(content_hash([]), 0x6c62272e07bb014262b821756295c58d);
+        assert_eq!(
+            content_hash(["dot", "-Tsvg", "digraph {}"]),
+            content_hash(["dot", "-Tsvg", "digraph {}"])
+        );
+        assert_ne!(content_hash(["ab", "c"]), content_hash(["a", "bc"]));
+        assert_ne!(content_hash(["dot", "a"]), content_hash(["dot", "b"]));
+    }
+
+    #[test]
+    fn url_path_to_cached_path() {
+        let dir = Path::new("cache");
+        let hash = "0123456789abcdef0123456789abcdef";
+        assert_eq!(
+            cached_diagram_path(dir, &format!("/diagrams/{hash}.svg")),
+            Some(dir.join(format!("{hash}.svg"))),
+        );
+        for path in [
+            "/diagrams/0123.svg",
+            "/diagrams/../../../../etc/passwd.svg",
+            "/diagrams/0123456789abcdef0123456789abcdef.png",
+            "/diagrams/0123456789abcdef0123456789abcdeg.svg",
+            "/other/0123456789abcdef0123456789abcdef.svg",
+        ] {
+            assert_eq!(cached_diagram_path(dir, path), None, "path={path:?}");
+        }
+    }
+
+    fn renderer(commands: &[(&str, &[&str])], data_dir: &Path) -> DiagramRenderer {
+        let commands = commands
+            .iter()
+            .map(|(lang, cmd)| (lang.to_string(), cmd.iter().map(|s| s.to_string()).collect()))
+            .collect();
+        let mut renderer = DiagramRenderer::new(None);
+        renderer.set_commands(commands, &DataDir::new(data_dir));
+        renderer
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    #[test]
+    fn render_and_cache_diagram() {
+        let tmp = tempfile::tempdir().unwrap();
+        let renderer = renderer(&[("echo", &["cat"])], tmp.path());
+        assert!(renderer.is_diagram("echo"));
+        assert!(!renderer.is_diagram("dot"));
+
+        let Diagram::Svg { url, path } = renderer.render("echo", "<svg></svg>") else {
+            panic!("diagram was not rendered");
+        };
+        assert_eq!(cached_diagram_path(&tmp.path().join(CACHE_DIR_NAME), &url), Some(path.clone()));
+        assert_eq!(fs::read_to_string(&path).unwrap(), "<svg></svg>");
+
+        // Cached image is used
+        fs::write(&path, "<svg>cached</svg>").unwrap();
+        let Diagram::Svg { url: cached, .. } = renderer.render("echo", "<svg></svg>") else {
+            panic!("cached diagram was not used");
+        };
+        assert_eq!(url, cached);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "<svg>cached</svg>");
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    #[test]
+    fn render_diagram_in_background() {
+        let tmp = tempfile::tempdir().unwrap();
+        let renderer = renderer(
+            &[("echo", &["cat"]), ("missing", &["this-command-does-not-exist"])],
+            tmp.path(),
+        );
+        let source = "<svg>background</svg>";
+        assert!(matches!(renderer.render_in_background("echo", source), Diagram::Pending));
+        assert!(matches!(renderer.render_in_background("missing", "foo"), Diagram::Pending));
+        assert!(matches!(renderer.render_in_background("unknown", "foo"), Diagram::Unavailable));
+
+        let jobs = renderer.take_jobs();
+        assert_eq!(jobs.len(), 2, "{jobs:?}");
+        assert!(renderer.take_jobs().is_empty());
+        assert_eq!(jobs.iter().map(DiagramJob::run).collect::<Vec<_>>(), [true, false]);
+        // The command is not run again for the rendered diagram
+        assert!(!jobs[0].run());
+
+        let Diagram::Svg { path, .. } = renderer.render_in_background("echo", source) else {
+            panic!("rendered diagram was not used");
+        };
+        assert_eq!(fs::read_to_string(&path).unwrap(), source);
+        assert!(matches!(renderer.render_in_background("missing", "foo"), Diagram::Unavailable));
+        assert!(renderer.take_jobs().is_empty());
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    #[test]
+    fn diagram_command_error() {
+        let tmp = tempfile::tempdir().unwrap();
+        let renderer = renderer(
+            &[("fail", &["sh", "-c", "echo 'syntax error' >&2; exit 1"]), ("empty", &["true"])],
+            tmp.path(),
+        );
+        let Diagram::Error(msg) = renderer.render("fail", "foo") else {
+            panic!("error was not reported");
+        };
+        assert!(msg.contains("syntax error"), "{msg:?}");
+        let Diagram::Error(msg) = renderer.render("empty", "foo") else {
+            panic!("error was not reported");
+        };
+        assert!(msg.contains("did not output any SVG image"), "{msg:?}");
+    }
+
+    #[test]
+    fn diagram_command_unavailable() {
+        let tmp = tempfile::tempdir().unwrap();
+        let renderer = renderer(&[("dot", &["this-command-does-not-exist"])], tmp.path());
+        assert!(matches!(renderer.render("dot", "digraph {}"), Diagram::Unavailable));
+        assert!(matches!(renderer.render("unknown", "digraph {}"), Diagram::Unavailable));
+        assert!(!DiagramRenderer::default().is_diagram("dot"));
+    }
+}
@@ -0,0 +1,896 @@
+use super::diagram::{Diagram, DiagramRenderer};
+use super::parser::{
+    Autolinker, EmojiToken, EmojiTokenizer, HtmlBlockReader, InlineHtmlReader, MarkdownContent,
+    Range, metadata_block_lang,
+};
+use super::sanitizer::{Sanitizer, SlashPath, should_rebase_url};
+use super::slug::{HeadingText, Slugger};
+use super::wikilink::{WikiLinkResolver, page_name};
+use crate::assets::guess_mime;
+use crate::renderer::RawMessageWriter;
+use pulldown_cmark::{
+    Alignment, BlockQuoteKind, CodeBlockKind, CowStr, Event, HeadingLevel, LinkType, Parser, Tag,
+    TagEnd,
+};
+use std::collections::HashMap;
+use std::fs;
+use std::io::{Result, Write};
+use std::path::Path;
+
+pub fn escape_html(mut out: impl Write, text: &str) -> Result<()> {
+    let bytes = text.as_bytes();
+    let mut start = 0;
+    for (i, b) in bytes.iter().enumerate() {
+        let escaped: &[u8] = match b {
+            b'&' => b"&amp;",
+            b'<' => b"&lt;",
+            b'>' => b"&gt;",
+            b'"' => b"&quot;",
+            b'\'' => b"&#39;",
+            // U+0000 must be replaced with U+FFFD. See the comment in `encode_string_byte` in parser.rs
+            b'\0' => "\u{fffd}".as_bytes(),
+            _ => continue,
+        };
+        out.write_all(&bytes[start..i])?;
+        out.write_all(escaped)?;
+        start = i + 1;
+    }
+    out.write_all(&bytes[start..])
+}
+
+fn encode_base64(out: &mut dyn Write, bytes: &[u8]) -> Result<()> {
+    const TABLE: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    for chunk in bytes.chunks(3) {
+        let n = chunk.iter().enumerate().fold(0u32, |n, (i, &b)| n | (b as u32) << (16 - i * 8));
+        let mut buf = [b'='; 4];
+        for (i, c) in buf.iter_mut().take(chunk.len() + 1).enumerate() {
+            *c = TABLE[(n >> (18 - i * 6)) as usize & 0x3f];
+        }
+        out.write_all(&buf)?;
+    }
+    Ok(())
+}
+
+// Renders the Markdown document as HTML directly without any renderer. The output is the same markup as what
+// `RenderTreeToReact` in ui/markdown.tsx renders from the render tree, except for the parts which require
+// JavaScript such as syntax highlighting, math expressions and mermaid diagrams. Relative URLs are kept as-is by
+// default.
+pub struct MarkdownHtml<'input> {
+    parser: Parser<'input>,
+    content: &'input MarkdownContent,
+    link_base: SlashPath,
+    image_dir: Option<&'input Path>,
+}
+
+impl<'input> MarkdownHtml<'input> {
+    pub fn new(content: &'input MarkdownContent) -> Self {
+        let parser = Parser::new_ext(&content.source, content.options);
+        Self { parser, content, link_base: SlashPath::default(), image_dir: None }
+    }
+
+    // Prefix relative URLs with the path. This is used when the HTML is put in another directory than the document.
+    pub fn with_link_base(mut self, base: &Path) -> Self {
+        self.link_base = SlashPath::from(base);
+        self
+    }
+
+    // Embed local images in the directory as data URLs so that the HTML does not depend on the files around it
+    pub fn with_embedded_images(mut self, dir: &'input Path) -> Self {
+        self.image_dir = Some(dir);
+        self
+    }
+}
+
+impl RawMessageWriter for MarkdownHtml<'_> {
+    type Output = ();
+
+    fn write_to(self, writer: impl Write) -> Result<Self::Output> {
+        let mut enc = HtmlEncoder::new(writer, self.content, &self.link_base, self.image_dir);
+        enc.events(self.parser)?;
+        enc.footnotes()
+    }
+}
+
+enum TableState {
+    Head,
+    Row,
+}
+
+fn heading_level(level: HeadingLevel) -> u8 {
+    match level {
+        HeadingLevel::H1 => 1,
+        HeadingLevel::H2 => 2,
+        HeadingLevel::H3 => 3,
+        HeadingLevel::H4 => 4,
+        HeadingLevel::H5 => 5,
+        HeadingLevel::H6 => 6,
+    }
+}
+
+fn alert_kind(kind: BlockQuoteKind) -> (&'static str, &'static str) {
+    match kind {
+        BlockQuoteKind::Note => ("note", "Note"),
+        BlockQuoteKind::Tip => ("tip", "Tip"),
+        BlockQuoteKind::Important => ("important", "Important"),
+        BlockQuoteKind::Warning => ("warning", "Warning"),
+        BlockQuoteKind::Caution => ("caution", "Caution"),
+    }
+}
+
+// Relative URL has no scheme like `data:` or `mailto:`
+fn is_relative_url(url: &str) -> bool {
+    if !should_rebase_url(url) {
+        return false;
+    }
+    match url.split_once(':') {
+        Some((scheme, _)) => {
+            !scheme.bytes().all(|b| b.is_ascii_alphanumeric() || b"+-.".contains(&b))
+        }
+        None => true,
+    }
+}
+
+struct HtmlEncoder<'input, W: Write> {
+    out: W,
+    base_dir: &'input SlashPath,
+    link_base: &'input SlashPath,
+    image_dir: Option<&'input Path>,
+    wikilinks: &'input WikiLinkResolver,
+    diagrams: &'input DiagramRenderer,
+    table: TableState,
+    aligns: Vec<Alignment>,
+    cell: usize,
+    ids: HashMap<CowStr<'input>, usize>,
+    // Footnote definitions are rendered at the bottom of the page. While rendering a definition, outputs are
+    // written to this buffer.
+    footnote: Option<(usize, Vec<u8>)>,
+    footnotes: Vec<(usize, Vec<u8>)>,
+    // ID of heading is generated from its content. While rendering a heading, its content is written to this buffer.
+    heading: Option<(HeadingText<'input>, Vec<CowStr<'input>>, Vec<u8>)>,
+    slugger: Slugger,
+    autolinker: Autolinker,
+    sanitizer: Sanitizer<'input>,
+}
+
+impl<'input, W: Write> HtmlEncoder<'input, W> {
+    fn new(
+        out: W,
+        content: &'input MarkdownContent,
+        link_base: &'input SlashPath,
+        image_dir: Option<&'input Path>,
+    ) -> Self {
+        let sanitizer = if link_base.is_empty() {
+            Sanitizer::without_rebase()
+        } else {
+            Sanitizer::new(link_base)
+        };
+        Self {
+            out,
+            base_dir: &content.base_dir,
+            link_base,
+            image_dir,
+            wikilinks: &content.wikilinks,
+            diagrams: &content.diagrams,
+            table: TableState::Head,
+            aligns: vec![],
+            cell: 0,
+            ids: HashMap::new(),
+            footnote: None,
+            footnotes: vec![],
+            heading: None,
+            slugger: Slugger::default(),
+            autolinker: Autolinker::default(),
+            sanitizer,
+        }
+    }
+
+    fn out(&mut self) -> &mut dyn Write {
+        match (&mut self.heading, &mut self.footnote) {
+            (Some((_, _, buf)), _) | (None, Some((_, buf))) => buf,
+            (None, None) => &mut self.out,
+        }
+    }
+
+    fn write(&mut self, b: &[u8]) -> Result<()> {
+        self.out().write_all(b)
+    }
+
+    fn escaped(&mut self, s: &str) -> Result<()> {
+        escape_html(self.out(), s)
+    }
+
+    fn id(&mut self, name: CowStr<'input>) -> usize {
+        let new = self.ids.len() + 1;
+        *self.ids.entry(name).or_insert(new)
+    }
+
+    fn sanitized(&mut self, html: impl std::io::Read) -> Result<()> {
+        let out: &mut dyn Write = match (&mut self.heading, &mut self.footnote) {
+            (Some((_, _, buf)), _) | (None, Some((_, buf))) => buf,
+            (None, None) => &mut self.out,
+        };
+        self.sanitizer.clean(out, html)
+    }
+
+    fn emoji_text(&mut self, text: &str) -> Result<()> {
+        for token in EmojiTokenizer::new(text) {
+            match token {
+                EmojiToken::Text(text) => self.escaped(text)?,
+                EmojiToken::Emoji(emoji, _) => {
+                    let name = emoji.name();
+                    self.write(b"<span title=\"")?;
+                    self.escaped(name)?;
+                    self.write(b"\" role=\"img\" aria-label=\"")?;
+                    self.escaped(name)?;
+                    self.write(b" emoji\">")?;
+                    self.write(emoji.as_str().as_bytes())?;
+                    self.write(b"</span>")?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn autolink_text(&mut self, mut text: &str) -> Result<()> {
+        while let Some((s, e)) = self.autolinker.find_autolink(text) {
+            if s > 0 {
+                self.emoji_text(&text[..s])?;
+            }
+
+            let url = &text[s..e];
+            self.write(b"<a href=\"")?;
+            self.escaped(url)?;
+            self.write(b"\">")?;
+            self.escaped(url)?;
+            self.write(b"</a>")?;
+
+            text = &text[e..];
+        }
+
+        if !text.is_empty() {
+            self.emoji_text(text)?;
+        }
+
+        Ok(())
+    }
+
+    fn diagram(
+        &mut self,
+        lang: &str,
+        events: impl Iterator<Item = (Event<'input>, Range)>,
+    ) -> Result<()> {
+        let mut source = String::new();
+        for (event, _) in events {
+            match event {
+                Event::Text(text) => source.push_str(&text),
+                Event::End(TagEnd::CodeBlock) => break,
+                _ => {}
+            }
+        }
+
+        match self.diagrams.render(lang, &source) {
+            // The image is embedded as data URL so that the exported HTML does not depend on the cache directory
+            Diagram::Svg { path, .. } => match fs::read(&path) {
+                Ok(svg) => {
+                    self.write(b"<p><img class=\"diagram\" alt=\"")?;
+                    self.escaped(lang)?;
+                    self.write(b" diagram\" src=\"data:image/svg+xml;base64,")?;
+                    encode_base64(self.out(), &svg)?;
+                    self.write(b"\"></p>\n")
+                }
+                Err(err) => {
+                    let message = format!("Could not read the rendered diagram at {path:?}: {err}");
+                    self.diagram_error(lang, &message, &source)
+                }
+            },
+            Diagram::Error(message) => self.diagram_error(lang, &message, &source),
+            Diagram::Unavailable | Diagram::Pending => {
+                self.write(b"<pre><code class=\"language-")?;
+                self.escaped(lang)?;
+                self.write(b"\">")?;
+                self.escaped(&source)?;
+                self.write(b"</code></pre>\n")
+            }
+        }
+    }
+
+    fn diagram_error(&mut self, lang: &str, message: &str, source: &str) -> Result<()> {
+        self.write(b"<div class=\"diagram-error\">\n<p>")?;
+        self.escaped(message)?;
+        self.write(b"</p>\n<pre><code class=\"language-")?;
+        self.escaped(lang)?;
+        self.write(b"\">")?;
+        self.escaped(source)?;
+        self.write(b"</code></pre>\n</div>\n")
+    }
+
+    fn rebase_link(&mut self, dest: &str) -> Result<()> {
+        if self.link_base.is_empty() || !is_relative_url(dest) {
+            return self.escaped(dest);
+        }
+
+        // Rebase 'foo/bar/' with '../path/to/base' as '../path/to/base/foo/bar'
+        let link_base = self.link_base;
+        self.escaped(link_base)?;
+        if !dest.starts_with('/') {
+            self.write(b"/")?;
+        }
+        self.escaped(dest)
+    }
+
+    fn image_src(&mut self, dest: &str) -> Result<()> {
+        if let Some(dir) = self.image_dir
+            && is_relative_url(dest)
+        {
+            let file = dest.split(['?', '#']).next().unwrap_or(dest);
+            let mime = guess_mime(file);
+            if mime.starts_with("image/") {
+                let path = dir.join(file.trim_start_matches('/'));
+                match fs::read(&path) {
+                    Ok(bytes) => {
+                        write!(self.out(), "data:{mime};base64,")?;
+                        return encode_base64(self.out(), &bytes);
+                    }
+                    Err(err) => log::warn!("Could not embed image {path:?} in HTML: {err}"),
+                }
+            }
+        }
+        self.rebase_link(dest)
+    }
+
+    // Wikilinks are resolved to absolute paths. Make them relative to the document as well as other links.
+    fn wikilink(&mut self, href: &str) -> Result<()> {
+        let base_dir: &str = self.base_dir;
+        match href.strip_prefix(base_dir).and_then(|rel| rel.strip_prefix('/')) {
+            Some(rel) if !base_dir.is_empty() => self.rebase_link(rel),
+            _ => self.escaped(href),
+        }
+    }
+
+    fn table_cell(&mut self, tag: &str) -> Result<()> {
+        let align = match self.aligns.get(self.cell).copied().unwrap_or(Alignment::None) {
+            Alignment::None => None,
+            Alignment::Left => Some("left"),
+            Alignment::Center => Some("center"),
+            Alignment::Right => Some("right"),
+        };
+        self.cell += 1;
+        if let Some(align) = align {
+            write!(self.out(), r#"<{tag} style="text-align: {align}">"#)
+        } else {
+            write!(self.out(), "<{tag}>")
+        }
+    }
+
+    fn events(&mut self, parser: Parser<'input>) -> Result<()> {
+        let mut in_code_block = false;
+        let mut in_auto_link = false;
+        let mut in_link = false;
+        let mut in_missing_page = false;
+
+        let mut events = parser.into_offset_iter().peekable();
+        while let Some((event, range)) = events.next() {
+            if let Some((heading, _, _)) = &mut self.heading {
+                heading.push_event(&event);
+            }
+
+            match event {
+                Event::Start(tag) => {
+                    use Tag::*;
+                    match tag {
+                        Paragraph => self.write(b"<p>")?,
+                        Heading { id, classes, .. } => {
+                            self.heading = Some((HeadingText::new(id), classes, vec![]));
+                        }
+                        Table(alignments) => {
+                            self.aligns = alignments;
+                            self.write(b"<table>\n")?;
+                        }
+                        TableHead => {
+                            self.table = TableState::Head;
+                            self.cell = 0;
+                            self.write(b"<thead>\n<tr>\n")?;
+                        }
+                        TableRow => {
+                            self.table = TableState::Row;
+                            self.cell = 0;
+                            self.write(b"<tr>\n")?;
+                        }
+                        TableCell => match self.table {
+                            TableState::Head => self.table_cell("th")?,
+                            TableState::Row => self.table_cell("td")?,
+                        },
+                        BlockQuote(None) => self.write(b"<blockquote>\n")?,
+                        BlockQuote(Some(kind)) => {
+                            let (kind, title) = alert_kind(kind);
+                            write!(
+                                self.out(),
+                                "<div class=\"markdown-alert markdown-alert-{kind}\">\n<p class=\"markdown-alert-title\">{title}</p>\n",
+                            )?;
+                        }
+                        CodeBlock(info) => {
+                            let lang = match &info {
+                                CodeBlockKind::Fenced(info) => {
+                                    info.split(' ').next().filter(|l| !l.is_empty())
+                                }
+                                CodeBlockKind::Indented => None,
+                            };
+                            if let Some(lang) = lang
+                                && self.diagrams.is_diagram(lang)
+                            {
+                                // Unlike other tags, this consumes all events until `TagEnd::CodeBlock`
+                                self.diagram(lang, &mut events)?;
+                                continue;
+                            }
+
+                            self.write(b"<pre><code")?;
+                            if let Some(lang) = lang {
+                                self.write(b" class=\"language-")?;
+                                self.escaped(lang)?;
+                                self.write(b"\"")?;
+                            }
+                            self.write(b">")?;
+                            in_code_block = true;
+                        }
+                        List(Some(1)) => self.write(b"<ol>\n")?,
+                        List(Some(start)) => writeln!(self.out(), "<ol start=\"{start}\">")?,
+                        List(None) => self.write(b"<ul>\n")?,
+                        Item => {
+                            if let Some((Event::TaskListMarker(_), _)) = events.peek() {
+                                self.write(b"<li class=\"task-list-item\">")?;
+                            } else {
+                                self.write(b"<li>")?;
+                            }
+                        }
+                        Emphasis => self.write(b"<em>")?,
+                        Strong => self.write(b"<strong>")?,
+                        Strikethrough => self.write(b"<del>")?,
+                        Link { link_type: LinkType::Autolink, .. } => {
+                            // Ignore autolink since it is linked by `Autolinker`
+                            in_auto_link = true;
+                        }
+                        Link { link_type: LinkType::WikiLink { .. }, dest_url, .. } => {
+                            if let Some(href) = self.wikilinks.resolve(&dest_url) {
+                                self.write(b"<a href=\"")?;
+                                self.wikilink(&href)?;
+                                self.write(b"\">")?;
+                            } else {
+                                self.write(
+                                    b"<span class=\"missing-page\" title=\"Page not found: ",
+                                )?;
+                                self.escaped(page_name(&dest_url))?;
+                                self.write(b"\">")?;
+                                in_missing_page = true;
+                            }
+                            in_link = true;
+                        }
+                        Link { link_type, dest_url, title, .. } => {
+                            self.write(b"<a href=\"")?;
+                            if link_type == LinkType::Email {
+                                self.write(b"mailto:")?;
+                                self.escaped(&dest_url)?;
+                            } else {
+                                self.rebase_link(&dest_url)?;
+                            }
+                            self.write(b"\"")?;
+                            if !title.is_empty() {
+                                self.write(b" title=\"")?;
+                                self.escaped(&title)?;
+                                self.write(b"\"")?;
+                            }
+                            self.write(b">")?;
+                            in_link = true;
+                        }
+                        Image { dest_url, title, .. } => {
+                            // Children of the image are rendered as its alt text. This consumes all events until
+                            // `TagEnd::Image`.
+                            let mut alt = String::new();
+                            let mut depth = 0usize;
+                            for (event, _) in events.by_ref() {
+                                if let Some((heading, _, _)) = &mut self.heading {
+                                    heading.push_event(&event);
+                                }
+                                match event {
+                                    Event::End(TagEnd::Image) if depth == 0 => break,
+                                    Event::Start(_) => depth += 1,
+                                    Event::End(_) => depth = depth.saturating_sub(1),
+                                    Event::Text(text)
+                                    | Event::Code(text)
+                                    | Event::InlineMath(text)
+                                    | Event::DisplayMath(text) => alt.push_str(&text),
+                                    Event::SoftBreak | Event::HardBreak => alt.push(' '),
+                                    _ => {}
+                                }
+                            }
+
+                            self.write(b"<img src=\"")?;
+                            self.image_src(&dest_url)?;
+                            self.write(b"\" alt=\"")?;
+                            self.escaped(&alt)?;
+                            self.write(b"\"")?;
+                            if !title.is_empty() {
+                                self.write(b" title=\"")?;
+                                self.escaped(&title)?;
+                                self.write(b"\"")?;
+                            }
+                            self.write(b">")?;
+                        }
+                        HtmlBlock => {
+                            // Unlike other tags, `HtmlBlockReader consumes all events until `TagEnd::HtmlBlock`
+                            self.sanitized(HtmlBlockReader::new(&mut events))?;
+                        }
+                        FootnoteDefinition(name) => {
+                            let id = self.id(name);
+                            if let Some(footnote) = self.footnote.replace((id, vec![])) {
+                                self.footnotes.push(footnote);
+                            }
+                        }
+                        MetadataBlock(_) if range.start == 0 => {
+                            // Front matter is not a part of the document
+                            for (event, _) in events.by_ref() {
+                                if let Event::End(TagEnd::MetadataBlock(_)) = event {
+                                    break;
+                                }
+                            }
+                        }
+                        MetadataBlock(kind) => {
+                            let lang = metadata_block_lang(kind);
+                            write!(self.out(), r#"<pre><code class="language-{lang}">"#)?;
+                            in_code_block = true;
+                        }
+                        DefinitionList => self.write(b"<dl>\n")?,
+                        DefinitionListTitle => self.write(b"<dt>")?,
+                        DefinitionListDefinition => self.write(b"<dd>")?,
+                        Superscript => self.write(b"<sup>")?,
+                        Subscript => self.write(b"<sub>")?,
+                    }
+                }
+                Event::End(tag_end) => {
+                    use TagEnd::*;
+                    match tag_end {
+                        Paragraph => self.write(b"</p>\n")?,
+                        Heading(level) => {
+                            let (text, classes, content) = self.heading.take().unwrap_or_default();
+                            let id = self.slugger.id(text);
+                            let level = heading_level(level);
+                            write!(self.out(), "<h{level} id=\"")?;
+                            self.escaped(&id)?;
+                            if !classes.is_empty() {
+                                self.write(b"\" class=\"")?;
+                                self.escaped(&classes.join(" "))?;
+                            }
+                            self.write(b"\">")?;
+                            self.write(&content)?;
+                            writeln!(self.out(), "</h{level}>")?;
+                        }
+                        Table => self.write(b"</tbody>\n</table>\n")?,
+                        TableHead => self.write(b"</tr>\n</thead>\n<tbody>\n")?,
+                        TableRow => self.write(b"</tr>\n")?,
+                        TableCell => match self.table {
+                            TableState::Head => self.write(b"</th>\n")?,
+                            TableState::Row => self.write(b"</td>\n")?,
+                        },
+                        BlockQuote(None) => self.write(b"</blockquote>\n")?,
+                        BlockQuote(Some(_)) => self.write(b"</div>\n")?,
+                        CodeBlock | MetadataBlock(_) => {
+                            in_code_block = false;
+                            self.write(b"</code></pre>\n")?;
+                        }
+                        List(true) => self.write(b"</ol>\n")?,
+                        List(false) => self.write(b"</ul>\n")?,
+                        Item => self.write(b"</li>\n")?,
+                        Emphasis => self.write(b"</em>")?,
+                        Strong => self.write(b"</strong>")?,
+                        Strikethrough => self.write(b"</del>")?,
+                        DefinitionList => self.write(b"</dl>\n")?,
+                        DefinitionListTitle => self.write(b"</dt>\n")?,
+                        DefinitionListDefinition => self.write(b"</dd>\n")?,
+                        Superscript => self.write(b"</sup>")?,
+                        Subscript => self.write(b"</sub>")?,
+                        Link if in_auto_link => in_auto_link = false,
+                        Link if in_missing_page => {
+                            in_link = false;
+                            in_missing_page = false;
+                            self.write(b"</span>")?;
+                        }
+                        Link => {
+                            in_link = false;
+                            self.write(b"</a>")?;
+                        }
+                        FootnoteDefinition => {
+                            if let Some(footnote) = self.footnote.take() {
+                                self.footnotes.push(footnote);
+                            }
+                        }
+                        Image => unreachable!(), // This event is consumed in `Tag::Image` event
+                        HtmlBlock => unreachable!(), // This event is handled in `Tag::HtmlBlock` event using `HtmlBlockReader`
+                    }
+                }
+                Event::Text(text) if in_code_block || in_link => self.escaped(&text)?,
+                Event::Text(text) => self.autolink_text(&text)?,
+                Event::Code(text) => {
+                    self.write(b"<code>")?;
+                    self.escaped(&text)?;
+                    self.write(b"</code>")?;
+                }
+                // Inline HTML inside blockquote is emitted as Event::Html event wrongly.
+                // https://github.com/pulldown-cmark/pulldown-cmark/issues/960
+                Event::Html(html) | Event::InlineHtml(html) => {
+                    self.sanitized(InlineHtmlReader::new(html, &mut events))?;
+                }
+                Event::SoftBreak => self.write(b"\n")?,
+                Event::HardBreak => self.write(b"<br>\n")?,
+                Event::Rule => self.write(b"<hr>\n")?,
+                Event::FootnoteReference(name) => {
+                    let id = self.id(name);
+                    write!(
+                        self.out(),
+                        r##"<sup><a href="#user-content-fn-{id}" id="user-content-fnref-{id}" aria-describedby="footnote-label">[{id}]</a></sup>"##,
+                    )?;
+                }
+                Event::TaskListMarker(checked) => {
+                    let checked = if checked { " checked" } else { "" };
+                    write!(
+                        self.out(),
+                        r#"<input type="checkbox" class="task-list-item-checkbox" disabled{checked}> "#,
+                    )?;
+                }
+                // Math expressions are left with MathJax delimiters so that they can be typeset later
+                Event::DisplayMath(text) => {
+                    self.write(br#"<span class="math-expr-block">\["#)?;
+                    self.escaped(&text)?;
+                    self.write(br#"\]</span>"#)?;
+                }
+                Event::InlineMath(text) => {
+                    self.write(br#"<span class="math-expr-inline">\("#)?;
+                    self.escaped(&text)?;
+                    self.write(br#"\)</span>"#)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    // Footnotes are rendered at the bottom of page as `RenderTreeToReact.renderFootnotes` does
+    fn footnotes(mut self) -> Result<()> {
+        if self.footnotes.is_empty() {
+            return Ok(());
+        }
+
+        self.out.write_all(
+            b"<section class=\"footnotes\">\n<h2 id=\"footnote-label\">Footnotes</h2>\n<ol>\n",
+        )?;
+        for (id, mut content) in self.footnotes {
+            let backref = format!(
+                r##"<a href="#user-content-fnref-{id}" aria-label="Back to reference {id}">↩</a>"##,
+            );
+            // Put the back reference link at the end of the last paragraph
+            if content.ends_with(b"</p>\n") {
+                let idx = content.len() - b"</p>\n".len();
+                content.splice(idx..idx, backref.bytes());
+            } else {
+                content.extend_from_slice(backref.as_bytes());
+            }
+            writeln!(self.out, "<li id=\"user-content-fn-{id}\">")?;
+            self.out.write_all(&content)?;
+            self.out.write_all(b"</li>\n")?;
+        }
+        self.out.write_all(b"</ol>\n</section>\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Markdown;
+    use crate::persistent::DataDir;
+    use crate::test::commonmark;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+
+    fn load_data(name: &str) -> String {
+        let mut path = PathBuf::from("src");
+        path.push("markdown");
+        path.push("testdata");
+        path.push(format!("{}.md", name));
+        match fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(err) => panic!("Could not find Markdown test data at {:?}: {}", path, err),
+        }
+    }
+
+    fn render(content: &MarkdownContent) -> String {
+        let mut buf = vec![];
+        let () = MarkdownHtml::new(content).write_to(&mut buf).unwrap();
+        String::from_utf8(buf).unwrap()
+    }
+
+    macro_rules! snapshot_test {
+        ($name:ident, $basedir:expr, $config:expr) => {
+            #[test]
+            fn $name() {
+                let source = load_data(stringify!($name));
+                let content = MarkdownContent::new(source, $basedir, &$config);
+                insta::assert_snapshot!(render(&content));
+            }
+        };
+        ($name:ident, $basedir:expr) => {
+            snapshot_test!($name, $basedir, Markdown::default());
+        };
+        ($name:ident) => {
+            snapshot_test!($name, None);
+        };
+    }
+
+    snapshot_test!(paragraph);
+    snapshot_test!(blockquote);
+    snapshot_test!(list);
+    snapshot_test!(headings);
+    snapshot_test!(codeblock);
+    snapshot_test!(link);
+    snapshot_test!(html);
+    snapshot_test!(sanitized);
+    snapshot_test!(inline_code);
+    snapshot_test!(emphasis);
+    snapshot_test!(image);
+    snapshot_test!(autolink);
+    snapshot_test!(emoji);
+    snapshot_test!(table);
+    snapshot_test!(math);
+    snapshot_test!(strikethrough);
+    snapshot_test!(tasklist);
+    snapshot_test!(footnotes);
+    snapshot_test!(not_link);
+    snapshot_test!(soft_and_hard_break);
+    snapshot_test!(all);
+    snapshot_test!(alert);
+    snapshot_test!(inline_html_inside_blockquote);
+    snapshot_test!(escaped_chars_in_text);
+    snapshot_test!(empty);
+    snapshot_test!(front_matter_yaml);
+    snapshot_test!(front_matter_toml);
+    snapshot_test!(definition_list);
+    snapshot_test!(superscript_subscript);
+    snapshot_test!(heading_ids);
+    snapshot_test!(
+        markdown_extensions,
+        None,
+        Markdown {
+            smart_punctuation: true,
+            heading_attributes: true,
+            wikilinks: true,
+            ..Default::default()
+        }
+    );
+    snapshot_test!(
+        wikilinks,
+        Some(Path::new("src/markdown/testdata")),
+        Markdown { wikilinks: true, ..Default::default() }
+    );
+
+    #[cfg(target_os = "windows")]
+    const BASE_DIR: &str = r"\a\b\c\d\e";
+    #[cfg(not(target_os = "windows"))]
+    const BASE_DIR: &str = "/a/b/c/d/e";
+    snapshot_test!(relative_links, Some(Path::new(BASE_DIR)));
+
+    #[test]
+    fn rebase_relative_links() {
+        let source = "[a](foo/bar.md) [b](/foo.md) [c](#hash) [d](https://example.com) [e](data:text/plain,hi)\n\n<a href=\"x.md\">f</a>\n";
+        let content = MarkdownContent::new(source.to_string(), None, &Markdown::default());
+        let mut buf = vec![];
+        MarkdownHtml::new(&content)
+            .with_link_base(Path::new("../docs"))
+            .write_to(&mut buf)
+            .unwrap();
+        let html = String::from_utf8(buf).unwrap();
+        for want in [
+            r#"<a href="../docs/foo/bar.md">a</a>"#,
+            r#"<a href="../docs/foo.md">b</a>"#,
+            r##"<a href="#hash">c</a>"##,
+            r#"<a href="https://example.com">d</a>"#,
+            r#"<a href="data:text/plain,hi">e</a>"#,
+            r#"href="../docs/x.md""#,
+        ] {
+            assert!(html.contains(want), "{want:?} is not contained in {html:?}");
+        }
+    }
+
+    #[test]
+    fn sanitize_raw_html() {
+        let content = MarkdownContent::new(
+            "<script>alert(1)</script>\n\nfoo <b onclick=\"alert(1)\">bar</b>\n".to_string(),
+            None,
+            &Markdown::default(),
+        );
+        let html = render(&content);
+        assert!(!html.contains("<script>"), "{html:?}");
+        assert!(!html.contains("onclick"), "{html:?}");
+        assert!(html.contains("<b>bar</b>"), "{html:?}");
+    }
+
+    #[test]
+    fn footnote_back_reference() {
+        let content =
+            MarkdownContent::new("foo[^1]\n\n[^1]: bar\n".to_string(), None, &Markdown::default());
+        let html = render(&content);
+        let want = r##"<li id="user-content-fn-1">
+<p>bar<a href="#user-content-fnref-1" aria-label="Back to reference 1">↩</a></p>
+</li>"##;
+        assert!(html.contains(want), "{html:?}");
+        assert!(html.ends_with("</section>\n"), "{html:?}");
+    }
+
+    #[test]
+    fn render_with_any_config() {
+        let dir = fs::read_dir(Path::new("src").join("markdown").join("testdata")).unwrap();
+        for entry in dir {
+            let source = fs::read_to_string(entry.unwrap().path()).unwrap();
+            let all = Markdown {
+                smart_punctuation: true,
+                heading_attributes: true,
+                wikilinks: true,
+                ..Default::default()
+            };
+            for config in [commonmark(), all] {
+                render(&MarkdownContent::new(source.clone(), None, &config));
+            }
+        }
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    #[test]
+    fn diagrams() {
+        let tmp = tempfile::tempdir().unwrap();
+        let commands = [
+            ("svg", &["cat"][..]),
+            ("fail", &["sh", "-c", "echo 'syntax error' >&2; exit 1"]),
+            ("missing", &["this-command-does-not-exist"]),
+        ]
+        .into_iter()
+        .map(|(lang, cmd)| (lang.to_string(), cmd.iter().map(|s| s.to_string()).collect()))
+        .collect();
+        let content = MarkdownContent::new(load_data("diagrams"), None, &Markdown::default())
+            .with_diagrams(commands, &DataDir::new(tmp.path()));
+        insta::assert_snapshot!(render(&content));
+    }
+
+    #[test]
+    fn encode_bytes_in_base64() {
+        for (input, want) in [
+            ("", ""),
+            ("f", "Zg=="),
+            ("fo", "Zm8="),
+            ("foo", "Zm9v"),
+            ("foob", "Zm9vYg=="),
+            ("<svg></svg>", "PHN2Zz48L3N2Zz4="),
+        ] {
+            let mut buf = vec![];
+            encode_base64(&mut buf, input.as_bytes()).unwrap();
+            assert_eq!(String::from_utf8(buf).unwrap(), want, "input={input:?}");
+        }
+    }
+
+    #[test]
+    fn replace_nul_with_fffd() {
+        let content = MarkdownContent::new("\0 `\0` $\0$".to_string(), None, &Markdown::default());
+        let html = render(&content);
+        assert_eq!(html.matches("\u{fffd}").count(), 3, "output={html:?}");
+        assert!(!html.contains('\0'), "output={html:?}");
+    }
+
+    #[test]
+    fn escape_html_special_chars() {
+        for (input, want) in [
+            ("", ""),
+            ("hello", "hello"),
+            ("<a href=\"x\">'&'</a>", "&lt;a href=&quot;x&quot;&gt;&#39;&amp;&#39;&lt;/a&gt;"),
+            ("あ<い>う", "あ&lt;い&gt;う"),
+        ] {
+            let mut buf = vec![];
+            escape_html(&mut buf, input).unwrap();
+            assert_eq!(String::from_utf8(buf).unwrap(), want, "input={input:?}");
+        }
+    }
+}
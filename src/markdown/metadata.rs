@@ -0,0 +1,190 @@
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum FrontMatterKind {
+    Yaml, // Delimited by ---
+    Toml, // Delimited by +++
+}
+
+// Find the metadata block (so-called front matter) at the beginning of the document. This follows the same rules as
+// pulldown-cmark's `Options::ENABLE_YAML_STYLE_METADATA_BLOCKS` and `Options::ENABLE_PLUSES_DELIMITED_METADATA_BLOCKS`.
+// Returns the kind and the content of the block.
+pub fn front_matter(source: &str) -> Option<(FrontMatterKind, &str)> {
+    fn is_delim(line: &str, delim: &str) -> bool {
+        line.strip_prefix(delim).is_some_and(|rest| rest.trim_end_matches([' ', '\r']).is_empty())
+    }
+
+    let (first, rest) = source.split_once('\n')?;
+    let kind = match first.trim_end() {
+        "---" => FrontMatterKind::Yaml,
+        "+++" => FrontMatterKind::Toml,
+        _ => return None,
+    };
+    let is_end = |line: &str| match kind {
+        FrontMatterKind::Yaml => is_delim(line, "---") || is_delim(line, "..."),
+        FrontMatterKind::Toml => is_delim(line, "+++"),
+    };
+
+    let mut offset = 0;
+    for (i, line) in rest.split_inclusive('\n').enumerate() {
+        let line = line.strip_suffix('\n').unwrap_or(line);
+        // The first line of the block cannot be an empty line nor the end of the block
+        if i == 0 && (line.trim().is_empty() || is_end(line)) {
+            return None;
+        }
+        if is_end(line) {
+            return Some((kind, &rest[..offset]));
+        }
+        offset += line.len() + 1;
+    }
+
+    None
+}
+
+#[derive(Default, Serialize, PartialEq, Debug)]
+#[serde(transparent)]
+pub struct Metadata(Map<String, Value>);
+
+impl Metadata {
+    pub fn parse(source: &str) -> Option<Self> {
+        let (kind, block) = front_matter(source)?;
+        let map = match kind {
+            FrontMatterKind::Yaml => match serde_yaml::from_str(block) {
+                Ok(map) => map,
+                Err(err) => {
+                    log::debug!("Could not parse YAML front matter as mapping: {err}");
+                    return None;
+                }
+            },
+            FrontMatterKind::Toml => parse_toml_table(block),
+        };
+        Some(Self(map))
+    }
+
+    pub fn title(&self) -> Option<&str> {
+        self.0.get("title")?.as_str()
+    }
+}
+
+// Note: This is not a complete TOML parser. Only key-value pairs at top level are supported. Values which cannot be
+// parsed as JSON values (dates, inline tables, ...) are stored as raw strings.
+fn parse_toml_table(block: &str) -> Map<String, Value> {
+    fn value(input: &str) -> Value {
+        match input {
+            "true" => return Value::Bool(true),
+            "false" => return Value::Bool(false),
+            _ => {}
+        }
+
+        if let Some(rest) = input.strip_prefix('\'')
+            && let Some((literal, _)) = rest.split_once('\'')
+        {
+            return Value::String(literal.to_string());
+        }
+
+        // Basic strings, integers, floats and arrays of them are compatible with JSON
+        let mut stream = serde_json::Deserializer::from_str(input).into_iter();
+        if let Some(Ok(value)) = stream.next() {
+            let rest = input[stream.byte_offset()..].trim_start();
+            if rest.is_empty() || rest.starts_with('#') {
+                return value;
+            }
+        }
+
+        Value::String(input.to_string())
+    }
+
+    let mut map = Map::new();
+    for line in block.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if line.starts_with('[') {
+            break; // Tables are not supported
+        }
+        let Some((key, val)) = line.split_once('=') else {
+            continue;
+        };
+        let key = key.trim().trim_matches(['"', '\'']);
+        map.insert(key.to_string(), value(val.trim()));
+    }
+    map
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn find_front_matter() {
+        use FrontMatterKind::*;
+
+        for (input, want) in [
+            ("---\ntitle: foo\n---\n", Some((Yaml, "title: foo\n"))),
+            ("---\ntitle: foo\n---", Some((Yaml, "title: foo\n"))),
+            ("---\na: 1\nb: 2\n...\nbody", Some((Yaml, "a: 1\nb: 2\n"))),
+            ("---  \r\ntitle: foo\r\n---\r\n", Some((Yaml, "title: foo\r\n"))),
+            ("+++\ntitle = 'foo'\n+++\n", Some((Toml, "title = 'foo'\n"))),
+            ("+++\ntitle = 'foo'\n---\n", None),
+            ("---\n\ntitle: foo\n---\n", None),
+            ("---\n---\n", None),
+            ("---\ntitle: foo\n", None),
+            ("----\ntitle: foo\n----\n", None),
+            ("# title\n---\nfoo\n---\n", None),
+            (" ---\ntitle: foo\n---\n", None),
+            ("", None),
+        ] {
+            assert_eq!(front_matter(input), want, "input={input:?}");
+        }
+    }
+
+    #[test]
+    fn parse_yaml_metadata() {
+        let meta = Metadata::parse("---\ntitle: Hello\ntags: [a, b]\ndraft: false\n---\n").unwrap();
+        let want = json!({"title": "Hello", "tags": ["a", "b"], "draft": false});
+        assert_eq!(serde_json::to_value(&meta).unwrap(), want);
+        assert_eq!(meta.title(), Some("Hello"));
+    }
+
+    #[test]
+    fn parse_toml_metadata() {
+        let source = r#"+++
+# comment
+title = "Hello \"world\""
+"quoted key" = 'literal \n'
+weight = 10
+ratio = 0.5 # comment
+draft = true
+tags = ["a", "b"]
+date = 2025-01-01
+
+[params]
+nested = 1
++++
+"#;
+        let meta = Metadata::parse(source).unwrap();
+        let want = json!({
+            "title": "Hello \"world\"",
+            "quoted key": "literal \\n",
+            "weight": 10,
+            "ratio": 0.5,
+            "draft": true,
+            "tags": ["a", "b"],
+            "date": "2025-01-01",
+        });
+        assert_eq!(serde_json::to_value(&meta).unwrap(), want);
+        assert_eq!(meta.title(), Some("Hello \"world\""));
+    }
+
+    #[test]
+    fn invalid_metadata() {
+        for input in ["---\n- a\n- b\n---\n", "---\n: : :\n---\n", "no front matter"] {
+            assert_eq!(Metadata::parse(input), None, "input={input:?}");
+        }
+        let meta = Metadata::parse("---\ntitle: [1]\n---\n").unwrap();
+        assert_eq!(meta.title(), None);
+    }
+}
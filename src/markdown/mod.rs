@@ -1,9 +1,17 @@
 mod bytes;
+mod diagram;
+mod html;
+mod metadata;
 mod parser;
 mod sanitizer;
 mod search;
+mod slug;
+mod wikilink;
 
 #[cfg(feature = "__bench")]
 pub use bytes::{modified_offset, modified_offset_scalar};
-pub use parser::{MarkdownContent, MarkdownParser};
-pub use search::DisplayText;
+pub use diagram::{DiagramJob, cached_diagram_path, clear_diagram_failures, diagram_cache_dir};
+pub use html::{MarkdownHtml, escape_html};
+pub use metadata::Metadata;
+pub use parser::{Heading, MarkdownContent, MarkdownParser};
+pub use search::{DisplayText, Replaced, ReplacedHunk, ScopedQuery};
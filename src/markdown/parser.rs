@@ -1,27 +1,44 @@
+use super::diagram::{Diagram, DiagramJob, DiagramRenderer};
+use super::metadata::{Metadata, front_matter};
 use super::sanitizer::{Sanitizer, SlashPath, should_rebase_url};
+use super::slug::{HeadingText, Slugger};
+use super::wikilink::{WikiLinkResolver, page_name};
+use crate::config::{FileExtensions, Markdown};
 use crate::markdown::bytes::modified_offset;
+use crate::persistent::DataDir;
 use crate::renderer::RawMessageWriter;
 use aho_corasick::AhoCorasick;
 use emojis::Emoji;
 use memchr::{Memchr, memchr_iter};
 use pulldown_cmark::{
-    Alignment, BlockQuoteKind, CodeBlockKind, CowStr, Event, HeadingLevel, LinkType, Options,
-    Parser, Tag, TagEnd,
+    Alignment, BlockQuoteKind, CodeBlockKind, CowStr, Event, HeadingLevel, LinkType,
+    MetadataBlockKind, Options, Parser, Tag, TagEnd,
 };
 use std::collections::HashMap;
 use std::io::{Read, Result, Write};
 use std::iter::Peekable;
 use std::marker::PhantomData;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 pub type Range = std::ops::Range<usize>;
 
+// Structure of the document which a text belongs to. It is used for narrowing down search results.
+#[derive(Clone, Default, PartialEq, Eq, Debug)]
+pub struct TextScope {
+    pub heading: Option<u8>, // Level of the heading
+    pub code_block: bool,
+    pub lang: Option<String>, // Language of the code block
+    pub table_cell: bool,
+    pub link: bool, // Text of links including auto links and wiki links
+    pub alert: Option<&'static str>, // Kind of the alert such as "note"
+}
+
 pub trait TextVisitor: Default {
-    fn visit(&mut self, text: &str, range: &Range);
+    fn visit(&mut self, text: &str, range: &Range, scope: &TextScope);
 }
 
 impl TextVisitor for () {
-    fn visit(&mut self, _text: &str, _range: &Range) {}
+    fn visit(&mut self, _text: &str, _range: &Range, _scope: &TextScope) {}
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -55,16 +72,61 @@ impl TextTokenizer for () {
     }
 }
 
-#[derive(Default)]
+#[derive(Debug, PartialEq, Eq)]
+pub struct Heading {
+    pub level: u8,
+    pub text: String,
+    pub offset: usize,
+}
+
 pub struct MarkdownContent {
-    source: String,
-    base_dir: SlashPath,
+    pub(super) source: String,
+    pub(super) base_dir: SlashPath,
+    pub(super) options: Options,
+    pub(super) wikilinks: WikiLinkResolver,
+    pub(super) diagrams: DiagramRenderer,
+}
+
+impl Default for MarkdownContent {
+    fn default() -> Self {
+        Self {
+            source: String::new(),
+            base_dir: SlashPath::default(),
+            options: Options::empty(),
+            wikilinks: WikiLinkResolver::default(),
+            diagrams: DiagramRenderer::default(),
+        }
+    }
 }
 
 impl MarkdownContent {
-    pub fn new(source: String, base_dir: Option<&Path>) -> Self {
+    pub fn new(source: String, base_dir: Option<&Path>, config: &Markdown) -> Self {
+        let wikilinks = WikiLinkResolver::new(base_dir);
+        let diagrams = DiagramRenderer::new(base_dir);
         let base_dir = base_dir.map(SlashPath::from).unwrap_or_default();
-        Self { source, base_dir }
+        let options = parser_options(&source, config);
+        Self { source, base_dir, options, wikilinks, diagrams }
+    }
+
+    // Wikilinks are resolved against the watched directories as well as the directory of the file
+    pub fn with_wikilink_dirs(mut self, dirs: Vec<PathBuf>, extensions: FileExtensions) -> Self {
+        self.wikilinks.add_dirs(dirs, extensions);
+        self
+    }
+
+    // Fenced code blocks in the languages of the commands are rendered as diagrams
+    pub fn with_diagrams(
+        mut self,
+        commands: HashMap<String, Vec<String>>,
+        data_dir: &DataDir,
+    ) -> Self {
+        self.diagrams.set_commands(commands, data_dir);
+        self
+    }
+
+    // Diagrams which were not rendered yet while parsing the content. They should be rendered in background.
+    pub fn diagram_jobs(&self) -> Vec<DiagramJob> {
+        self.diagrams.take_jobs()
     }
 
     pub fn modified_utf8_offset(&self, new: &Self) -> Option<usize> {
@@ -83,11 +145,142 @@ impl MarkdownContent {
     pub fn is_empty(&self) -> bool {
         self.source.is_empty() && self.base_dir.is_empty()
     }
+
+    // Byte ranges of the top-level blocks in the source. Front matter and footnote definitions are not included since
+    // they are not rendered at their positions.
+    fn blocks(&self) -> Vec<Range> {
+        let mut depth = 0;
+        let mut blocks = vec![];
+        for (event, range) in Parser::new_ext(&self.source, self.options).into_offset_iter() {
+            let is_block = match event {
+                Event::Start(tag) => {
+                    depth += 1;
+                    depth == 1
+                        && !matches!(tag, Tag::FootnoteDefinition(_))
+                        && !(matches!(tag, Tag::MetadataBlock(_)) && range.start == 0)
+                }
+                Event::End(_) => {
+                    depth -= 1;
+                    false
+                }
+                Event::Rule => depth == 0,
+                _ => false,
+            };
+            if is_block {
+                blocks.push(range);
+            }
+        }
+        blocks
+    }
+
+    // Index of the top-level block rendered from the line (1-based) in the source. When the line is not in any block
+    // (e.g. a blank line), the next block is chosen.
+    pub fn block_at_line(&self, line: usize) -> Option<usize> {
+        let offset: usize =
+            self.source.split_inclusive('\n').take(line.saturating_sub(1)).map(str::len).sum();
+        let blocks = self.blocks();
+        let last = blocks.len().checked_sub(1)?;
+        Some(blocks.iter().position(|r| offset < r.end).unwrap_or(last))
+    }
+
+    // Lines (1-based and inclusive) of the top-level blocks in the source. They are sent to the window to synchronize
+    // the scroll positions of the source and the preview in split view.
+    pub fn block_lines(&self) -> Vec<(usize, usize)> {
+        let bytes = self.source.as_bytes();
+        let (mut line, mut pos) = (1, 0);
+        let mut line_at = |offset: usize| {
+            line += memchr_iter(b'\n', &bytes[pos..offset]).count();
+            pos = offset;
+            line
+        };
+        self.blocks()
+            .into_iter()
+            .map(|r| (line_at(r.start), line_at(r.end.saturating_sub(1).max(r.start))))
+            .collect()
+    }
+
+    // Line number (1-based) at the byte offset in the source. The offset is sent from the window as 'o' of block
+    // elements in the render tree.
+    pub fn line_at(&self, offset: usize) -> usize {
+        let offset = offset.min(self.source.len());
+        self.source.as_bytes()[..offset].iter().filter(|&&b| b == b'\n').count() + 1
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    // Headings in the source order with their byte offsets. Texts of inline elements such as `code` are included in
+    // the heading text.
+    pub fn headings(&self) -> Vec<Heading> {
+        let mut headings = vec![];
+        let mut current = None;
+        for (event, range) in Parser::new_ext(&self.source, self.options).into_offset_iter() {
+            match event {
+                Event::Start(Tag::Heading { level, .. }) => {
+                    let (level, text, offset) = (level as u8, String::new(), range.start);
+                    current = Some(Heading { level, text, offset });
+                }
+                Event::End(TagEnd::Heading(_)) => headings.extend(current.take()),
+                Event::Text(text) | Event::Code(text) | Event::InlineMath(text) => {
+                    if let Some(heading) = &mut current {
+                        heading.text.push_str(&text);
+                    }
+                }
+                _ => {}
+            }
+        }
+        headings
+    }
+
+    pub fn metadata(&self) -> Option<Metadata> {
+        if !self.options.contains(Options::ENABLE_YAML_STYLE_METADATA_BLOCKS) {
+            return None;
+        }
+        Metadata::parse(&self.source)
+    }
+}
+
+fn parser_options(source: &str, config: &Markdown) -> Options {
+    let mut options = Options::empty();
+    for (enabled, option) in [
+        (config.tables, Options::ENABLE_TABLES),
+        (config.footnotes, Options::ENABLE_FOOTNOTES),
+        (config.strikethrough, Options::ENABLE_STRIKETHROUGH),
+        (config.task_lists, Options::ENABLE_TASKLISTS),
+        (config.math, Options::ENABLE_MATH),
+        (config.alerts, Options::ENABLE_GFM),
+        (config.definition_lists, Options::ENABLE_DEFINITION_LIST),
+        (config.superscript, Options::ENABLE_SUPERSCRIPT),
+        (config.subscript, Options::ENABLE_SUBSCRIPT),
+        (config.smart_punctuation, Options::ENABLE_SMART_PUNCTUATION),
+        (config.heading_attributes, Options::ENABLE_HEADING_ATTRIBUTES),
+        (config.wikilinks, Options::ENABLE_WIKILINKS),
+    ] {
+        options.set(option, enabled);
+    }
+    // pulldown-cmark recognizes metadata blocks not only at the beginning of document but also in the middle of it.
+    // It breaks documents which use `---` for thematic breaks and setext headings. Enable the options only when the
+    // document starts with front matter.
+    if config.front_matter && front_matter(source).is_some() {
+        options.insert(
+            Options::ENABLE_YAML_STYLE_METADATA_BLOCKS
+                | Options::ENABLE_PLUSES_DELIMITED_METADATA_BLOCKS,
+        );
+    }
+    options
+}
+
+pub(super) fn metadata_block_lang(kind: MetadataBlockKind) -> &'static str {
+    match kind {
+        MetadataBlockKind::YamlStyle => "yaml",
+        MetadataBlockKind::PlusesStyle => "toml",
+    }
 }
 
 pub struct MarkdownParser<'input, V: TextVisitor, T: TextTokenizer> {
     parser: Parser<'input>,
-    base_dir: &'input SlashPath,
+    content: &'input MarkdownContent,
     offset: Option<usize>,
     text_tokenizer: T,
     _phantom: PhantomData<V>,
@@ -97,18 +290,8 @@ impl<'input, V: TextVisitor, T: TextTokenizer> MarkdownParser<'input, V, T> {
     pub fn new(content: &'input MarkdownContent, offset: Option<usize>, text_tokenizer: T) -> Self {
         // Note: `MarkdownContent::modified_utf8_offset` guarantees that `offset` is on UTF-8 char boundary so we don't
         // need to check it here.
-        let mut options = Options::empty();
-        options.insert(
-            Options::ENABLE_STRIKETHROUGH
-                | Options::ENABLE_FOOTNOTES
-                | Options::ENABLE_TABLES
-                | Options::ENABLE_TASKLISTS
-                | Options::ENABLE_MATH
-                | Options::ENABLE_GFM,
-        );
-        let parser = Parser::new_ext(&content.source, options);
-        let base_dir = &content.base_dir;
-        Self { parser, base_dir, offset, text_tokenizer, _phantom: PhantomData }
+        let parser = Parser::new_ext(&content.source, content.options);
+        Self { parser, content, offset, text_tokenizer, _phantom: PhantomData }
     }
 }
 
@@ -119,7 +302,7 @@ impl<V: TextVisitor, T: TextTokenizer> RawMessageWriter for MarkdownParser<'_, V
 
     fn write_to(self, writer: impl Write) -> Result<Self::Output> {
         let mut enc =
-            RenderTreeEncoder::new(writer, self.base_dir, self.offset, self.text_tokenizer);
+            RenderTreeEncoder::new(writer, self.content, self.offset, self.text_tokenizer);
         enc.out.write_all(br#"JSON.parse('{"kind":"render_tree","tree":"#)?;
         enc.push(self.parser)?;
         enc.out.write_all(b"}')")?;
@@ -200,7 +383,7 @@ impl<W: Write> Write for StringContentEncoder<W> {
 
 // XXX: Items inside inline HTML are treated as raw texts
 // See src/markdown/testdata/inline_items_nested_in_inline_html.md
-struct InlineHtmlReader<'events, 'input, I: Iterator> {
+pub(super) struct InlineHtmlReader<'events, 'input, I: Iterator> {
     current: CowStr<'input>,
     index: usize,
     events: &'events mut Peekable<I>,
@@ -212,7 +395,7 @@ impl<'events, 'input, I> InlineHtmlReader<'events, 'input, I>
 where
     I: Iterator<Item = (Event<'input>, Range)>,
 {
-    fn new(current: CowStr<'input>, events: &'events mut Peekable<I>) -> Self {
+    pub(super) fn new(current: CowStr<'input>, events: &'events mut Peekable<I>) -> Self {
         let tag_stack = if current.starts_with("</") { 0 } else { 1 };
         Self { current, index: 0, events, tag_stack, child_stack: 0 }
     }
@@ -282,7 +465,7 @@ where
     }
 }
 
-struct HtmlBlockReader<'input, I: Iterator<Item = (Event<'input>, Range)>> {
+pub(super) struct HtmlBlockReader<'input, I: Iterator<Item = (Event<'input>, Range)>> {
     current: CowStr<'input>,
     index: usize,
     events: I,
@@ -290,7 +473,7 @@ struct HtmlBlockReader<'input, I: Iterator<Item = (Event<'input>, Range)>> {
 }
 
 impl<'input, I: Iterator<Item = (Event<'input>, Range)>> HtmlBlockReader<'input, I> {
-    fn new(events: I) -> Self {
+    pub(super) fn new(events: I) -> Self {
         Self { current: "".into(), index: 0, events, end: false }
     }
 
@@ -336,6 +519,8 @@ impl<'input, I: Iterator<Item = (Event<'input>, Range)>> Read for HtmlBlockReade
 struct RenderTreeEncoder<'input, W: Write, V: TextVisitor, T: TextTokenizer> {
     out: W,
     base_dir: &'input SlashPath,
+    wikilinks: &'input WikiLinkResolver,
+    diagrams: &'input DiagramRenderer,
     table: TableState,
     is_start: bool,
     ids: HashMap<CowStr<'input>, usize>,
@@ -344,13 +529,24 @@ struct RenderTreeEncoder<'input, W: Write, V: TextVisitor, T: TextTokenizer> {
     text_tokenizer: T,
     autolinker: Autolinker,
     sanitizer: Sanitizer<'input>,
+    slugger: Slugger,
+    heading: Option<HeadingText<'input>>,
+    scope: TextScope,
 }
 
 impl<'input, W: Write, V: TextVisitor, T: TextTokenizer> RenderTreeEncoder<'input, W, V, T> {
-    fn new(w: W, base_dir: &'input SlashPath, modified: Option<usize>, text_tokenizer: T) -> Self {
+    fn new(
+        w: W,
+        content: &'input MarkdownContent,
+        modified: Option<usize>,
+        text_tokenizer: T,
+    ) -> Self {
+        let base_dir = &content.base_dir;
         Self {
             out: w,
             base_dir,
+            wikilinks: &content.wikilinks,
+            diagrams: &content.diagrams,
             table: TableState::Head,
             is_start: true,
             ids: HashMap::new(),
@@ -359,6 +555,9 @@ impl<'input, W: Write, V: TextVisitor, T: TextTokenizer> RenderTreeEncoder<'inpu
             text_tokenizer,
             autolinker: Autolinker::default(),
             sanitizer: Sanitizer::new(base_dir),
+            slugger: Slugger::default(),
+            heading: None,
+            scope: TextScope::default(),
         }
     }
 
@@ -415,6 +614,12 @@ impl<'input, W: Write, V: TextVisitor, T: TextTokenizer> RenderTreeEncoder<'inpu
         write!(self.out, r#"{{"t":"{}""#, name)
     }
 
+    // Block element has the byte offset in the source as 'o' to know its source location
+    fn block(&mut self, name: &str, offset: usize) -> Result<()> {
+        self.tag(name)?;
+        write!(self.out, r#","o":{}"#, offset)
+    }
+
     fn text_tokens(&mut self, mut input: &str, mut range: Range) -> Result<()> {
         use TokenKind::*;
 
@@ -440,7 +645,7 @@ impl<'input, W: Write, V: TextVisitor, T: TextTokenizer> RenderTreeEncoder<'inpu
     }
 
     fn text(&mut self, text: &str, range: Range) -> Result<()> {
-        self.text_visitor.visit(text, &range);
+        self.text_visitor.visit(text, &range, &self.scope);
 
         let Some(offset) = self.modified else {
             return self.text_tokens(text, range);
@@ -459,7 +664,9 @@ impl<'input, W: Write, V: TextVisitor, T: TextTokenizer> RenderTreeEncoder<'inpu
             self.tag("modified")?;
             self.out.write_all(b"}")?;
             self.text_tokens(text, range)
-        } else if end == offset {
+        } else if end == offset || !text.is_char_boundary(offset - start) {
+            // When the text was replaced by the parser (e.g. "---" by "—" with smart punctuation), offsets in the text
+            // don't correspond to offsets in the source. It cannot be split at the modified offset.
             self.text_tokens(text, range)?;
             self.tag("modified")?;
             self.out.write_all(b"}")
@@ -513,7 +720,9 @@ impl<'input, W: Write, V: TextVisitor, T: TextTokenizer> RenderTreeEncoder<'inpu
             self.out.write_all(br#","auto":true,"href":"#)?;
             self.string(url)?;
             self.children_begin()?;
+            let in_link = std::mem::replace(&mut self.scope.link, true);
             self.text(url, start + s..start + e)?;
+            self.scope.link = in_link;
             self.tag_end()?;
 
             text = &text[e..];
@@ -534,15 +743,19 @@ impl<'input, W: Write, V: TextVisitor, T: TextTokenizer> RenderTreeEncoder<'inpu
 
         let mut events = parser.into_offset_iter().peekable();
         while let Some((event, range)) = events.next() {
+            if let Some(heading) = &mut self.heading {
+                heading.push_event(&event);
+            }
+
             match event {
                 Event::Start(tag) => {
                     use Tag::*;
                     match tag {
                         Paragraph => {
-                            self.tag("p")?;
+                            self.block("p", range.start)?;
                         }
-                        Heading { level, .. } => {
-                            self.tag("h")?;
+                        Heading { level, id, classes, .. } => {
+                            self.block("h", range.start)?;
 
                             let level: u8 = match level {
                                 HeadingLevel::H1 => 1,
@@ -553,9 +766,18 @@ impl<'input, W: Write, V: TextVisitor, T: TextTokenizer> RenderTreeEncoder<'inpu
                                 HeadingLevel::H6 => 6,
                             };
                             write!(self.out, r#","level":{}"#, level)?;
+                            self.scope.heading = Some(level);
+
+                            if !classes.is_empty() {
+                                self.out.write_all(br#","class":"#)?;
+                                self.string(&classes.join(" "))?;
+                            }
+
+                            // ID of the heading is written after its children since slug is generated from them
+                            self.heading = Some(HeadingText::new(id));
                         }
                         Table(alignments) => {
-                            self.tag("table")?;
+                            self.block("table", range.start)?;
 
                             self.out.write_all(br#","align":["#)?;
                             let mut alignments = alignments.into_iter();
@@ -572,11 +794,11 @@ impl<'input, W: Write, V: TextVisitor, T: TextTokenizer> RenderTreeEncoder<'inpu
                             self.table = TableState::Head;
                             self.tag("thead")?;
                             self.children_begin()?;
-                            self.tag("tr")?;
+                            self.block("tr", range.start)?;
                         }
                         TableRow => {
                             self.table = TableState::Row;
-                            self.tag("tr")?;
+                            self.block("tr", range.start)?;
                         }
                         TableCell => {
                             let tag = match self.table {
@@ -584,8 +806,9 @@ impl<'input, W: Write, V: TextVisitor, T: TextTokenizer> RenderTreeEncoder<'inpu
                                 TableState::Row => "td",
                             };
                             self.tag(tag)?;
+                            self.scope.table_cell = true;
                         }
-                        BlockQuote(None) => self.tag("blockquote")?,
+                        BlockQuote(None) => self.block("blockquote", range.start)?,
                         BlockQuote(Some(kind)) => {
                             let kind = match kind {
                                 BlockQuoteKind::Warning => "warning",
@@ -594,33 +817,49 @@ impl<'input, W: Write, V: TextVisitor, T: TextTokenizer> RenderTreeEncoder<'inpu
                                 BlockQuoteKind::Note => "note",
                                 BlockQuoteKind::Tip => "tip",
                             };
-                            self.tag("alert")?;
+                            self.block("alert", range.start)?;
                             write!(self.out, r#","kind":"{}""#, kind)?;
+                            self.scope.alert = Some(kind);
                         }
                         CodeBlock(info) => {
-                            self.tag("pre")?;
+                            let lang = match &info {
+                                CodeBlockKind::Fenced(info) => {
+                                    info.split(' ').next().filter(|l| !l.is_empty())
+                                }
+                                CodeBlockKind::Indented => None,
+                            };
+                            self.scope.code_block = true;
+                            self.scope.lang = lang.map(str::to_string);
+                            if let Some(lang) = lang
+                                && self.diagrams.is_diagram(lang)
+                            {
+                                // Unlike other tags, this consumes all events until `TagEnd::CodeBlock`
+                                self.diagram(lang, &mut events, range)?;
+                                self.scope.code_block = false;
+                                self.scope.lang = None;
+                                continue;
+                            }
+
+                            self.block("pre", range.start)?;
                             self.children_begin()?;
                             self.tag("code")?;
-                            if let CodeBlockKind::Fenced(info) = info
-                                && let Some(lang) = info.split(' ').next()
-                                && !lang.is_empty()
-                            {
+                            if let Some(lang) = lang {
                                 self.out.write_all(br#","lang":"#)?;
                                 self.string(lang)?;
                             }
                             in_code_block = true;
                         }
-                        List(Some(1)) => self.tag("ol")?,
+                        List(Some(1)) => self.block("ol", range.start)?,
                         List(Some(start)) => {
-                            self.tag("ol")?;
+                            self.block("ol", range.start)?;
                             write!(self.out, r#","start":{}"#, start)?;
                         }
-                        List(None) => self.tag("ul")?,
+                        List(None) => self.block("ul", range.start)?,
                         Item => {
                             if let Some((Event::TaskListMarker(_), _)) = events.peek() {
-                                self.tag("task-list")?;
+                                self.block("task-list", range.start)?;
                             } else {
-                                self.tag("li")?;
+                                self.block("li", range.start)?;
                             }
                         }
                         Emphasis => self.tag("em")?,
@@ -631,6 +870,19 @@ impl<'input, W: Write, V: TextVisitor, T: TextTokenizer> RenderTreeEncoder<'inpu
                             // Ignore autolink since it is linked by `Autolinker`
                             continue;
                         }
+                        Link { link_type: LinkType::WikiLink { .. }, dest_url, .. } => {
+                            if let Some(href) = self.wikilinks.resolve(&dest_url) {
+                                self.tag("a")?;
+                                self.out.write_all(br#","href":"#)?;
+                                self.string(&href)?;
+                            } else {
+                                self.tag("missing-page")?;
+                                self.out.write_all(br#","page":"#)?;
+                                self.string(page_name(&dest_url))?;
+                            }
+                            in_link = true;
+                            self.scope.link = true;
+                        }
                         Link { link_type, dest_url, title, .. } => {
                             self.tag("a")?;
 
@@ -650,6 +902,7 @@ impl<'input, W: Write, V: TextVisitor, T: TextTokenizer> RenderTreeEncoder<'inpu
                             }
 
                             in_link = true;
+                            self.scope.link = true;
                         }
                         Image { dest_url, title, .. } => {
                             self.tag("img")?;
@@ -663,7 +916,7 @@ impl<'input, W: Write, V: TextVisitor, T: TextTokenizer> RenderTreeEncoder<'inpu
                             self.rebase_link(&dest_url)?;
                         }
                         HtmlBlock => {
-                            self.tag("html")?;
+                            self.block("html", range.start)?;
                             self.out.write_all(br#","raw":""#)?;
 
                             let dst = StringContentEncoder(&mut self.out);
@@ -685,12 +938,32 @@ impl<'input, W: Write, V: TextVisitor, T: TextTokenizer> RenderTreeEncoder<'inpu
                             let id = self.id(name);
                             write!(self.out, r#","id":{}"#, id)?;
                         }
-                        DefinitionList
-                        | DefinitionListDefinition
-                        | DefinitionListTitle
-                        | MetadataBlock(_)
-                        | Superscript
-                        | Subscript => unreachable!("disabled markdown feature"),
+                        MetadataBlock(_) if range.start == 0 => {
+                            // Front matter is not rendered. It is sent to the window separately as `Metadata`.
+                            // Texts in the block are not visited so that they are not searched.
+                            for (event, _) in events.by_ref() {
+                                if let Event::End(TagEnd::MetadataBlock(_)) = event {
+                                    break;
+                                }
+                            }
+                            continue;
+                        }
+                        MetadataBlock(kind) => {
+                            // Metadata blocks in the middle of document are rendered as code blocks
+                            self.block("pre", range.start)?;
+                            self.children_begin()?;
+                            self.tag("code")?;
+                            let lang = metadata_block_lang(kind);
+                            write!(self.out, r#","lang":"{}""#, lang)?;
+                            in_code_block = true;
+                            self.scope.code_block = true;
+                            self.scope.lang = Some(lang.to_string());
+                        }
+                        DefinitionList => self.block("dl", range.start)?,
+                        DefinitionListTitle => self.block("dt", range.start)?,
+                        DefinitionListDefinition => self.block("dd", range.start)?,
+                        Superscript => self.tag("sup")?,
+                        Subscript => self.tag("sub")?,
                     }
 
                     // Tag element must have its children (maybe empty)
@@ -702,14 +975,44 @@ impl<'input, W: Write, V: TextVisitor, T: TextTokenizer> RenderTreeEncoder<'inpu
                         Link if in_auto_link => in_auto_link = false,
                         Link => {
                             in_link = false;
+                            self.scope.link = false;
                             self.tag_end()?
                         }
-                        Paragraph | Heading(_) | TableRow | TableCell | BlockQuote(_) | List(_)
-                        | Item | Emphasis | Strong | Strikethrough | Image | FootnoteDefinition => {
-                            self.tag_end()?
+                        Heading(_) => {
+                            self.scope.heading = None;
+                            let id = self.slugger.id(self.heading.take().unwrap_or_default());
+                            self.out.write_all(br#"],"id":"#)?;
+                            self.string(&id)?;
+                            self.out.write_all(b"}")?;
+                            self.is_start = false;
                         }
-                        CodeBlock => {
+                        TableCell => {
+                            self.scope.table_cell = false;
+                            self.tag_end()?;
+                        }
+                        BlockQuote(Some(_)) => {
+                            self.scope.alert = None;
+                            self.tag_end()?;
+                        }
+                        Paragraph
+                        | TableRow
+                        | BlockQuote(None)
+                        | List(_)
+                        | Item
+                        | Emphasis
+                        | Strong
+                        | Strikethrough
+                        | Image
+                        | FootnoteDefinition
+                        | DefinitionList
+                        | DefinitionListTitle
+                        | DefinitionListDefinition
+                        | Superscript
+                        | Subscript => self.tag_end()?,
+                        CodeBlock | MetadataBlock(_) => {
                             in_code_block = false;
+                            self.scope.code_block = false;
+                            self.scope.lang = None;
                             self.tag_end()?;
                             self.tag_end()?;
                         }
@@ -724,12 +1027,6 @@ impl<'input, W: Write, V: TextVisitor, T: TextTokenizer> RenderTreeEncoder<'inpu
                             self.children_begin()?;
                         }
                         HtmlBlock => unreachable!(), // This event is handled in `Tag::HtmlBlock` event using `HtmlBlockReader`
-                        DefinitionList
-                        | DefinitionListDefinition
-                        | DefinitionListTitle
-                        | MetadataBlock(_)
-                        | Superscript
-                        | Subscript => unreachable!("disabled markdown feature"), // This option is not enabled
                     }
                 }
                 Event::Text(text) if in_code_block || in_link => self.text(&text, range)?,
@@ -768,7 +1065,7 @@ impl<'input, W: Write, V: TextVisitor, T: TextTokenizer> RenderTreeEncoder<'inpu
                     self.out.write_all(b"}")?;
                 }
                 Event::Rule => {
-                    self.tag("hr")?;
+                    self.block("hr", range.start)?;
                     self.out.write_all(b"}")?;
                 }
                 Event::FootnoteReference(name) => {
@@ -798,6 +1095,67 @@ impl<'input, W: Write, V: TextVisitor, T: TextTokenizer> RenderTreeEncoder<'inpu
         Ok(())
     }
 
+    fn diagram(
+        &mut self,
+        lang: &str,
+        events: impl Iterator<Item = (Event<'input>, Range)>,
+        range: Range,
+    ) -> Result<()> {
+        let mut texts = vec![];
+        for (event, range) in events {
+            match event {
+                Event::Text(text) => texts.push((text, range)),
+                Event::End(TagEnd::CodeBlock) => break,
+                _ => {}
+            }
+        }
+        let source: String = texts.iter().map(|(text, _)| text.as_ref()).collect();
+
+        match self.diagrams.render_in_background(lang, &source) {
+            Diagram::Svg { url, .. } => {
+                // Texts in the diagram are not rendered. Put the modified marker on the diagram instead.
+                if let Some(offset) = self.modified
+                    && offset <= range.end
+                {
+                    self.modified = None;
+                    self.tag("modified")?;
+                    self.out.write_all(b"}")?;
+                }
+                self.block("diagram", range.start)?;
+                self.out.write_all(br#","lang":"#)?;
+                self.string(lang)?;
+                self.out.write_all(br#","src":"#)?;
+                self.string(&url)?;
+                self.out.write_all(b"}")
+            }
+            Diagram::Error(message) => {
+                self.block("diagram-error", range.start)?;
+                self.out.write_all(br#","lang":"#)?;
+                self.string(lang)?;
+                self.out.write_all(br#","message":"#)?;
+                self.string(&message)?;
+                self.children_begin()?;
+                for (text, range) in texts {
+                    self.text(&text, range)?;
+                }
+                self.tag_end()
+            }
+            Diagram::Unavailable | Diagram::Pending => {
+                self.block("pre", range.start)?;
+                self.children_begin()?;
+                self.tag("code")?;
+                self.out.write_all(br#","lang":"#)?;
+                self.string(lang)?;
+                self.children_begin()?;
+                for (text, range) in texts {
+                    self.text(&text, range)?;
+                }
+                self.tag_end()?;
+                self.tag_end()
+            }
+        }
+    }
+
     fn rebase_link(&mut self, dest: &str) -> Result<()> {
         if !should_rebase_url(dest) {
             return self.string(dest);
@@ -855,7 +1213,7 @@ impl UrlCharKind {
     }
 }
 
-struct Autolinker(AhoCorasick);
+pub(super) struct Autolinker(AhoCorasick);
 
 impl Default for Autolinker {
     fn default() -> Self {
@@ -864,7 +1222,7 @@ impl Default for Autolinker {
 }
 
 impl Autolinker {
-    fn find_autolink(&self, text: &str) -> Option<(usize, usize)> {
+    pub(super) fn find_autolink(&self, text: &str) -> Option<(usize, usize)> {
         for mat in self.0.find_iter(text) {
             let (start, scheme_end) = (mat.start(), mat.end());
             if let Some(c) = text[..start].chars().next_back()
@@ -893,19 +1251,19 @@ impl Autolinker {
 }
 
 #[derive(Debug)]
-enum EmojiToken<'input> {
+pub(super) enum EmojiToken<'input> {
     Text(&'input str),
     Emoji(&'static Emoji, usize),
 }
 
-struct EmojiTokenizer<'input> {
+pub(super) struct EmojiTokenizer<'input> {
     text: &'input str,
     iter: Memchr<'input>,
     start: usize,
 }
 
 impl<'input> EmojiTokenizer<'input> {
-    fn new(text: &'input str) -> Self {
+    pub(super) fn new(text: &'input str) -> Self {
         Self { iter: memchr_iter(b':', text.as_bytes()), text, start: 0 }
     }
 
@@ -961,7 +1319,11 @@ impl<'input> Iterator for EmojiTokenizer<'input> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::SearchMatcher;
+    use crate::markdown::DisplayText;
+    use crate::test::commonmark;
     use std::fs;
+    use std::io;
     use std::path::PathBuf;
 
     fn load_data(name: &str) -> String {
@@ -975,26 +1337,41 @@ mod tests {
         }
     }
 
+    fn all_extensions() -> Markdown {
+        Markdown {
+            smart_punctuation: true,
+            heading_attributes: true,
+            wikilinks: true,
+            ..Default::default()
+        }
+    }
+
+    fn render_tree(target: &MarkdownContent, offset: Option<usize>) -> serde_json::Value {
+        let parser = MarkdownParser::new(target, offset, ());
+        let mut buf = Vec::new();
+        let () = parser.write_to(&mut buf).unwrap();
+        let buf = String::from_utf8(buf).unwrap();
+        // Revert extra escape for '...' JavaScript string
+        let buf = buf.replace("\\\\", "\\").replace("\\'", "'");
+        // Remove the `JSON.parse` call to restore JSON value passed to the function
+        let buf = buf.strip_prefix("JSON.parse('").unwrap();
+        let buf = buf.strip_suffix("')").unwrap();
+        // Check if the written output is in the valid JSON format
+        serde_json::from_str(buf).unwrap()
+    }
+
     macro_rules! snapshot_test {
-        ($name:ident, $offset:expr, $basedir:expr) => {
+        ($name:ident, $offset:expr, $basedir:expr, $config:expr) => {
             #[test]
             fn $name() {
                 let source = load_data(stringify!($name));
-                let target = MarkdownContent::new(source, $basedir);
-                let parser = MarkdownParser::new(&target, $offset, ());
-                let mut buf = Vec::new();
-                let () = parser.write_to(&mut buf).unwrap();
-                let buf = String::from_utf8(buf).unwrap();
-                // Revert extra escape for '...' JavaScript string
-                let buf = buf.replace("\\\\", "\\").replace("\\'", "'");
-                // Remove the `JSON.parse` call to restore JSON value passed to the function
-                let buf = buf.strip_prefix("JSON.parse('").unwrap();
-                let buf = buf.strip_suffix("')").unwrap();
-                // Check if the written output is in the valid JSON format
-                let json: serde_json::Value = serde_json::from_str(buf).unwrap();
-                insta::assert_json_snapshot!(json);
+                let target = MarkdownContent::new(source, $basedir, &$config);
+                insta::assert_json_snapshot!(render_tree(&target, $offset));
             }
         };
+        ($name:ident, $offset:expr, $basedir:expr) => {
+            snapshot_test!($name, $offset, $basedir, Markdown::default());
+        };
         ($name:ident) => {
             snapshot_test!($name, None, None);
         };
@@ -1036,6 +1413,14 @@ mod tests {
     snapshot_test!(empty);
     snapshot_test!(non_printable);
     snapshot_test!(mathjax_extensions);
+    snapshot_test!(front_matter_yaml);
+    snapshot_test!(front_matter_toml);
+    snapshot_test!(not_front_matter);
+    snapshot_test!(definition_list);
+    snapshot_test!(superscript_subscript);
+    snapshot_test!(heading_ids);
+    snapshot_test!(markdown_extensions, None, None, all_extensions());
+    snapshot_test!(wikilinks, None, Some(Path::new("src/markdown/testdata")), all_extensions());
 
     // Offset
     snapshot_test!(offset_block, Some(30));
@@ -1050,11 +1435,182 @@ mod tests {
     const BASE_DIR: &str = "/a/b/c/d/e";
     snapshot_test!(relative_links, None, Some(Path::new(BASE_DIR)));
 
+    #[test]
+    fn render_with_any_config() {
+        let dir = fs::read_dir(Path::new("src").join("markdown").join("testdata")).unwrap();
+        for entry in dir {
+            let path = entry.unwrap().path();
+            let source = fs::read_to_string(&path).unwrap();
+            for config in [Markdown::default(), commonmark(), all_extensions()] {
+                let target = MarkdownContent::new(source.clone(), None, &config);
+                let step = source.len() / 16 + 1;
+                let offsets =
+                    (0..source.len()).step_by(step).map(|i| source.floor_char_boundary(i));
+                for offset in [None].into_iter().chain(offsets.map(Some)) {
+                    let parser = MarkdownParser::new(&target, offset, ());
+                    let _: () = parser.write_to(io::sink()).unwrap();
+                }
+
+                // Render search matches
+                let parser = MarkdownParser::new(&target, None, ());
+                let text: DisplayText = parser.write_to(io::sink()).unwrap();
+                let matches = text.search("e", SearchMatcher::CaseSensitive).unwrap();
+                if let Some(tokenizer) = matches.tokenizer(Some(0)) {
+                    let parser = MarkdownParser::new(&target, None, tokenizer);
+                    let _: () = parser.write_to(io::sink()).unwrap();
+                }
+            }
+        }
+    }
+
+    fn diagram_commands() -> HashMap<String, Vec<String>> {
+        [
+            ("svg", &["cat"][..]),
+            ("fail", &["sh", "-c", "echo 'syntax error' >&2; exit 1"]),
+            ("missing", &["this-command-does-not-exist"]),
+        ]
+        .into_iter()
+        .map(|(lang, cmd)| (lang.to_string(), cmd.iter().map(|s| s.to_string()).collect()))
+        .collect()
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    #[test]
+    fn diagrams() {
+        let tmp = tempfile::tempdir().unwrap();
+        let target = MarkdownContent::new(load_data("diagrams"), None, &Markdown::default())
+            .with_diagrams(diagram_commands(), &DataDir::new(tmp.path()));
+
+        // Code blocks are rendered as-is until the diagrams are rendered in background
+        let tree = render_tree(&target, None).to_string();
+        assert!(!tree.contains(r#""diagram""#), "{tree}");
+        for job in target.diagram_jobs() {
+            job.run();
+        }
+        assert!(target.diagram_jobs().is_empty());
+        insta::assert_json_snapshot!(render_tree(&target, None));
+        assert!(target.diagram_jobs().is_empty());
+
+        // Modified offset in the rendered diagram
+        let offset = target.source.find("<circle").unwrap();
+        let tree = render_tree(&target, Some(offset)).to_string();
+        let (marker, diagram) = (tree.find(r#""modified""#), tree.find(r#""diagram""#));
+        assert!(marker.is_some() && marker < diagram, "{tree}");
+    }
+
+    #[test]
+    fn block_at_line() {
+        let source = [
+            "---",            // 1
+            "title: Hello",   // 2
+            "---",            // 3
+            "# Heading",      // 4
+            "",               // 5
+            "paragraph",      // 6
+            "continued[^1]",  // 7
+            "",               // 8
+            "[^1]: footnote", // 9
+            "",               // 10
+            "- item 1",       // 11
+            "- item 2",       // 12
+            "",               // 13
+            "---",            // 14
+            "",               // 15
+            "```",            // 16
+            "code",           // 17
+            "```",            // 18
+        ]
+        .join("\n");
+        let config = Markdown { front_matter: true, ..Default::default() };
+        let content = MarkdownContent::new(source, None, &config);
+        for (line, want) in [
+            (0, 0),
+            (1, 0),
+            (3, 0),
+            (4, 0),
+            (5, 1),
+            (6, 1),
+            (7, 1),
+            (9, 2),
+            (12, 2),
+            (14, 3),
+            (17, 4),
+            (100, 4),
+        ] {
+            assert_eq!(content.block_at_line(line), Some(want), "line {line}");
+        }
+
+        let content = MarkdownContent::new(String::new(), None, &config);
+        assert_eq!(content.block_at_line(1), None);
+    }
+
+    #[test]
+    fn block_lines() {
+        let source = [
+            "---",           // 1
+            "title: Hello",  // 2
+            "---",           // 3
+            "# Heading",     // 4
+            "",              // 5
+            "paragraph",     // 6
+            "continued[^1]", // 7
+            "",              // 8
+            "[^1]: note",    // 9
+            "",              // 10
+            "- item 1",      // 11
+            "- item 2",      // 12
+            "---",           // 13
+            "```",           // 14
+            "code",          // 15
+            "```",           // 16
+        ]
+        .join("\n");
+        let config = Markdown { front_matter: true, ..Default::default() };
+        let content = MarkdownContent::new(source, None, &config);
+        assert_eq!(content.block_lines(), [(4, 4), (6, 7), (11, 12), (13, 13), (14, 16)]);
+
+        let content = MarkdownContent::new(String::new(), None, &config);
+        assert_eq!(content.block_lines(), []);
+    }
+
+    #[test]
+    fn line_at_offset() {
+        let content = MarkdownContent::new("a\n\nb\r\nc".into(), None, &Markdown::default());
+        for (offset, want) in [(0, 1), (1, 1), (2, 2), (3, 3), (5, 3), (6, 4), (7, 4), (100, 4)] {
+            assert_eq!(content.line_at(offset), want, "offset {offset}");
+        }
+    }
+
+    #[test]
+    fn collect_headings() {
+        let source = "# Title\n\ntext\n\n## `code` and *emph*\n\nSetext\n------\n\n> # Quoted\n";
+        let content = MarkdownContent::new(source.into(), None, &Markdown::default());
+        let have: Vec<_> =
+            content.headings().into_iter().map(|h| (h.level, h.text, h.offset)).collect();
+        let want = [
+            (1, "Title".to_string(), 0),
+            (2, "code and emph".to_string(), 15),
+            (2, "Setext".to_string(), 37),
+            (1, "Quoted".to_string(), 54),
+        ];
+        assert_eq!(have, want);
+    }
+
+    #[test]
+    fn front_matter_disabled_by_config() {
+        let source = "---\ntitle: Hello\n---\n";
+        let content = MarkdownContent::new(source.into(), None, &Markdown::default());
+        assert!(content.metadata().is_some());
+        let config = Markdown { front_matter: false, ..Default::default() };
+        let content = MarkdownContent::new(source.into(), None, &config);
+        assert_eq!(content.metadata(), None);
+    }
+
     // Note: This test cannot be done by snapshot_test! since JSON parser complains the escaped single quote.
     #[test]
     fn escaped_characters_in_text() {
         let source = load_data("escaped_chars_in_text");
-        let target = MarkdownContent::new(source, None);
+        let target = MarkdownContent::new(source, None, &Markdown::default());
         let parser = MarkdownParser::new(&target, None, ());
         let mut buf = Vec::new();
         let () = parser.write_to(&mut buf).unwrap();
@@ -1064,14 +1620,13 @@ mod tests {
 
     mod visitor {
         use super::*;
-        use crate::markdown::DisplayText;
 
         macro_rules! snapshot_test {
             ($name:ident) => {
                 #[test]
                 fn $name() {
                     let source = load_data(stringify!($name));
-                    let content = MarkdownContent::new(source, None);
+                    let content = MarkdownContent::new(source, None, &Markdown::default());
                     let parser = MarkdownParser::new(&content, None, ());
                     let mut buf = Vec::new();
                     let visitor: DisplayText = parser.write_to(&mut buf).unwrap();
@@ -1113,6 +1668,9 @@ mod tests {
         snapshot_test!(footnotes);
         snapshot_test!(highlight);
         snapshot_test!(not_link);
+        snapshot_test!(front_matter_yaml);
+        snapshot_test!(definition_list);
+        snapshot_test!(superscript_subscript);
     }
 
     #[test]
@@ -1229,8 +1787,8 @@ mod tests {
             ("あいう", "あいう", None),
             ("", "", None),
         ] {
-            let prev = MarkdownContent::new(before.into(), None);
-            let now = MarkdownContent::new(after.into(), None);
+            let prev = MarkdownContent::new(before.into(), None, &Markdown::default());
+            let now = MarkdownContent::new(after.into(), None, &Markdown::default());
             let offset = prev.modified_utf8_offset(&now);
             assert_eq!(offset, expected, "{before:?}, {after:?}");
         }
@@ -1238,20 +1796,20 @@ mod tests {
 
     #[test]
     fn text_event_inside_html_block() {
-        let target = MarkdownContent::new(" <p>foo</p>".to_string(), None);
+        let target = MarkdownContent::new(" <p>foo</p>".to_string(), None, &Markdown::default());
         let parser = MarkdownParser::new(&target, None, ());
         let mut buf = Vec::new();
         let () = parser.write_to(&mut buf).unwrap();
         let buf = String::from_utf8(buf).unwrap();
         assert!(
-            buf.contains(r#"{"t":"html","raw":" <p>foo</p>"}"#),
+            buf.contains(r#"{"t":"html","o":1,"raw":" <p>foo</p>"}"#),
             "expected HTML block is not contained: {buf:?}",
         );
     }
 
     #[test]
     fn replace_nul_with_fffd() {
-        let target = MarkdownContent::new("\0 `\0` $\0$".to_string(), None);
+        let target = MarkdownContent::new("\0 `\0` $\0$".to_string(), None, &Markdown::default());
         let parser = MarkdownParser::new(&target, None, ());
         let mut buf = Vec::new();
         let () = parser.write_to(&mut buf).unwrap();
@@ -1263,14 +1821,14 @@ mod tests {
     #[test]
     fn encode_special_chars() {
         let src = str::from_utf8(b"\x08\x22\x27\x5c").unwrap();
-        let target = MarkdownContent::new(src.to_string(), None);
+        let target = MarkdownContent::new(src.to_string(), None, &Markdown::default());
         let parser = MarkdownParser::new(&target, None, ());
         let mut buf = Vec::new();
         let () = parser.write_to(&mut buf).unwrap();
         let buf = String::from_utf8(buf).unwrap();
         // \t, \n, \f, \r are eaten by Markdown parser so they don't appear in the output
         assert!(
-            buf.contains(r#"{"t":"p","c":["\\b\\"\'\\\\"]}"#),
+            buf.contains(r#"{"t":"p","o":0,"c":["\\b\\"\'\\\\"]}"#),
             "expected escaped special characters in {buf:?}",
         );
     }
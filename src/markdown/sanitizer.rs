@@ -80,22 +80,28 @@ impl<'a> UrlRelativeEvaluate<'a> for RebaseUrl<'a> {
 const ALLOWED_ATTRIBUTES: &[&str] = &["name", "id"];
 
 pub struct Sanitizer<'a> {
-    base_dir: &'a SlashPath,
+    base_dir: Option<&'a SlashPath>,
     cleaner: OnceCell<Builder<'a>>,
 }
 
 impl<'a> Sanitizer<'a> {
     pub fn new(base_dir: &'a SlashPath) -> Self {
-        Self { base_dir, cleaner: OnceCell::new() }
+        Self { base_dir: Some(base_dir), cleaner: OnceCell::new() }
+    }
+
+    // Relative URLs are kept as-is
+    pub fn without_rebase() -> Self {
+        Self { base_dir: None, cleaner: OnceCell::new() }
     }
 
     pub fn clean<W: Write, R: Read>(&self, out: W, reader: R) -> Result<()> {
         let cleaner = self.cleaner.get_or_init(|| {
-            let eval = Box::new(RebaseUrl { prefix: self.base_dir });
+            let relative = match self.base_dir {
+                Some(prefix) => UrlRelative::Custom(Box::new(RebaseUrl { prefix })),
+                None => UrlRelative::PassThrough,
+            };
             let mut builder = Builder::default();
-            builder
-                .add_generic_attributes(ALLOWED_ATTRIBUTES)
-                .url_relative(UrlRelative::Custom(eval));
+            builder.add_generic_attributes(ALLOWED_ATTRIBUTES).url_relative(relative);
             builder
         });
         cleaner.clean_from_reader(reader)?.write_to(out)
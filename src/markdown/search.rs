@@ -1,10 +1,15 @@
-use super::parser::{Range, TextTokenizer, TextVisitor, TokenKind};
+use super::parser::{Range, TextScope, TextTokenizer, TextVisitor, TokenKind};
 use crate::config::SearchMatcher;
 use aho_corasick::{
     AhoCorasick, AhoCorasickBuilder, FindIter as AhoCorasickFindIter, Match as AhoCorasickMatch,
 };
 use anyhow::Result;
 use regex::{Match as RegexMatch, Matches as RegexMatches, Regex, RegexBuilder};
+use serde::Serialize;
+use std::vec::IntoIter as VecIntoIter;
+
+// Max number of characters between two characters matched by fuzzy search
+const FUZZY_MAX_GAP: usize = 8;
 
 trait MatchPosition {
     fn start(&self) -> usize;
@@ -29,6 +34,15 @@ impl MatchPosition for RegexMatch<'_> {
     }
 }
 
+impl MatchPosition for Range {
+    fn start(&self) -> usize {
+        self.start
+    }
+    fn end(&self) -> usize {
+        self.end
+    }
+}
+
 trait Searcher: Sized {
     type Match<'text>: MatchPosition;
     type Iter<'me, 'text>: Iterator<Item = Self::Match<'text>>
@@ -64,14 +78,193 @@ impl Searcher for Regex {
     }
 }
 
+// Matches the query only when it is not a part of another word. `\b` is not put at the edge of the query which is not a
+// word character (e.g. `foo()`) since `\b` would require a word character next to it.
+struct WholeWord(Regex);
+
+impl Searcher for WholeWord {
+    type Match<'text> = RegexMatch<'text>;
+    type Iter<'me, 'text> = RegexMatches<'me, 'text>;
+
+    fn new(query: &str, ignore_case: bool) -> Result<Self> {
+        let is_word = |c: char| c.is_alphanumeric() || c == '_';
+        let mut pattern = String::new();
+        if query.starts_with(is_word) {
+            pattern.push_str(r"\b");
+        }
+        pattern.push_str(&regex::escape(query));
+        if query.ends_with(is_word) {
+            pattern.push_str(r"\b");
+        }
+        Ok(Self(RegexBuilder::new(&pattern).case_insensitive(ignore_case).build()?))
+    }
+
+    fn find_iter<'me, 'text>(&'me self, text: &'text str) -> Self::Iter<'me, 'text> {
+        self.0.find_iter(text)
+    }
+}
+
+// Matches the characters of the query in order with at most `FUZZY_MAX_GAP` characters between them. Whitespaces in
+// the query are ignored. When candidates overlap, more compact one is ranked higher and chosen.
+struct Fuzzy {
+    chars: Vec<char>,
+    ignore_case: bool,
+}
+
+impl Fuzzy {
+    fn eq(&self, q: char, c: char) -> bool {
+        q == c || self.ignore_case && q.to_lowercase().eq(c.to_lowercase())
+    }
+
+    // Index of the last character of the alignment which starts at `start`
+    fn find_end(&self, text: &[(usize, char)], start: usize) -> Option<usize> {
+        let mut pos = start;
+        for &q in &self.chars[1..] {
+            let last = (pos + FUZZY_MAX_GAP + 1).min(text.len() - 1);
+            pos = (pos + 1..=last).find(|&i| self.eq(q, text[i].1))?;
+        }
+        Some(pos)
+    }
+
+    // Index of the first character of the most compact alignment which ends at `end`
+    fn find_start(&self, text: &[(usize, char)], end: usize) -> usize {
+        let mut pos = end;
+        for &q in self.chars[..self.chars.len() - 1].iter().rev() {
+            pos = (0..pos).rev().find(|&i| self.eq(q, text[i].1)).unwrap_or(pos);
+        }
+        pos
+    }
+}
+
+impl Searcher for Fuzzy {
+    type Match<'text> = Range;
+    type Iter<'me, 'text> = VecIntoIter<Range>;
+
+    fn new(query: &str, ignore_case: bool) -> Result<Self> {
+        let chars = query.chars().filter(|c| !c.is_whitespace()).collect();
+        Ok(Self { chars, ignore_case })
+    }
+
+    fn find_iter<'me, 'text>(&'me self, text: &'text str) -> Self::Iter<'me, 'text> {
+        let Some(&first) = self.chars.first() else {
+            return vec![].into_iter();
+        };
+        let text: Vec<_> = text.char_indices().collect();
+
+        let mut candidates = vec![];
+        for (i, &(_, c)) in text.iter().enumerate() {
+            if self.eq(first, c)
+                && let Some(end) = self.find_end(&text, i)
+            {
+                candidates.push((self.find_start(&text, end), end));
+            }
+        }
+        candidates.dedup();
+
+        candidates.sort_by_key(|&(start, end)| (end - start, start));
+        let mut used = vec![false; text.len()];
+        let mut chosen = vec![];
+        for (start, end) in candidates {
+            if !used[start..=end].contains(&true) {
+                used[start..=end].fill(true);
+                chosen.push((start, end));
+            }
+        }
+        chosen.sort_unstable();
+
+        chosen
+            .into_iter()
+            .map(|(start, end)| {
+                let (last, c) = text[end];
+                text[start].0..last + c.len_utf8()
+            })
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+}
+
+// Qualifier to narrow down the search matches to the specific structure of the document
+#[derive(Clone, PartialEq, Eq, Debug)]
+enum Qualifier {
+    Heading(Option<u8>),   // `h:` or `h1:` ... `h6:`
+    Code(Option<String>),  // `code:` or `code.rust:`
+    Table,                 // `table:`
+    Link,                  // `link:`
+    Alert(Option<String>), // `alert:` or `alert.note:`
+}
+
+impl Qualifier {
+    fn parse(s: &str) -> Option<Self> {
+        let (name, arg) = match s.split_once('.') {
+            Some((name, arg)) if !arg.is_empty() => (name, Some(arg)),
+            Some(_) => return None,
+            None => (s, None),
+        };
+        let qualifier = match (name, arg) {
+            ("h", None) => Self::Heading(None),
+            ("h1" | "h2" | "h3" | "h4" | "h5" | "h6", None) => {
+                Self::Heading(Some(name.as_bytes()[1] - b'0'))
+            }
+            ("code", arg) => Self::Code(arg.map(str::to_string)),
+            ("table", None) => Self::Table,
+            ("link", None) => Self::Link,
+            ("alert", arg) => Self::Alert(arg.map(str::to_string)),
+            _ => return None,
+        };
+        Some(qualifier)
+    }
+
+    fn matches(&self, scope: &TextScope) -> bool {
+        let eq = |want: &Option<String>, have: Option<&str>| match (want, have) {
+            (None, _) => true,
+            (Some(want), Some(have)) => want.eq_ignore_ascii_case(have),
+            (Some(_), None) => false,
+        };
+        match self {
+            Self::Heading(None) => scope.heading.is_some(),
+            Self::Heading(level) => scope.heading == *level,
+            Self::Code(lang) => scope.code_block && eq(lang, scope.lang.as_deref()),
+            Self::Table => scope.table_cell,
+            Self::Link => scope.link,
+            Self::Alert(kind) => scope.alert.is_some() && eq(kind, scope.alert),
+        }
+    }
+}
+
+// Search query with the qualifiers at the start such as `h2:install` or `code.rust:table:unwrap`. The rest of the
+// query is searched with the matcher. A prefix which is not a known qualifier is a part of the query (e.g. `http:`).
+pub struct ScopedQuery<'a> {
+    qualifiers: Vec<Qualifier>,
+    text: &'a str,
+}
+
+impl<'a> ScopedQuery<'a> {
+    pub fn parse(mut query: &'a str) -> Self {
+        let mut qualifiers = vec![];
+        while let Some((prefix, rest)) = query.split_once(':')
+            && let Some(qualifier) = Qualifier::parse(prefix)
+        {
+            qualifiers.push(qualifier);
+            query = rest;
+        }
+        Self { qualifiers, text: query }
+    }
+
+    // The query without the qualifiers
+    pub fn text(&self) -> &'a str {
+        self.text
+    }
+}
+
 #[derive(Default)]
 pub struct DisplayText {
     text: String,
     srcmap: Vec<Range>,
+    scopes: Vec<(Range, TextScope)>, // Ranges in the source which are sorted and not overlapping
 }
 
 impl TextVisitor for DisplayText {
-    fn visit(&mut self, text: &str, range: &Range) {
+    fn visit(&mut self, text: &str, range: &Range, scope: &TextScope) {
         self.text.push_str(text);
         match self.srcmap.last_mut() {
             Some(last) if last.end == range.start => {
@@ -79,6 +272,12 @@ impl TextVisitor for DisplayText {
             }
             _ => self.srcmap.push(range.clone()),
         }
+        match self.scopes.last_mut() {
+            Some((last, s)) if s == scope => {
+                last.end = range.end;
+            }
+            _ => self.scopes.push((range.clone(), scope.clone())),
+        }
     }
 }
 
@@ -103,12 +302,42 @@ impl DisplayText {
         Ok(SearchMatches(matches))
     }
 
+    // Drop the matches which are not entirely in the structures specified by the qualifiers
+    fn filter_scoped(&self, matches: SearchMatches, qualifiers: &[Qualifier]) -> SearchMatches {
+        let mut scopes = self.scopes.as_slice();
+        let mut filtered = matches.0;
+        filtered.retain(|mat| {
+            while let Some(((range, _), rest)) = scopes.split_first()
+                && range.end <= mat.start
+            {
+                scopes = rest;
+            }
+            let mut overlaps =
+                scopes.iter().take_while(|(range, _)| range.start < mat.end).peekable();
+            overlaps.peek().is_some()
+                && overlaps.all(|(_, scope)| qualifiers.iter().all(|q| q.matches(scope)))
+        });
+        SearchMatches(filtered)
+    }
+
     pub fn search(&self, query: &str, matcher: SearchMatcher) -> Result<SearchMatches> {
+        let query = ScopedQuery::parse(query);
+        if query.text.is_empty() {
+            return Ok(SearchMatches::default());
+        }
+        let matches = self.search_text(query.text, matcher)?;
+        if query.qualifiers.is_empty() {
+            return Ok(matches);
+        }
+        Ok(self.filter_scoped(matches, &query.qualifiers))
+    }
+
+    fn search_text(&self, query: &str, matcher: SearchMatcher) -> Result<SearchMatches> {
         use SearchMatcher::*;
 
         let ignore_case = match matcher {
-            SmartCase => !query.chars().any(|c| c.is_ascii_uppercase()),
-            CaseInsensitive => true,
+            SmartCase | WholeWord | Fuzzy => !query.chars().any(|c| c.is_ascii_uppercase()),
+            CaseInsensitive | CaseInsensitiveRegex => true,
             CaseSensitive | CaseSensitiveRegex => false,
         };
 
@@ -116,11 +345,66 @@ impl DisplayText {
             SmartCase | CaseInsensitive | CaseSensitive => {
                 self.search_with::<AhoCorasick>(query, ignore_case)
             }
-            CaseSensitiveRegex => self.search_with::<Regex>(query, ignore_case),
+            WholeWord => self.search_with::<self::WholeWord>(query, ignore_case),
+            Fuzzy => self.search_with::<self::Fuzzy>(query, ignore_case),
+            CaseSensitiveRegex | CaseInsensitiveRegex => {
+                self.search_with::<Regex>(query, ignore_case)
+            }
         }
     }
 
-    #[cfg(test)]
+    // Check the match is mapped to a contiguous text in the source. A match across some markups like "foo **bar**" is
+    // not contiguous and replacing it would break the markups.
+    fn is_contiguous(&self, range: &Range) -> bool {
+        let idx = self.srcmap.partition_point(|r| r.start <= range.start);
+        idx > 0 && range.end <= self.srcmap[idx - 1].end
+    }
+
+    // Replace the matches in the source. The lines containing the replaced matches are collected as hunks for preview.
+    pub fn replace(&self, source: &str, matches: &SearchMatches, replacement: &str) -> Replaced {
+        let line_end =
+            |offset: usize| source[offset..].find('\n').map_or(source.len(), |i| offset + i);
+        let (targets, skipped): (Vec<_>, Vec<_>) =
+            matches.ranges().iter().partition(|r| self.is_contiguous(r));
+
+        let mut replaced = String::with_capacity(source.len());
+        let mut hunks = vec![];
+        let (mut copied, mut line, mut counted) = (0, 1, 0);
+        let mut targets = targets.as_slice();
+        while let Some(first) = targets.first() {
+            let start = source[..first.start].rfind('\n').map_or(0, |i| i + 1);
+            let mut end = line_end(first.end);
+            let mut len = 1;
+            while let Some(next) = targets.get(len)
+                && next.start <= end
+            {
+                end = end.max(line_end(next.end));
+                len += 1;
+            }
+
+            let mut new = String::new();
+            let mut pos = start;
+            for target in &targets[..len] {
+                new.push_str(&source[pos..target.start]);
+                new.push_str(replacement);
+                pos = target.end;
+            }
+            new.push_str(&source[pos..end]);
+
+            line += source[counted..start].matches('\n').count();
+            counted = start;
+            replaced.push_str(&source[copied..start]);
+            replaced.push_str(&new);
+            copied = end;
+            hunks.push(ReplacedHunk { line, old: source[start..end].to_string(), new });
+            targets = &targets[len..];
+        }
+        replaced.push_str(&source[copied..]);
+
+        let count = matches.len() - skipped.len();
+        Replaced { source: replaced, count, skipped: skipped.len(), hunks }
+    }
+
     pub fn raw_text(&self) -> &'_ str {
         &self.text
     }
@@ -175,11 +459,32 @@ impl<'a> SourceMapper<'a> {
 #[derive(Default)]
 pub struct SearchMatches(Vec<Range>);
 
+// Lines in the source before and after the replacement
+#[derive(Serialize, PartialEq, Eq, Debug)]
+pub struct ReplacedHunk {
+    pub line: usize,
+    pub old: String,
+    pub new: String,
+}
+
+#[derive(Debug)]
+pub struct Replaced {
+    pub source: String,
+    pub count: usize,
+    pub skipped: usize, // Number of matches which cannot be replaced since they are across markups
+    pub hunks: Vec<ReplacedHunk>,
+}
+
 impl SearchMatches {
     pub fn len(&self) -> usize {
         self.0.len()
     }
 
+    // Byte ranges of the matches in the Markdown source
+    pub fn ranges(&self) -> &[Range] {
+        &self.0
+    }
+
     pub fn tokenizer(&self, index: Option<usize>) -> Option<MatchTokenizer<'_>> {
         let (head, tail) = self.0.split_first()?;
         Some(MatchTokenizer { head, tail, current: 0, is_start: true, index })
@@ -227,7 +532,6 @@ impl MatchTokenizer<'_> {
 
 impl TextTokenizer for MatchTokenizer<'_> {
     fn tokenize<'t>(&mut self, text: &'t str, range: &Range) -> (TokenKind, &'t str) {
-        debug_assert_eq!(text.len(), range.len(), "text={:?} range={:?}", text, range);
         let Range { start, end } = *range;
 
         while self.head.end <= start {
@@ -236,15 +540,24 @@ impl TextTokenizer for MatchTokenizer<'_> {
             }
         }
 
+        // When the text was replaced by the parser (e.g. "&amp;" by "&", or "---" by "—" with smart punctuation),
+        // offsets in the text don't correspond to offsets in the source. The text is not split in the middle of a
+        // character in the case.
         if self.head.start <= start {
             let token = self.match_token();
-            if self.head.end < end {
-                (token, &text[..self.head.end - start])
+            let len = self.head.end - start;
+            if self.head.end < end && text.is_char_boundary(len) {
+                (token, &text[..len])
             } else {
                 (token, text)
             }
         } else if self.head.start < end {
-            (TokenKind::Normal, &text[..self.head.start - start])
+            let len = self.head.start - start;
+            if text.is_char_boundary(len) {
+                (TokenKind::Normal, &text[..len])
+            } else {
+                (self.match_token(), text)
+            }
         } else {
             (TokenKind::Normal, text)
         }
@@ -255,11 +568,15 @@ impl TextTokenizer for MatchTokenizer<'_> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::Markdown;
+    use crate::markdown::{MarkdownContent, MarkdownParser};
+    use crate::renderer::RawMessageWriter;
+    use std::io;
 
     fn build_text(input: &str, maps: &[Range]) -> DisplayText {
         let mut text = DisplayText::default();
         for m in maps {
-            text.visit(&input[m.clone()], m);
+            text.visit(&input[m.clone()], m, &TextScope::default());
         }
         text
     }
@@ -372,5 +689,206 @@ mod tests {
         assert_eq!(&matches, &[0..1, 1..4, 5..8]);
         let matches = text.search("(fo+)+", SearchMatcher::CaseSensitiveRegex).unwrap().0;
         assert_eq!(&matches, &[0..11]);
+        let matches = text.search("Fo+", SearchMatcher::CaseSensitiveRegex).unwrap().0;
+        assert!(matches.is_empty(), "{:?}", matches);
+    }
+
+    #[test]
+    fn search_case_insensitive_regex() {
+        let input = "fo FOO fOoO";
+        let text = build_text(input, &[0..2, 3..6, 7..11]);
+
+        let matches = text.search("foo+", SearchMatcher::CaseInsensitiveRegex).unwrap().0;
+        assert_eq!(&matches, &[3..6, 7..11]);
+        let matches = text.search("F(O|X)", SearchMatcher::CaseInsensitiveRegex).unwrap().0;
+        assert_eq!(&matches, &[0..2, 3..5, 7..9]);
+        assert!(text.search("(", SearchMatcher::CaseInsensitiveRegex).is_err());
+    }
+
+    #[test]
+    fn search_whole_word() {
+        //           foo     foo    Foo    foo()
+        let input = "foo foobar afoo Foo_ foo() Foo";
+        let text = build_text(input, &[0..4, 4..10, 10..16, 16..21, 21..27, 27..30]);
+
+        let matches = text.search("foo", SearchMatcher::WholeWord).unwrap().0;
+        assert_eq!(&matches, &[0..3, 21..24, 27..30]);
+        let matches = text.search("Foo", SearchMatcher::WholeWord).unwrap().0;
+        assert_eq!(&matches, &[27..30]);
+        let matches = text.search("foo()", SearchMatcher::WholeWord).unwrap().0;
+        assert_eq!(&matches, &[21..26]);
+        let matches = text.search("oo", SearchMatcher::WholeWord).unwrap().0;
+        assert!(matches.is_empty(), "{:?}", matches);
+        let matches = text.search("(", SearchMatcher::WholeWord).unwrap().0;
+        assert_eq!(&matches, &[24..25]);
+    }
+
+    #[test]
+    fn search_fuzzy() {
+        //           abc  a-b-c  axxxxxxxxxxb  ABC
+        let input = "abc--a-b-c..axxxxxxxxxxb__ABC";
+        let text = build_text(input, &[0..3, 5..10, 12..24, 26..29]);
+
+        let matches = text.search("abc", SearchMatcher::Fuzzy).unwrap().0;
+        assert_eq!(&matches, &[0..3, 5..10, 26..29]);
+        let matches = text.search("a c", SearchMatcher::Fuzzy).unwrap().0;
+        assert_eq!(&matches, &[0..3, 5..10, 26..29]);
+        let matches = text.search("ABC", SearchMatcher::Fuzzy).unwrap().0;
+        assert_eq!(&matches, &[26..29]);
+        let matches = text.search("abd", SearchMatcher::Fuzzy).unwrap().0;
+        assert!(matches.is_empty(), "{:?}", matches);
+        let matches = text.search("", SearchMatcher::Fuzzy).unwrap().0;
+        assert!(matches.is_empty(), "{:?}", matches);
+    }
+
+    #[test]
+    fn search_fuzzy_prefers_compact_match() {
+        //           a  aab   b
+        let input = "a, aab - b";
+        let text = build_text(input, &[0..1, 3..6, 9..10]);
+
+        // "a, aab" and "ab" overlap. The compact one is chosen
+        let matches = text.search("ab", SearchMatcher::Fuzzy).unwrap().0;
+        assert_eq!(&matches, &[4..6]);
+        let matches = text.search("aab", SearchMatcher::Fuzzy).unwrap().0;
+        assert_eq!(&matches, &[3..6]);
+
+        let input = "日本語のテキスト";
+        let text = build_text(input, &[0..input.len()]);
+        let matches = text.search("日語テ", SearchMatcher::Fuzzy).unwrap().0;
+        assert_eq!(&matches, &[0..15]);
+    }
+
+    #[test]
+    fn parse_scoped_query() {
+        let query = ScopedQuery::parse("h2:code.rust:foo:bar");
+        assert_eq!(
+            query.qualifiers,
+            [Qualifier::Heading(Some(2)), Qualifier::Code(Some("rust".into()))]
+        );
+        assert_eq!(query.text(), "foo:bar");
+
+        for (input, qualifiers, text) in [
+            ("foo", vec![], "foo"),
+            ("h:foo", vec![Qualifier::Heading(None)], "foo"),
+            ("table:link:", vec![Qualifier::Table, Qualifier::Link], ""),
+            ("alert.note:x", vec![Qualifier::Alert(Some("note".into()))], "x"),
+            ("h7:foo", vec![], "h7:foo"),
+            ("code.:foo", vec![], "code.:foo"),
+            ("https://example.com", vec![], "https://example.com"),
+            ("Code:foo", vec![], "Code:foo"),
+        ] {
+            let query = ScopedQuery::parse(input);
+            assert_eq!(query.qualifiers, qualifiers, "{input:?}");
+            assert_eq!(query.text(), text, "{input:?}");
+        }
+    }
+
+    #[test]
+    fn search_scoped_by_structure() {
+        let source = [
+            "# Install foo",
+            "",
+            "## Install",
+            "",
+            "Install foo with `cargo`.",
+            "",
+            "```rust",
+            "let foo = install();",
+            "```",
+            "",
+            "```sh",
+            "cargo install foo",
+            "```",
+            "",
+            "| Name | Install |",
+            "|------|---------|",
+            "| foo  | [install](https://example.com) |",
+            "",
+            "> [!NOTE]",
+            "> Install foo first.",
+            "",
+            "See https://example.com/install",
+        ]
+        .join("\n");
+        let content = MarkdownContent::new(source.clone(), None, &Markdown::default());
+        let text: DisplayText =
+            MarkdownParser::new(&content, None, ()).write_to(io::sink()).unwrap();
+
+        for (query, want) in [
+            ("install", &[1, 3, 5, 8, 12, 15, 17, 20, 22][..]),
+            ("h:install", &[1, 3]),
+            ("h2:install", &[3]),
+            ("h1:install foo", &[1]),
+            ("h3:install", &[]),
+            ("code:install", &[8, 12]),
+            ("code.rust:install", &[8]),
+            ("code.RUST:install", &[8]),
+            ("code.python:install", &[]),
+            ("code:cargo", &[12]),
+            ("table:install", &[15, 17]),
+            ("link:install", &[17, 22]),
+            ("table:link:install", &[17]),
+            ("alert:install", &[20]),
+            ("alert.note:install", &[20]),
+            ("alert.tip:install", &[]),
+            ("h:fooInstall", &[1]), // Matches across the two headings
+            ("h2:fooInstall", &[]),
+            ("h:", &[]),
+            ("https:", &[22]),
+        ] {
+            let matches = text.search(query, SearchMatcher::SmartCase).unwrap();
+            let lines: Vec<_> = matches
+                .ranges()
+                .iter()
+                .map(|r| source[..r.start].matches('\n').count() + 1)
+                .collect();
+            assert_eq!(lines, want, "query={query:?}");
+        }
+    }
+
+    #[test]
+    fn replace_matches_in_source() {
+        let source = "# Foo\n\nfoo bar foo\n\n**foo** and *foo bar*\n\nfoo\nbar\n";
+        let content = MarkdownContent::new(source.into(), None, &Markdown::default());
+        let text: DisplayText =
+            MarkdownParser::new(&content, None, ()).write_to(io::sink()).unwrap();
+        let hunk =
+            |line, old: &str, new: &str| ReplacedHunk { line, old: old.into(), new: new.into() };
+
+        let matches = text.search("foo", SearchMatcher::CaseSensitive).unwrap();
+        let replaced = text.replace(source, &matches, "baz");
+        assert_eq!(replaced.source, "# Foo\n\nbaz bar baz\n\n**baz** and *baz bar*\n\nbaz\nbar\n");
+        assert_eq!((replaced.count, replaced.skipped), (5, 0));
+        assert_eq!(
+            replaced.hunks,
+            [
+                hunk(3, "foo bar foo", "baz bar baz"),
+                hunk(5, "**foo** and *foo bar*", "**baz** and *baz bar*"),
+                hunk(7, "foo", "baz"),
+            ],
+        );
+
+        // Match across lines
+        let matches = text.search("foo bar", SearchMatcher::CaseSensitive).unwrap();
+        let replaced = text.replace(source, &matches, "X");
+        assert_eq!(replaced.source, "# Foo\n\nX foo\n\n**foo** and *X*\n\nX\n");
+        assert_eq!((replaced.count, replaced.skipped), (3, 0));
+        assert_eq!(
+            replaced.hunks,
+            [
+                hunk(3, "foo bar foo", "X foo"),
+                hunk(5, "**foo** and *foo bar*", "**foo** and *X*"),
+                hunk(7, "foo\nbar", "X")
+            ],
+        );
+
+        // Match across markups is not replaced
+        let matches = text.search("foo and foo", SearchMatcher::CaseSensitive).unwrap();
+        assert_eq!(matches.len(), 1);
+        let replaced = text.replace(source, &matches, "X");
+        assert_eq!(replaced.source, source);
+        assert_eq!((replaced.count, replaced.skipped), (0, 1));
+        assert!(replaced.hunks.is_empty());
     }
 }
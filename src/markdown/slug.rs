@@ -0,0 +1,155 @@
+use super::parser::{EmojiToken, EmojiTokenizer};
+use pulldown_cmark::{CowStr, Event, Tag, TagEnd};
+use std::collections::HashMap;
+
+// Convert heading text into a slug in the same way as GitHub. Alphanumeric characters, '-' and '_' are kept as-is,
+// white spaces are replaced with '-', and other characters such as punctuations and emojis are removed.
+// https://github.com/Flet/github-slugger
+pub fn slugify(text: &str) -> String {
+    text.chars()
+        .filter_map(|c| match c {
+            ' ' => Some('-'),
+            '-' | '_' => Some(c),
+            c if c.is_alphanumeric() => Some(c),
+            _ => None,
+        })
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+// Text content of the heading currently being rendered. Its ID is determined when the heading ends.
+#[derive(Default)]
+pub struct HeadingText<'input> {
+    id: Option<CowStr<'input>>,
+    text: String,
+    image: usize,
+}
+
+impl<'input> HeadingText<'input> {
+    pub fn new(id: Option<CowStr<'input>>) -> Self {
+        Self { id, text: String::new(), image: 0 }
+    }
+
+    pub fn push_event(&mut self, event: &Event<'_>) {
+        if self.id.is_some() {
+            return;
+        }
+        match event {
+            // Alt texts of images are not included in the text content
+            Event::Start(Tag::Image { .. }) => self.image += 1,
+            Event::End(TagEnd::Image) => self.image = self.image.saturating_sub(1),
+            _ if self.image > 0 => {}
+            // Emojis are removed from slugs
+            Event::Text(text) => {
+                for token in EmojiTokenizer::new(text) {
+                    if let EmojiToken::Text(text) = token {
+                        self.text.push_str(text);
+                    }
+                }
+            }
+            Event::Code(text) | Event::InlineMath(text) => self.text.push_str(text),
+            Event::SoftBreak => self.text.push(' '),
+            _ => {}
+        }
+    }
+}
+
+// Generates unique heading IDs in a document. When the same slug is generated multiple times, suffixes like -1, -2,
+// ... are added to the slugs.
+#[derive(Default)]
+pub struct Slugger {
+    occurrences: HashMap<String, usize>,
+}
+
+impl Slugger {
+    pub fn id<'input>(&mut self, heading: HeadingText<'input>) -> CowStr<'input> {
+        if let Some(id) = heading.id {
+            // Explicit IDs like `# Title {#id}` are used as-is. They are remembered to avoid conflicts with slugs.
+            self.occurrences.entry(id.to_string()).or_insert(0);
+            return id;
+        }
+
+        let slug = slugify(&heading.text);
+        let mut id = slug.clone();
+        while self.occurrences.contains_key(&id) {
+            let count = self.occurrences.entry(slug.clone()).or_default();
+            *count += 1;
+            id = format!("{slug}-{count}");
+        }
+        self.occurrences.insert(id.clone(), 0);
+        id.into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slugify_heading_text() {
+        for (input, want) in [
+            ("Hello", "hello"),
+            ("Hello, world!", "hello-world"),
+            ("Two  spaces", "two--spaces"),
+            ("foo-bar_baz", "foo-bar_baz"),
+            ("API v2.0 (beta)", "api-v20-beta"),
+            ("`code` and *emphasis*", "code-and-emphasis"),
+            ("日本語 の見出し", "日本語-の見出し"),
+            ("Über Größe", "über-größe"),
+            ("Dog 🐶", "dog-"),
+            ("", ""),
+        ] {
+            assert_eq!(slugify(input), want, "input={input:?}");
+        }
+    }
+
+    fn heading(text: &'static str) -> HeadingText<'static> {
+        let mut h = HeadingText::new(None);
+        h.push_event(&Event::Text(text.into()));
+        h
+    }
+
+    #[test]
+    fn unique_heading_ids() {
+        let mut slugger = Slugger::default();
+        let ids: Vec<_> = ["Foo", "Foo", "Foo 1", "Foo", "Bar"]
+            .into_iter()
+            .map(|t| slugger.id(heading(t)).to_string())
+            .collect();
+        assert_eq!(ids, ["foo", "foo-1", "foo-1-1", "foo-2", "bar"]);
+    }
+
+    #[test]
+    fn explicit_heading_ids() {
+        let mut slugger = Slugger::default();
+        assert_eq!(slugger.id(HeadingText::new(Some("foo".into()))).as_ref(), "foo");
+        assert_eq!(slugger.id(heading("Foo")).as_ref(), "foo-1");
+        let mut h = HeadingText::new(Some("custom".into()));
+        h.push_event(&Event::Text("Ignored".into()));
+        assert_eq!(slugger.id(h).as_ref(), "custom");
+        assert_eq!(slugger.id(heading("Custom")).as_ref(), "custom-1");
+    }
+
+    #[test]
+    fn heading_text_without_emojis() {
+        let mut h = HeadingText::new(None);
+        for event in [
+            Event::Text("Hello :dog: ".into()),
+            Event::Code("a:dog:b".into()),
+            Event::SoftBreak,
+            Event::InlineMath("x".into()),
+            Event::Html("<b>".into()),
+            Event::Start(Tag::Image {
+                link_type: pulldown_cmark::LinkType::Inline,
+                dest_url: "icon.png".into(),
+                title: "".into(),
+                id: "".into(),
+            }),
+            Event::Text("icon".into()),
+            Event::End(TagEnd::Image),
+        ] {
+            h.push_event(&event);
+        }
+        assert_eq!(slugify(&h.text), "hello--adogb-x");
+    }
+}
@@ -0,0 +1,117 @@
+use super::sanitizer::SlashPath;
+use super::slug::slugify;
+use crate::config::FileExtensions;
+use std::path::{Path, PathBuf};
+
+// Resolves targets of wikilinks such as `[[Other Note]]` and `[[Other Note#Heading|label]]` to Markdown files. The
+// page name is looked up in the directory of the current file at first, then in the directories being watched.
+#[derive(Default, Debug)]
+pub struct WikiLinkResolver {
+    dirs: Vec<PathBuf>,
+    extensions: FileExtensions,
+}
+
+impl WikiLinkResolver {
+    pub fn new(base_dir: Option<&Path>) -> Self {
+        let dirs = base_dir.map(|dir| vec![dir.to_path_buf()]).unwrap_or_default();
+        Self { dirs, extensions: FileExtensions::default() }
+    }
+
+    pub fn add_dirs(&mut self, dirs: Vec<PathBuf>, extensions: FileExtensions) {
+        for dir in dirs {
+            if !self.dirs.contains(&dir) {
+                self.dirs.push(dir);
+            }
+        }
+        self.extensions = extensions;
+    }
+
+    fn find_file(&self, page: &str) -> Option<PathBuf> {
+        let has_ext = self.extensions.matches(Path::new(page));
+        self.dirs.iter().find_map(|dir| {
+            if has_ext {
+                let path = dir.join(page);
+                if path.is_file() {
+                    return Some(path);
+                }
+            }
+            self.extensions.as_slice().iter().find_map(|ext| {
+                let path = dir.join(format!("{page}.{ext}"));
+                path.is_file().then_some(path)
+            })
+        })
+    }
+
+    // Returns the link destination of the wikilink target. `None` means that the page was not found.
+    //   e.g. 'Other Note#Some Heading' -> '/path/to/Other Note.md#some-heading'
+    pub fn resolve(&self, target: &str) -> Option<String> {
+        let (page, heading) = match target.split_once('#') {
+            Some((page, heading)) => (page.trim(), Some(heading.trim())),
+            None => (target.trim(), None),
+        };
+
+        let mut href = String::new();
+        if !page.is_empty() {
+            let path = self.find_file(page)?;
+            href.push_str(&SlashPath::from(path.as_path()));
+        }
+        if let Some(heading) = heading {
+            href.push('#');
+            href.push_str(&slugify(heading));
+        }
+        Some(href)
+    }
+}
+
+// Page name of the wikilink target shown for the missing page.
+//   e.g. 'Other Note#Some Heading' -> 'Other Note'
+pub fn page_name(target: &str) -> &str {
+    target.split_once('#').map(|(page, _)| page).unwrap_or(target).trim()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TESTDATA_DIR: &str = "src/markdown/testdata";
+
+    #[test]
+    fn resolve_in_base_dir() {
+        let resolver = WikiLinkResolver::new(Some(Path::new("src/markdown")));
+        for (target, want) in [
+            ("testdata/headings", Some("src/markdown/testdata/headings.md")),
+            ("testdata/headings.md", Some("src/markdown/testdata/headings.md")),
+            (" testdata/headings ", Some("src/markdown/testdata/headings.md")),
+            (
+                "testdata/headings#Some Heading",
+                Some("src/markdown/testdata/headings.md#some-heading"),
+            ),
+            ("#Some Heading", Some("#some-heading")),
+            ("testdata/this-file-does-not-exist", None),
+            ("testdata/headings.txt", None),
+        ] {
+            assert_eq!(resolver.resolve(target).as_deref(), want, "target={target:?}");
+        }
+    }
+
+    #[test]
+    fn resolve_in_watched_dirs() {
+        let mut resolver = WikiLinkResolver::new(Some(Path::new("src")));
+        assert_eq!(resolver.resolve("headings"), None);
+
+        resolver.add_dirs(vec![PathBuf::from(TESTDATA_DIR)], FileExtensions::default());
+        let href = resolver.resolve("headings");
+        assert_eq!(href.as_deref(), Some("src/markdown/testdata/headings.md"));
+
+        resolver.add_dirs(vec![PathBuf::from(TESTDATA_DIR)], FileExtensions::new(["txt"]));
+        assert_eq!(resolver.resolve("headings"), None);
+    }
+
+    #[test]
+    fn page_name_of_target() {
+        assert_eq!(page_name("Other Note"), "Other Note");
+        assert_eq!(page_name("Other Note#Heading"), "Other Note");
+        assert_eq!(page_name(" Other Note #Heading"), "Other Note");
+        assert_eq!(page_name("#Heading"), "");
+    }
+}
@@ -1,8 +1,13 @@
-use anyhow::Result;
+use crate::config::Editor;
+use anyhow::{Context as _, Result};
 use std::ffi::OsStr;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::thread;
 
 pub trait Opener: Default {
     fn open(&mut self, path: impl AsRef<OsStr>) -> Result<()>;
+    fn open_editor(&mut self, editor: &Editor, path: &Path, line: usize) -> Result<()>;
 }
 
 #[derive(Default)]
@@ -12,4 +17,32 @@ impl Opener for SystemOpener {
     fn open(&mut self, path: impl AsRef<OsStr>) -> Result<()> {
         Ok(open::that(path)?)
     }
+
+    fn open_editor(&mut self, editor: &Editor, path: &Path, line: usize) -> Result<()> {
+        let args = editor.args(path, line);
+        let Some((program, args)) = args.split_first() else {
+            anyhow::bail!("Command to open a text editor is empty");
+        };
+        log::debug!("Launching text editor command: {program:?} {args:?}");
+
+        let mut child = Command::new(program)
+            .args(args)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .with_context(|| {
+                format!("Could not launch text editor command {:?}", editor.command)
+            })?;
+
+        // Wait for the process in another thread so that it does not remain as a zombie process
+        thread::spawn(move || match child.wait() {
+            Ok(status) if !status.success() => {
+                log::error!("Text editor command exited with non-zero status: {status}");
+            }
+            Ok(_) => {}
+            Err(err) => log::error!("Could not wait for text editor command: {err}"),
+        });
+        Ok(())
+    }
 }
@@ -5,11 +5,15 @@ use std::fs;
 use std::io::ErrorKind;
 use std::path::{Path, PathBuf};
 
+const ZSTD_COMPRESS_LEVEL: i32 = 3;
+
 pub trait PersistentData {
     const FILE: &str;
+    // Compress the data file with zstd. This is useful for large data
+    const COMPRESSED: bool = false;
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct DataDir {
     path: Option<PathBuf>,
 }
@@ -40,7 +44,12 @@ impl DataDir {
     }
 
     pub fn load<D: PersistentData + DeserializeOwned>(&self) -> Option<D> {
-        let path = self.path.as_deref()?.join(D::FILE);
+        self.load_from(D::FILE)
+    }
+
+    // Load the data from the file other than `D::FILE`. This is useful when the file name is determined at runtime
+    pub fn load_from<D: PersistentData + DeserializeOwned>(&self, file: &str) -> Option<D> {
+        let path = self.path.as_deref()?.join(file);
         let bytes = match fs::read(&path) {
             Ok(data) => data,
             Err(err) => {
@@ -48,6 +57,17 @@ impl DataDir {
                 return None;
             }
         };
+        let bytes = if D::COMPRESSED {
+            match zstd::decode_all(bytes.as_slice()) {
+                Ok(bytes) => bytes,
+                Err(err) => {
+                    log::error!("Could not decompress persistent data at {path:?}: {err}");
+                    return None;
+                }
+            }
+        } else {
+            bytes
+        };
         // serde_json::from_reader may be efficient when writing large data
         match serde_json::from_slice(&bytes) {
             Ok(data) => Some(data),
@@ -61,15 +81,23 @@ impl DataDir {
     }
 
     pub fn save<D: PersistentData + Serialize>(&self, data: &D) -> Result<()> {
+        self.save_to(data, D::FILE)
+    }
+
+    pub fn save_to<D: PersistentData + Serialize>(&self, data: &D, file: &str) -> Result<()> {
         let Some(dir) = &self.path else {
             return Ok(());
         };
-        let path = dir.join(D::FILE);
+        let path = dir.join(file);
         // serde_json::to_writer may be efficient when writing large data
-        let s = serde_json::to_string(data)
+        let mut bytes = serde_json::to_vec(data)
             .with_context(|| format!("Could not serialize persistent data to {path:?}"))?;
+        if D::COMPRESSED {
+            bytes = zstd::encode_all(bytes.as_slice(), ZSTD_COMPRESS_LEVEL)
+                .with_context(|| format!("Could not compress persistent data to {path:?}"))?;
+        }
         log::debug!("Saved persistent data at {path:?}");
-        fs::write(&path, s)
+        fs::write(&path, bytes)
             .with_context(|| format!("Could not save persistent data to file {path:?}"))
     }
 
@@ -119,6 +147,29 @@ mod tests {
         assert!(!file.exists(), "path={file:?}");
     }
 
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Compressed(Vec<String>);
+
+    impl PersistentData for Compressed {
+        const FILE: &str = "compressed.json.zst";
+        const COMPRESSED: bool = true;
+    }
+
+    #[test]
+    fn save_and_load_compressed() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = DataDir::new(tmp.path());
+        let expected = Compressed(vec!["hello".repeat(100); 10]);
+        dir.save(&expected).unwrap();
+        let file = tmp.path().join("compressed.json.zst");
+        let bytes = fs::read(&file).unwrap();
+        assert!(bytes.len() < 5000, "data is not compressed: {}", bytes.len());
+        assert_eq!(dir.load::<Compressed>(), Some(expected));
+
+        fs::write(&file, "not compressed").unwrap();
+        assert_eq!(dir.load::<Compressed>(), None);
+    }
+
     #[test]
     fn no_data_dir() {
         let dir = DataDir::new("this-directory-does-not-exist");
@@ -1,17 +1,63 @@
-use crate::config::{SearchMatcher, home_dir};
-use crate::markdown::{DisplayText, MarkdownContent, MarkdownParser};
-use crate::renderer::{MessageToWindow, Window};
-use anyhow::{Error, Result};
-use std::fs;
-use std::io::ErrorKind;
+use crate::config::{Config, SearchMatcher, home_dir};
+use crate::document_state::{DocumentStates, SearchState};
+use crate::markdown::{
+    DiagramJob, DisplayText, MarkdownContent, MarkdownParser, Metadata, Replaced,
+};
+use crate::renderer::{MessageToWindow, RawMessageWriter as _, ScrollRequest, Window};
+use crate::watcher::Watcher;
+use anyhow::{Context as _, Error, Result};
+use std::ffi::OsString;
+use std::fs::{self, File};
+use std::io::{self, ErrorKind, Write as _};
 use std::mem;
 use std::path::{MAIN_SEPARATOR, Path, PathBuf};
 
+// Write the content to the file atomically. The content is written to a temporary file in the same directory and then
+// the temporary file is renamed to the file so that the watcher and editors never see a partially written file.
+fn write_atomically(path: &Path, content: &str) -> Result<()> {
+    let path = fs::canonicalize(path)
+        .with_context(|| format!("Could not resolve the path of the file to write: {path:?}"))?;
+    let Some(name) = path.file_name() else {
+        anyhow::bail!("File name is not found in the path to write: {path:?}");
+    };
+    let mut tmp_name = OsString::from(".");
+    tmp_name.push(name);
+    tmp_name.push(".shiba-tmp");
+    let tmp_path = path.with_file_name(tmp_name);
+    let permissions = fs::metadata(&path)
+        .with_context(|| format!("Could not read metadata of the file to write: {path:?}"))?
+        .permissions();
+
+    let write = || -> io::Result<()> {
+        let mut file = File::create(&tmp_path)?;
+        file.write_all(content.as_bytes())?;
+        file.set_permissions(permissions)?;
+        file.sync_all()?;
+        fs::rename(&tmp_path, &path)
+    };
+    if let Err(err) = write() {
+        let _ = fs::remove_file(&tmp_path);
+        return Err(Error::new(err).context(format!("Could not write the file {path:?}")));
+    }
+    log::debug!("Wrote {} bytes to {path:?}", content.len());
+    Ok(())
+}
+
+// Search whose matches are highlighted in the window
+struct ActiveSearch {
+    query: String,
+    index: Option<usize>,
+    matcher: SearchMatcher,
+}
+
 #[derive(Default)]
 pub struct Preview {
     content: MarkdownContent,
     text: DisplayText,
+    metadata: Option<Metadata>,
     path: PathBuf,
+    block: Option<usize>, // Index of the top-level block at the top of the viewport reported by the window
+    search: Option<ActiveSearch>,
 }
 
 impl Preview {
@@ -23,7 +69,38 @@ impl Preview {
         &self.path
     }
 
+    // Resolve the relative path against the directory of the document
+    pub fn absolute_path(&self, path: &Path) -> Option<PathBuf> {
+        if path.is_relative()
+            && !self.is_empty()
+            && let Some(dir) = self.path.parent()
+        {
+            dir.join(path).canonicalize().ok()
+        } else {
+            None
+        }
+    }
+
+    pub fn scroll_block(&self) -> Option<usize> {
+        self.block
+    }
+
+    pub fn set_scroll_block(&mut self, block: usize) {
+        self.block = Some(block);
+    }
+
+    pub fn block_at_line(&self, line: usize) -> Option<usize> {
+        self.content.block_at_line(line)
+    }
+
+    pub fn line_at(&self, offset: usize) -> usize {
+        self.content.line_at(offset)
+    }
+
     fn title(&self) -> String {
+        if let Some(title) = self.metadata.as_ref().and_then(Metadata::title) {
+            return format!("Shiba: {title}");
+        }
         if let Some(home_dir) = home_dir()
             && let Ok(path) = self.path.strip_prefix(home_dir)
         {
@@ -32,10 +109,59 @@ impl Preview {
         format!("Shiba: {}", self.path.display())
     }
 
-    pub fn show<W: Window>(&mut self, path: &Path, window: &W) -> Result<bool> {
-        log::debug!("Opening markdown preview for {:?}", path);
-        let new_content = match fs::read_to_string(path) {
-            Ok(source) => MarkdownContent::new(source, path.parent()),
+    pub fn show<W: Window>(
+        &mut self,
+        path: &Path,
+        window: &W,
+        config: &Config,
+        watcher: &impl Watcher,
+        documents: &DocumentStates,
+    ) -> Result<bool> {
+        let is_new = self.path != path;
+        if !self.load(path, window, config, watcher)? {
+            return Ok(false);
+        }
+        if is_new {
+            self.restore(window, documents)?;
+        }
+        Ok(true)
+    }
+
+    // Restore the scroll position and the search of the document when it was previously opened. The messages are sent
+    // after the content so that they are applied to the new content.
+    fn restore<W: Window>(&self, window: &W, documents: &DocumentStates) -> Result<()> {
+        let Some(state) = documents.get(&self.path) else {
+            return Ok(());
+        };
+        log::debug!("Restoring the previous state of {:?}: {:?}", self.path, state);
+        if let Some(block) = state.block {
+            let scroll = ScrollRequest::BlockTop(block);
+            window.send_message(MessageToWindow::Scroll { scroll })?;
+        }
+        if let Some(SearchState { query, matcher }) = &state.search {
+            let (query, matcher) = (Some(query.as_str()), Some(*matcher));
+            window.send_message(MessageToWindow::Search { query, matcher })?;
+        }
+        Ok(())
+    }
+
+    fn read(
+        path: &Path,
+        config: &Config,
+        watcher: &impl Watcher,
+    ) -> Result<Option<MarkdownContent>> {
+        let content = match fs::read_to_string(path) {
+            Ok(source) => {
+                let diagrams = config.preview().diagrams.clone();
+                let content = MarkdownContent::new(source, path.parent(), config.markdown())
+                    .with_diagrams(diagrams, config.data_dir());
+                if config.markdown().wikilinks {
+                    let exts = config.watch().file_extensions.clone();
+                    content.with_wikilink_dirs(watcher.watched_dirs(), exts)
+                } else {
+                    content
+                }
+            }
             Err(err) => {
                 log::debug!("Could not open {:?} due to error: {}", path, err);
 
@@ -43,21 +169,46 @@ impl Preview {
                 // exists. This can happen when saving files on Vim. In this case, a file create event
                 // will follow so the preview can be updated with the event.
                 if err.kind() == ErrorKind::NotFound {
-                    return Ok(false);
+                    return Ok(None);
                 }
 
                 let err = Error::new(err).context(format!("Could not open {path:?} due to error"));
                 return Err(err);
             }
         };
+        Ok(Some(content))
+    }
+
+    fn load<W: Window>(
+        &mut self,
+        path: &Path,
+        window: &W,
+        config: &Config,
+        watcher: &impl Watcher,
+    ) -> Result<bool> {
+        log::debug!("Opening markdown preview for {:?}", path);
+        let Some(new_content) = Self::read(path, config, watcher)? else {
+            return Ok(false);
+        };
 
         let is_new = self.path != path;
         let prev_content = mem::replace(&mut self.content, new_content);
         let offset = if is_new { None } else { prev_content.modified_utf8_offset(&self.content) };
 
+        let mut title_changed = is_new;
         if is_new || offset.is_some() {
             log::debug!("Parse Markdown source and send it to renderer with offset {offset:?}");
             self.text = window.send_message_raw(MarkdownParser::new(&self.content, offset, ()))?;
+            self.search = None;
+
+            let metadata = self.content.metadata();
+            if is_new || metadata != self.metadata {
+                log::debug!("Metadata of the document changed: {metadata:?}");
+                window.send_message(MessageToWindow::Metadata { metadata: metadata.as_ref() })?;
+                let prev = mem::replace(&mut self.metadata, metadata);
+                title_changed |= prev.as_ref().and_then(Metadata::title)
+                    != self.metadata.as_ref().and_then(Metadata::title);
+            }
         } else {
             log::debug!("Skip parsing Markdown source because nothing has changed");
         }
@@ -65,6 +216,10 @@ impl Preview {
         if is_new {
             window.send_message(MessageToWindow::Path { path })?;
             self.path = path.to_path_buf();
+            self.block = None;
+        }
+
+        if title_changed {
             let title = self.title();
             log::debug!("Preview title changed to {title:?}");
             window.set_title(&title);
@@ -73,12 +228,99 @@ impl Preview {
         Ok(true)
     }
 
+    // Parse the current file again with the new config
+    pub fn refresh<W: Window>(
+        &mut self,
+        window: &W,
+        config: &Config,
+        watcher: &impl Watcher,
+    ) -> Result<()> {
+        if self.is_empty() {
+            return Ok(());
+        }
+        let path = mem::take(&mut self.path);
+        let block = self.block;
+        if !self.load(&path, window, config, watcher)? {
+            self.path = path;
+        }
+        self.block = block;
+        Ok(())
+    }
+
+    // Load the file in the tab which is not displayed in the window. Nothing is sent to the window until the tab is
+    // activated.
+    pub fn load_in_background(
+        &mut self,
+        path: &Path,
+        config: &Config,
+        watcher: &impl Watcher,
+    ) -> Result<bool> {
+        log::debug!("Loading markdown preview for {:?} in background", path);
+        let Some(content) = Self::read(path, config, watcher)? else {
+            return Ok(false);
+        };
+        self.metadata = content.metadata();
+        self.content = content;
+        if self.path != path {
+            self.path = path.to_path_buf();
+            self.block = None;
+        }
+        Ok(true)
+    }
+
+    // Render the whole content in the window on switching tabs. The scroll position is restored as well.
+    pub fn activate<W: Window>(&mut self, window: &W) -> Result<()> {
+        if self.is_empty() {
+            window.set_title("Shiba");
+            return window.send_message(MessageToWindow::Welcome);
+        }
+        log::debug!("Activate the preview for {:?}", self.path);
+        self.text = window.send_message_raw(MarkdownParser::new(&self.content, None, ()))?;
+        self.search = None;
+        window.send_message(MessageToWindow::Metadata { metadata: self.metadata.as_ref() })?;
+        window.send_message(MessageToWindow::Path { path: &self.path })?;
+        window.set_title(&self.title());
+        if let Some(block) = self.block {
+            let scroll = ScrollRequest::BlockTop(block);
+            window.send_message(MessageToWindow::Scroll { scroll })?;
+        }
+        Ok(())
+    }
+
+    // Send the source with the lines of the blocks for split view
+    pub fn send_source<W: Window>(&self, window: &W) -> Result<()> {
+        let blocks = self.content.block_lines();
+        log::debug!("Send the source of {:?} with {} blocks", self.path, blocks.len());
+        window.send_message(MessageToWindow::Source {
+            source: self.content.source(),
+            blocks: &blocks,
+        })
+    }
+
+    // Diagrams which should be rendered in background since the content was last sent to the window
+    pub fn diagram_jobs(&self) -> Vec<DiagramJob> {
+        self.content.diagram_jobs()
+    }
+
+    // Render the content again to replace the code blocks with the diagrams rendered in background. The matches of the
+    // active search are highlighted again.
+    pub fn show_diagrams<W: Window>(&mut self, window: &W) -> Result<()> {
+        log::debug!("Re-rendering {:?} with the diagrams rendered in background", self.path);
+        let Some(ActiveSearch { query, index, matcher }) = self.search.take() else {
+            self.text = window.send_message_raw(MarkdownParser::new(&self.content, None, ()))?;
+            return Ok(());
+        };
+        // The texts in the code blocks are no longer in the preview. Matches must be searched in the new text.
+        self.text = MarkdownParser::new(&self.content, None, ()).write_to(io::sink())?;
+        self.search(window, &query, index, matcher)
+    }
+
     pub fn rerender<W: Window>(&self, window: &W) -> Result<()> {
         window.send_message_raw(MarkdownParser::new(&self.content, None, ()))
     }
 
     pub fn search<W: Window>(
-        &self,
+        &mut self,
         window: &W,
         query: &str,
         index: Option<usize>,
@@ -86,8 +328,10 @@ impl Preview {
     ) -> Result<()> {
         log::debug!("Re-rendering content with query {:?} and current index {:?}", query, index);
         if query.is_empty() {
+            self.search = None;
             return self.rerender(window);
         }
+        self.search = Some(ActiveSearch { query: query.to_string(), index, matcher });
 
         let matches = match self.text.search(query, matcher) {
             Ok(matches) => matches,
@@ -104,4 +348,131 @@ impl Preview {
             self.rerender(window)
         }
     }
+
+    pub fn replace(
+        &self,
+        query: &str,
+        matcher: SearchMatcher,
+        replacement: &str,
+    ) -> Result<Replaced> {
+        let matches = self.text.search(query, matcher)?;
+        log::debug!("Replacing {} matches of {:?} with {:?}", matches.len(), query, replacement);
+        Ok(self.text.replace(self.content.source(), &matches, replacement))
+    }
+
+    // Write the replaced source to the file. The preview is updated by the watcher after that. The file may have been
+    // modified by other applications such as text editors after the source was replaced. Writing the replaced source
+    // in the case would silently discard the modification.
+    pub fn write_source(&self, source: &str) -> Result<()> {
+        let current = fs::read_to_string(&self.path)
+            .with_context(|| format!("Could not read the file to write: {:?}", self.path))?;
+        if current != self.content.source() {
+            anyhow::bail!(
+                "File {:?} was modified after it was previewed. Replace the text again after the preview is updated",
+                self.path,
+            );
+        }
+        write_atomically(&self.path, source)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::UserConfig;
+    use crate::test::TestWindow;
+    use crate::watcher::NopWatcher;
+    use std::collections::HashMap;
+
+    #[test]
+    fn write_file_atomically() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("test.md");
+        fs::write(&path, "foo").unwrap();
+        write_atomically(&path, "bar").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "bar");
+        let entries: Vec<_> =
+            fs::read_dir(tmp.path()).unwrap().map(|e| e.unwrap().file_name()).collect();
+        assert_eq!(entries, ["test.md"], "temporary file remains");
+
+        let missing = tmp.path().join("missing.md");
+        assert!(write_atomically(&missing, "foo").is_err());
+        assert!(!missing.exists());
+    }
+
+    #[test]
+    fn replace_and_write_source() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("test.md");
+        fs::write(&path, "foo bar foo\n").unwrap();
+        let mut preview = Preview::default();
+        assert!(preview.load_in_background(&path, &Config::default(), &NopWatcher).unwrap());
+        preview.activate(&TestWindow::default()).unwrap();
+
+        let replaced = preview.replace("foo", SearchMatcher::CaseSensitive, "baz").unwrap();
+        assert_eq!(replaced.count, 2);
+        preview.write_source(&replaced.source).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "baz bar baz\n");
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    #[test]
+    fn show_diagrams_rendered_in_background() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("test.md");
+        fs::write(&path, "hello\n\n```svg\n<svg>hello</svg>\n```\n").unwrap();
+        let mut user = UserConfig::default();
+        user.preview.diagrams = HashMap::from([("svg".into(), vec!["cat".into()])]);
+        let config = Config::new(user, tmp.path().join("config"), tmp.path());
+
+        let window = TestWindow::default();
+        let mut preview = Preview::default();
+        assert!(preview.load_in_background(&path, &config, &NopWatcher).unwrap());
+        preview.activate(&window).unwrap();
+        preview.search(&window, "hello", Some(0), SearchMatcher::CaseSensitive).unwrap();
+        let msg = window.messages.take().pop().unwrap();
+        assert!(msg.contains("match-current") && !msg.contains(r#""diagram""#), "{msg}");
+
+        let jobs = preview.diagram_jobs();
+        assert!(!jobs.is_empty());
+        for job in jobs {
+            job.run();
+        }
+
+        // The active search is highlighted again with the diagram
+        preview.show_diagrams(&window).unwrap();
+        let msgs = window.messages.take();
+        assert_eq!(msgs.len(), 1, "{msgs:?}");
+        assert!(msgs[0].contains("match-current") && msgs[0].contains(r#""diagram""#), "{msgs:?}");
+        assert!(preview.diagram_jobs().is_empty());
+
+        // The preview in a background tab shows the diagram when it is activated
+        let mut background = Preview::default();
+        assert!(background.load_in_background(&path, &config, &NopWatcher).unwrap());
+        background.activate(&window).unwrap();
+        let msgs = window.messages.take();
+        assert!(msgs[0].contains(r#""diagram""#), "{msgs:?}");
+        assert!(background.diagram_jobs().is_empty());
+
+        // The search was cleared by activating the preview
+        background.show_diagrams(&window).unwrap();
+        let msg = window.messages.take().pop().unwrap();
+        assert!(!msg.contains("match-current"), "{msg}");
+    }
+
+    #[test]
+    fn file_modified_before_writing_replaced_source() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("test.md");
+        fs::write(&path, "foo bar\n").unwrap();
+        let mut preview = Preview::default();
+        assert!(preview.load_in_background(&path, &Config::default(), &NopWatcher).unwrap());
+        preview.activate(&TestWindow::default()).unwrap();
+
+        let replaced = preview.replace("foo", SearchMatcher::CaseSensitive, "baz").unwrap();
+        fs::write(&path, "foo bar\nmodified by editor\n").unwrap();
+        let err = preview.write_source(&replaced.source).unwrap_err();
+        assert!(format!("{err}").contains("was modified"), "{err}");
+        assert_eq!(fs::read_to_string(&path).unwrap(), "foo bar\nmodified by editor\n");
+    }
 }
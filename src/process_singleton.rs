@@ -1,6 +1,8 @@
 use crate::cli::PathArgs;
+use crate::config::Config;
 #[cfg(not(target_os = "windows"))]
 use crate::persistent::DataDir;
+use crate::remote::{RemoteCall, RemoteClient, serve};
 use crate::renderer::{Event, RendererHandle};
 use anyhow::{Context as _, Result};
 #[cfg(not(target_os = "windows"))]
@@ -19,7 +21,7 @@ use std::thread::spawn;
 
 const MAX_MESSAGE_SIZE: usize = 16 * 1024 * 1024; // 16 MiB
 
-fn encode<W: Write, T: Serialize>(mut writer: W, args: &T) -> io::Result<()> {
+pub fn encode<W: Write, T: Serialize>(mut writer: W, args: &T) -> io::Result<()> {
     let payload = serde_json::to_vec(args)?;
     if payload.len() > MAX_MESSAGE_SIZE {
         let msg = format!("Could not send too large message ({} bytes > 16 MiB)", payload.len());
@@ -33,7 +35,7 @@ fn encode<W: Write, T: Serialize>(mut writer: W, args: &T) -> io::Result<()> {
     Ok(())
 }
 
-fn decode<R: Read, T: DeserializeOwned>(mut reader: R) -> io::Result<T> {
+pub fn decode<R: Read, T: DeserializeOwned>(mut reader: R) -> io::Result<T> {
     let mut buf = [0u8; 4];
     reader.read_exact(&mut buf)?;
     let len = u32::from_ne_bytes(buf) as usize;
@@ -55,6 +57,22 @@ pub struct ProcessSingleton {
 }
 
 impl ProcessSingleton {
+    pub fn new(config: &Config) -> Self {
+        if !config.process().singleton {
+            log::debug!("Disable process singleton due to user's preference");
+            return Self::default();
+        }
+
+        #[cfg(not(target_os = "windows"))]
+        {
+            Self::with_socket_file(config.data_dir())
+        }
+        #[cfg(target_os = "windows")]
+        {
+            Self::with_default_namespace()
+        }
+    }
+
     // `GenericFilePath` does not work on Windows due to 'not a named pipe path' error
     #[cfg(not(target_os = "windows"))]
     pub fn with_socket_file(data_dir: &DataDir) -> Self {
@@ -88,26 +106,32 @@ impl ProcessSingleton {
         }
     }
 
-    pub fn send(&self, args: &PathArgs) -> Result<bool> {
+    // Connect to the existing process singleton and complete the handshake. `None` is returned when no server is running.
+    pub fn connect(&self) -> Result<Option<RemoteClient>> {
         let Some(name) = &self.name else {
-            log::debug!("Skip sending IPC message because socket file was not created");
-            return Ok(false);
+            log::debug!("Skip connecting to IPC server because socket file was not created");
+            return Ok(None);
         };
         match Stream::connect(name.clone()) {
-            Ok(mut conn) => {
-                encode(&mut conn, args)?;
-                log::debug!("Arguments are sent to the existing process singleton: {args:?}");
-                Ok(true)
-            }
+            Ok(stream) => RemoteClient::handshake(stream).map(Some),
             Err(err) => {
                 log::debug!(
-                    "Could not send IPC message probably because no server is running yet: {err:?}"
+                    "Could not connect to IPC server probably because no server is running yet: {err:?}"
                 );
-                Ok(false)
+                Ok(None)
             }
         }
     }
 
+    pub fn send(&self, args: &PathArgs) -> Result<bool> {
+        let Some(mut client) = self.connect()? else {
+            return Ok(false);
+        };
+        client.call(&RemoteCall::Open(args.clone()))?;
+        log::debug!("Arguments are sent to the existing process singleton: {args:?}");
+        Ok(true)
+    }
+
     fn remove_socket_file(&self) {
         if let Some(path) = &self.path {
             let _ = remove_file(path);
@@ -132,11 +156,17 @@ impl ProcessSingleton {
 
         spawn(move || {
             for conn in listener {
-                match conn
-                    .and_then(|mut stream| decode(&mut stream))
-                    .context("Failed to receive IPC message")
-                {
-                    Ok(paths) => handle.send(Event::ProcessSingleton { paths }),
+                match conn.context("Failed to accept IPC connection") {
+                    Ok(stream) => {
+                        let handle = handle.clone();
+                        // Each client is served in its own thread since it can keep the connection open
+                        // Errors of each connection are reported to the client instead of the user
+                        spawn(move || {
+                            if let Err(err) = serve(stream, &handle) {
+                                log::error!("Could not serve IPC client: {err:#}");
+                            }
+                        });
+                    }
                     Err(err) => {
                         handle.send(Event::Error(err));
                         break;
@@ -185,6 +215,15 @@ mod tests {
         }
     }
 
+    fn recv_open(renderer: &TestRenderer, expected_args: &PathArgs) {
+        let request = renderer.recv_timeout(Duration::from_secs(5));
+        let Request::Emit(Event::Remote { call: RemoteCall::Open(paths), reply }) = request else {
+            panic!("unexpected request: {request:?}");
+        };
+        assert_eq!(&paths, expected_args);
+        reply.send(Ok(serde_json::Value::Null));
+    }
+
     #[test]
     fn create_process_singleton() {
         #[cfg(not(target_os = "windows"))]
@@ -223,24 +262,16 @@ mod tests {
 
         let expected_args = PathArgs {
             init: Some("foo.md".into()),
+            line: Some(42),
             additional_windows: vec!["a.md".into(), "b.md".into()],
             watched: vec!["dir1".into(), "dir2".into()],
         };
         let sender = ProcessSingleton::with_socket_file(&DataDir::new(dir.path()));
-        let sent = send_with_retry(&sender, &expected_args, 5).unwrap();
-        assert!(sent);
-
-        let request = renderer.recv_timeout(Duration::from_secs(1));
-        assert!(
-            matches!(
-                &request,
-                Request::Emit(
-                    Event::ProcessSingleton { paths }
-                )
-                if &expected_args == paths,
-            ),
-            "request={request:?}, expected_args={expected_args:?}"
-        );
+        let args = expected_args.clone();
+        let sent = spawn(move || send_with_retry(&sender, &args, 5));
+
+        recv_open(&renderer, &expected_args);
+        assert!(sent.join().unwrap().unwrap());
 
         // Check the socket file is cleaned up when the singleton is dropped
         drop(listener);
@@ -259,24 +290,16 @@ mod tests {
 
         let expected_args = PathArgs {
             init: Some("foo.md".into()),
+            line: Some(42),
             additional_windows: vec!["a.md".into(), "b.md".into()],
             watched: vec!["dir1".into(), "dir2".into()],
         };
         let sender = ProcessSingleton::with_default_namespace();
-        let sent = send_with_retry(&sender, &expected_args, 5).unwrap();
-        assert!(sent);
-
-        let request = renderer.recv_timeout(Duration::from_secs(1));
-        assert!(
-            matches!(
-                &request,
-                Request::Emit(
-                    Event::ProcessSingleton { paths }
-                )
-                if &expected_args == paths,
-            ),
-            "request={request:?}, expected_args={expected_args:?}"
-        );
+        let args = expected_args.clone();
+        let sent = spawn(move || send_with_retry(&sender, &args, 5));
+
+        recv_open(&renderer, &expected_args);
+        assert!(sent.join().unwrap().unwrap());
     }
 
     #[test]
@@ -358,16 +381,15 @@ mod tests {
         let too_large_len = (16u32 * 1024 * 1024 + 1).to_ne_bytes();
         conn.write_all(&too_large_len).unwrap();
 
-        let request = renderer.recv_timeout(Duration::from_secs(1));
-        assert!(
-            matches!(
-                &request,
-                Request::Emit(Event::Error(err))
-                if err
-                    .chain()
-                    .any(|err| format!("{err}").contains("Could not receive too large message")),
-            ),
-            "unexpected request: {request:?}",
-        );
+        let response: serde_json::Value = decode(&mut conn).unwrap();
+        assert_eq!(response["error"]["code"], -32600, "{response}");
+        let message = response["error"]["message"].as_str().unwrap();
+        assert!(message.contains("Could not receive too large message"), "{response}");
+
+        // The server closes the connection without reporting the error to the user
+        let mut rest = vec![];
+        conn.read_to_end(&mut rest).unwrap();
+        assert!(rest.is_empty(), "{rest:?}");
+        assert!(renderer.try_recv().is_none());
     }
 }
@@ -0,0 +1,453 @@
+use crate::cli::{Options, PathArgs};
+use crate::config::Config;
+use crate::process_singleton::{ProcessSingleton, decode, encode};
+use crate::renderer::{Event, RendererHandle};
+use anyhow::{Context as _, Result};
+use interprocess::local_socket::Stream;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::io::{self, ErrorKind, Write};
+use std::sync::mpsc::{Sender, channel};
+
+// Remote control protocol over the socket of the process singleton. Each message is a JSON-RPC 2.0 request or response
+// encoded with `encode` and `decode`. A client must start with `hello` request to agree on the protocol version.
+// https://www.jsonrpc.org/specification
+
+pub const PROTOCOL_VERSION: u32 = 1;
+const JSONRPC_VERSION: &str = "2.0";
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const APP_ERROR: i64 = -32000;
+const HANDSHAKE_ERROR: i64 = -32001;
+
+const HELLO: &str = "hello";
+const METHODS: &[&str] = &[
+    HELLO,
+    "open",
+    "close_window",
+    "reload",
+    "scroll_to_heading",
+    "search",
+    "zoom",
+    "list_windows",
+    "current_path",
+];
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum HeadingTarget {
+    Index(usize),
+    Id(String),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ZoomDirection {
+    In,
+    Out,
+}
+
+// `window` parameters are the window numbers returned from `list_windows`. The focused window is targeted when omitted.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "method", content = "params", rename_all = "snake_case")]
+pub enum RemoteCall {
+    Open(PathArgs),
+    CloseWindow { window: Option<u32> },
+    Reload { window: Option<u32> },
+    ScrollToHeading { window: Option<u32>, heading: HeadingTarget },
+    Search { window: Option<u32>, query: String },
+    Zoom { window: Option<u32>, direction: ZoomDirection },
+    ListWindows {},
+    CurrentPath { window: Option<u32> },
+}
+
+// Channel to send the result of `RemoteCall` from the main thread back to the connection
+#[derive(Debug)]
+pub struct RemoteReply(Sender<Result<Value, String>>);
+
+impl RemoteReply {
+    pub fn send(self, result: Result<Value>) {
+        let result = result.map_err(|err| format!("{err:#}"));
+        if self.0.send(result).is_err() {
+            log::debug!("Could not reply to the remote call because the client has gone");
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl Into<String>) -> Self {
+        Self { code, message: message.into() }
+    }
+}
+
+// Response has either "result" or "error" field. Note that `null` is a valid result
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Outcome {
+    Result(Value),
+    Error(RpcError),
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Response {
+    jsonrpc: String,
+    id: Value,
+    #[serde(flatten)]
+    outcome: Outcome,
+}
+
+impl Response {
+    fn new(id: Value, result: Result<Value, RpcError>) -> Self {
+        let outcome = match result {
+            Ok(value) => Outcome::Result(value),
+            Err(error) => Outcome::Error(error),
+        };
+        Self { jsonrpc: JSONRPC_VERSION.into(), id, outcome }
+    }
+}
+
+struct Request {
+    id: Option<Value>,
+    method: String,
+    params: Value,
+}
+
+impl Request {
+    fn parse(value: Value) -> Result<Self, RpcError> {
+        let Value::Object(mut object) = value else {
+            return Err(RpcError::new(INVALID_REQUEST, "Request must be a JSON object"));
+        };
+        if object.get("jsonrpc").and_then(Value::as_str) != Some(JSONRPC_VERSION) {
+            return Err(RpcError::new(INVALID_REQUEST, r#""jsonrpc" must be "2.0""#));
+        }
+        let id = match object.remove("id") {
+            Some(id @ (Value::Number(_) | Value::String(_) | Value::Null)) => Some(id),
+            Some(id) => {
+                let msg = format!(r#""id" must be a number or a string but got {id}"#);
+                return Err(RpcError::new(INVALID_REQUEST, msg));
+            }
+            None => None, // Notification
+        };
+        let Some(Value::String(method)) = object.remove("method") else {
+            return Err(RpcError::new(INVALID_REQUEST, r#""method" must be a string"#));
+        };
+        let params = match object.remove("params") {
+            Some(params @ Value::Object(_)) => params,
+            Some(Value::Null) | None => json!({}),
+            Some(params) => {
+                let msg = format!(r#""params" must be an object but got {params}"#);
+                return Err(RpcError::new(INVALID_PARAMS, msg));
+            }
+        };
+        Ok(Self { id, method, params })
+    }
+}
+
+#[derive(Deserialize)]
+struct HelloParams {
+    version: u32,
+}
+
+#[derive(Default)]
+struct Session {
+    handshaked: bool,
+}
+
+impl Session {
+    fn hello(&mut self, params: Value) -> Result<Value, RpcError> {
+        let HelloParams { version } = serde_json::from_value(params).map_err(|err| {
+            RpcError::new(INVALID_PARAMS, format!("Invalid parameters for method \"hello\": {err}"))
+        })?;
+        if version != PROTOCOL_VERSION {
+            let msg = format!(
+                "Protocol version {version} is not supported. The supported version is {PROTOCOL_VERSION}",
+            );
+            return Err(RpcError::new(HANDSHAKE_ERROR, msg));
+        }
+        self.handshaked = true;
+        Ok(json!({ "version": PROTOCOL_VERSION, "app": env!("CARGO_PKG_VERSION") }))
+    }
+
+    fn call<H: RendererHandle>(
+        &self,
+        method: &str,
+        params: Value,
+        handle: &H,
+    ) -> Result<Value, RpcError> {
+        if !self.handshaked {
+            let msg = format!("Method {method:?} was called before \"hello\" handshake");
+            return Err(RpcError::new(HANDSHAKE_ERROR, msg));
+        }
+        if !METHODS.contains(&method) {
+            return Err(RpcError::new(METHOD_NOT_FOUND, format!("Unknown method {method:?}")));
+        }
+        let call = serde_json::from_value(json!({ "method": method, "params": params })).map_err(
+            |err| {
+                RpcError::new(
+                    INVALID_PARAMS,
+                    format!("Invalid parameters for method {method:?}: {err}"),
+                )
+            },
+        )?;
+
+        log::debug!("Remote call from IPC client: {call:?}");
+        let (tx, rx) = channel();
+        handle.send(Event::Remote { call, reply: RemoteReply(tx) });
+        match rx.recv() {
+            Ok(Ok(value)) => Ok(value),
+            Ok(Err(msg)) => Err(RpcError::new(APP_ERROR, msg)),
+            Err(_) => Err(RpcError::new(APP_ERROR, "Application did not reply to the request")),
+        }
+    }
+
+    fn handle<H: RendererHandle>(&mut self, request: Value, handle: &H) -> Option<Response> {
+        let Request { id, method, params } = match Request::parse(request) {
+            Ok(request) => request,
+            Err(err) => return Some(Response::new(Value::Null, Err(err))),
+        };
+        let result =
+            if method == HELLO { self.hello(params) } else { self.call(&method, params, handle) };
+        // No response is sent to a notification
+        id.map(|id| Response::new(id, result))
+    }
+}
+
+// Serve requests from the client until the connection is closed
+pub fn serve<H: RendererHandle>(mut stream: Stream, handle: &H) -> Result<()> {
+    let mut session = Session::default();
+    loop {
+        let response = match decode(&mut stream) {
+            Ok(request) => session.handle(request, handle),
+            Err(err) if err.kind() == ErrorKind::UnexpectedEof => {
+                log::debug!("IPC client closed the connection");
+                return Ok(());
+            }
+            Err(err) if err.kind() == ErrorKind::InvalidData => {
+                let err = RpcError::new(PARSE_ERROR, format!("Could not parse request: {err}"));
+                Some(Response::new(Value::Null, Err(err)))
+            }
+            Err(err) if err.kind() == ErrorKind::InvalidInput => {
+                // The payload of the message was not read so the following messages cannot be received. The connection
+                // is closed after replying the error
+                let error =
+                    RpcError::new(INVALID_REQUEST, format!("Could not receive request: {err}"));
+                encode(&mut stream, &Response::new(Value::Null, Err(error)))
+                    .context("Failed to send IPC message")?;
+                return Err(err).context("Failed to receive IPC message");
+            }
+            Err(err) => return Err(err).context("Failed to receive IPC message"),
+        };
+        if let Some(response) = response {
+            encode(&mut stream, &response).context("Failed to send IPC message")?;
+        }
+    }
+}
+
+pub struct RemoteClient {
+    stream: Stream,
+    id: u64,
+}
+
+impl RemoteClient {
+    pub fn handshake(stream: Stream) -> Result<Self> {
+        let mut client = Self { stream, id: 0 };
+        let hello = json!({ "method": HELLO, "params": { "version": PROTOCOL_VERSION } });
+        let result = client.request(hello).context("Handshake with the Shiba process failed")?;
+        log::debug!("Connected to the Shiba process: {result}");
+        Ok(client)
+    }
+
+    fn request(&mut self, mut request: Value) -> Result<Value> {
+        self.id += 1;
+        request["jsonrpc"] = JSONRPC_VERSION.into();
+        request["id"] = self.id.into();
+        encode(&mut self.stream, &request)?;
+
+        let response: Response = decode(&mut self.stream)
+            .context("Could not receive the response from Shiba process")?;
+        if response.id != self.id {
+            anyhow::bail!("Response ID {} does not match to request ID {}", response.id, self.id);
+        }
+        match response.outcome {
+            Outcome::Result(result) => Ok(result),
+            Outcome::Error(RpcError { code, message }) => anyhow::bail!("{message} (code: {code})"),
+        }
+    }
+
+    pub fn call(&mut self, call: &RemoteCall) -> Result<Value> {
+        self.request(serde_json::to_value(call)?)
+    }
+}
+
+pub fn remote(options: Options, call: RemoteCall) -> Result<()> {
+    let config = Config::load(options)?;
+    let Some(mut client) = ProcessSingleton::new(&config)
+        .connect()
+        .context("Could not connect to IPC socket for process singleton")?
+    else {
+        anyhow::bail!(
+            "Shiba process is not running. Note that `process.singleton` must be enabled in the config to control Shiba remotely",
+        );
+    };
+
+    let result = client.call(&call)?;
+    let mut stdout = io::stdout().lock();
+    match result {
+        Value::Null => {}
+        Value::String(s) => writeln!(stdout, "{s}")?,
+        v => writeln!(stdout, "{}", serde_json::to_string_pretty(&v)?)?,
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::{Renderer, Request as RendererRequest};
+    use crate::test::TestRenderer;
+    use std::thread::spawn;
+    use std::time::Duration;
+
+    fn request(method: &str, params: Value) -> Value {
+        json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params })
+    }
+
+    fn error_code(response: Option<Response>) -> i64 {
+        match response.unwrap().outcome {
+            Outcome::Error(err) => err.code,
+            Outcome::Result(res) => panic!("unexpected result: {res}"),
+        }
+    }
+
+    #[test]
+    fn handshake() {
+        let renderer = TestRenderer::default();
+        let handle = renderer.create_handle();
+        let mut session = Session::default();
+
+        let res = session.handle(request("list_windows", json!({})), &handle);
+        assert_eq!(error_code(res), HANDSHAKE_ERROR);
+
+        let res = session.handle(request(HELLO, json!({ "version": 0 })), &handle);
+        assert_eq!(error_code(res), HANDSHAKE_ERROR);
+        let res = session.handle(request(HELLO, json!({})), &handle);
+        assert_eq!(error_code(res), INVALID_PARAMS);
+        assert!(!session.handshaked);
+
+        let res = session.handle(request(HELLO, json!({ "version": PROTOCOL_VERSION })), &handle);
+        let res = res.unwrap();
+        assert_eq!(res.id, json!(1));
+        let Outcome::Result(hello) = res.outcome else { panic!("{res:?}") };
+        assert_eq!(hello["version"], json!(PROTOCOL_VERSION));
+        assert!(session.handshaked);
+    }
+
+    #[test]
+    fn invalid_requests() {
+        let renderer = TestRenderer::default();
+        let handle = renderer.create_handle();
+        let mut session = Session { handshaked: true };
+
+        for (req, code) in [
+            (json!([]), INVALID_REQUEST),
+            (json!({ "id": 1, "method": "reload" }), INVALID_REQUEST),
+            (json!({ "jsonrpc": "1.0", "id": 1, "method": "reload" }), INVALID_REQUEST),
+            (json!({ "jsonrpc": "2.0", "id": [], "method": "reload" }), INVALID_REQUEST),
+            (json!({ "jsonrpc": "2.0", "id": 1, "method": 42 }), INVALID_REQUEST),
+            (
+                json!({ "jsonrpc": "2.0", "id": 1, "method": "reload", "params": [] }),
+                INVALID_PARAMS,
+            ),
+            (request("foo", json!({})), METHOD_NOT_FOUND),
+            (request("search", json!({})), INVALID_PARAMS),
+            (request("zoom", json!({ "direction": "up" })), INVALID_PARAMS),
+            (request("close_window", json!({ "window": "x" })), INVALID_PARAMS),
+        ] {
+            let res = session.handle(req.clone(), &handle);
+            assert_eq!(error_code(res), code, "request: {req}");
+        }
+
+        // Notification does not have response
+        let req = json!({ "jsonrpc": "2.0", "method": "foo" });
+        assert!(session.handle(req, &handle).is_none());
+    }
+
+    #[test]
+    fn parse_all_methods() {
+        for (method, params) in [
+            ("open", json!({ "init": "/path/to/file.md", "line": 3 })),
+            ("open", json!({})),
+            ("close_window", json!({ "window": 1 })),
+            ("reload", json!({})),
+            ("scroll_to_heading", json!({ "heading": 2 })),
+            ("scroll_to_heading", json!({ "heading": "some-id" })),
+            ("search", json!({ "query": "foo" })),
+            ("zoom", json!({ "direction": "in" })),
+            ("list_windows", json!({})),
+            ("current_path", json!({ "window": 2 })),
+        ] {
+            assert!(METHODS.contains(&method), "{method:?}");
+            let value = json!({ "method": method, "params": params });
+            let call: RemoteCall = serde_json::from_value(value.clone()).unwrap();
+            let mut want = value;
+            let have = serde_json::to_value(&call).unwrap();
+            // Omitted optional parameters are serialized as null
+            for (k, v) in have["params"].as_object().unwrap() {
+                want["params"].as_object_mut().unwrap().entry(k).or_insert(v.clone());
+            }
+            assert_eq!(have, want);
+        }
+    }
+
+    #[test]
+    fn null_result_response() {
+        let res = Response::new(json!(1), Ok(Value::Null));
+        let json = serde_json::to_value(&res).unwrap();
+        assert_eq!(json, json!({ "jsonrpc": "2.0", "id": 1, "result": null }));
+        let res: Response = serde_json::from_value(json).unwrap();
+        assert_eq!(res.outcome, Outcome::Result(Value::Null));
+    }
+
+    #[test]
+    fn call_main_thread() {
+        let renderer = TestRenderer::default();
+        let handle = renderer.create_handle();
+
+        let server = spawn(move || {
+            let mut session = Session { handshaked: true };
+            let ok = session.handle(request("current_path", json!({})), &handle).unwrap();
+            let err = session.handle(request("reload", json!({ "window": 42 })), &handle).unwrap();
+            (ok, err)
+        });
+
+        for expected in
+            [RemoteCall::CurrentPath { window: None }, RemoteCall::Reload { window: Some(42) }]
+        {
+            let RendererRequest::Emit(Event::Remote { call, reply }) =
+                renderer.recv_timeout(Duration::from_secs(1))
+            else {
+                panic!("unexpected request");
+            };
+            assert_eq!(call, expected);
+            let result = match call {
+                RemoteCall::CurrentPath { .. } => Ok(json!("/path/to/file.md")),
+                _ => Err(anyhow::anyhow!("unknown window number: 42")),
+            };
+            reply.send(result);
+        }
+
+        let (ok, err) = server.join().unwrap();
+        assert_eq!(ok.outcome, Outcome::Result(json!("/path/to/file.md")));
+        let error = RpcError::new(APP_ERROR, "unknown window number: 42");
+        assert_eq!(err.outcome, Outcome::Error(error));
+    }
+}
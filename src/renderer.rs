@@ -1,6 +1,9 @@
-use crate::cli::PathArgs;
+use crate::bookmarks::Bookmark;
 use crate::config::{Config, KeyAction, Search as SearchConfig, SearchMatcher};
+use crate::markdown::{Metadata, ReplacedHunk};
 use crate::persistent::PersistentData;
+use crate::remote::{RemoteCall, RemoteReply};
+use crate::search_index::{FileHits, SearchAllResult};
 use anyhow::{Error, Result};
 use indexmap::IndexSet;
 use raw_window_handle::{
@@ -12,7 +15,6 @@ use std::fmt::{self, Debug};
 use std::hash::Hash;
 use std::io;
 use std::path::{Path, PathBuf};
-use std::rc::Rc;
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct WindowState {
@@ -44,6 +46,8 @@ pub struct WindowAppearance {
 pub enum ScrollRequest<'a> {
     Fragment(&'a str),
     Heading(usize),
+    Block(usize),
+    BlockTop(usize), // Put the block at the top of the viewport to restore the previous scroll position
 }
 
 #[derive(Serialize)]
@@ -59,19 +63,57 @@ pub enum MessageToWindow<'a> {
     Path {
         path: &'a Path,
     },
-    Search,
+    Metadata {
+        metadata: Option<&'a Metadata>,
+    },
+    Search {
+        // Query to start searching. This is set when the search is requested remotely
+        #[serde(skip_serializing_if = "Option::is_none")]
+        query: Option<&'a str>,
+        // Matcher for the query. This is set when the search state of the document is restored
+        #[serde(skip_serializing_if = "Option::is_none")]
+        matcher: Option<SearchMatcher>,
+    },
     SearchNext,
     SearchPrevious,
+    SearchAll,
+    SearchAllResult {
+        query: &'a str,
+        files: &'a [FileHits],
+        #[serde(skip_serializing_if = "Option::is_none")]
+        error: Option<&'a str>,
+    },
+    ReplacePreview {
+        path: &'a Path,
+        count: usize,
+        skipped: usize,
+        hunks: &'a [ReplacedHunk],
+    },
+    ReplaceEnd,
     Welcome,
     Outline,
     History {
         paths: &'a IndexSet<PathBuf>,
     },
+    Bookmark,
+    Bookmarks {
+        bookmarks: &'a [Bookmark],
+    },
+    Tabs {
+        paths: &'a [Option<&'a Path>], // `None` means an empty tab
+        current: usize,
+    },
+    ToggleSplitView,
+    Source {
+        source: &'a str,
+        blocks: &'a [(usize, usize)], // Start and end lines of the top-level blocks
+    },
     Help,
     Zoomed {
         percent: u16,
     },
     Reload,
+    ReloadStyles,
     AlwaysOnTop {
         pinned: bool,
     },
@@ -95,9 +137,15 @@ pub enum MessageFromWindow {
     GoBack,
     GoTop,
     History,
+    Bookmarks,
+    AddBookmark { label: String, heading: Option<usize>, fragment: Option<String> },
+    OpenBookmark { index: usize, new_window: bool },
+    RemoveBookmark { index: usize },
     Quit,
     Search { query: String, index: Option<usize>, matcher: SearchMatcher },
-    OpenFile { path: String },
+    SearchAll { query: String, matcher: SearchMatcher },
+    Replace { query: String, matcher: SearchMatcher, replacement: String },
+    OpenFile { path: String, line: Option<usize> },
     ZoomIn,
     ZoomOut,
     DragWindow,
@@ -107,10 +155,18 @@ pub enum MessageFromWindow {
     DuplicateWindow { heading: Option<usize> },
     CloseWindow,
     CloseAllOtherWindows,
+    NewTab,
+    NextTab,
+    PreviousTab,
+    SelectTab { index: usize },
+    CloseTab { index: Option<usize> },
     OpenMenu { position: Option<(f64, f64)> },
     ToggleMenuBar,
     ToggleAlwaysOnTop,
     EditConfig,
+    OpenSource { offset: usize },
+    Source,
+    ScrollPosition { block: usize },
     Error { message: String },
 }
 
@@ -118,6 +174,8 @@ pub enum MessageFromWindow {
 pub enum InitScroll {
     Fragment(String),
     Heading(usize),
+    Line(usize),
+    BlockTop(usize),
     Nop,
 }
 
@@ -133,6 +191,13 @@ impl From<PathBuf> for InitFile {
     }
 }
 
+impl InitFile {
+    pub fn at_line(path: PathBuf, line: Option<usize>) -> Self {
+        let scroll = line.map_or(InitScroll::Nop, InitScroll::Line);
+        Self { path, scroll }
+    }
+}
+
 #[derive(Debug)]
 pub enum Event<WindowId> {
     WindowMessage { message: MessageFromWindow, id: WindowId },
@@ -143,7 +208,9 @@ pub enum Event<WindowId> {
     Menu(MenuItem),
     NewWindow { init_file: Option<InitFile> },
     DuplicateWindow { scroll: InitScroll, id: WindowId },
-    ProcessSingleton { paths: PathArgs },
+    Remote { call: RemoteCall, reply: RemoteReply },
+    SearchAllResult { id: WindowId, result: SearchAllResult },
+    DiagramsRendered(PathBuf),
     Error(Error),
 }
 
@@ -151,6 +218,7 @@ pub enum Event<WindowId> {
 pub enum Request<WindowId> {
     Emit(Event<WindowId>),
     CreateWindow,
+    RestoreWindow(WindowState),
 }
 
 pub enum WindowEvent<W> {
@@ -184,11 +252,15 @@ pub enum MenuItem {
     Search,
     SearchNext,
     SearchPrevious,
+    SearchAll,
     Outline,
+    ToggleSplitView,
     Print,
     ZoomIn,
     ZoomOut,
     History,
+    Bookmark,
+    Bookmarks,
     Help,
     OpenRepo,
     ToggleAlwaysOnTop,
@@ -198,6 +270,10 @@ pub enum MenuItem {
     DuplicateWindow,
     CloseWindow,
     CloseAllOtherWindows,
+    NewTab,
+    NextTab,
+    PreviousTab,
+    CloseTab,
     EditConfig,
     #[cfg(not(target_os = "macos"))]
     ToggleMenuBar,
@@ -280,6 +356,8 @@ pub trait RendererHandle: 'static + Send + Clone {
 
     fn send(&self, event: Event<Self::WindowId>);
     fn create_window(&self);
+    /// Creates a new window with the state saved in the previous session.
+    fn restore_window(&self, state: WindowState);
 }
 
 /// Window is responsible for rendering a single window in the rendering context.
@@ -307,6 +385,8 @@ pub trait Window {
     fn toggle_menu(&mut self) -> Result<()>;
     fn save_memory(&mut self, is_low: bool) -> Result<()>;
     fn delete_cache(&mut self) -> Result<()>;
+    /// Reloads the assets such as stylesheets with the config. The window needs to load them again to apply them.
+    fn reload_assets(&mut self, config: &Config);
     fn handles(&self) -> WindowHandles<'_>;
     fn id(&self) -> Self::Id;
     fn focus(&self);
@@ -318,7 +398,7 @@ pub trait Renderer: Sized {
     type Window: Window<Id = Self::WindowId>;
     type Handle: RendererHandle<WindowId = Self::WindowId>;
 
-    fn new(config: Rc<Config>) -> Result<Self>;
+    fn new() -> Result<Self>;
     fn create_handle(&self) -> Self::Handle;
     /// Starts the rendering execution and runs until the process exits.
     fn start<H>(self, handler: H) -> !
@@ -331,6 +411,8 @@ pub trait EventHandler {
     type Window: Window;
     type WindowId: 'static + PartialEq + Eq + Hash + Clone + Copy + Debug + Send + Sync;
 
+    /// The current application config. It may be changed while running by reloading the config file.
+    fn config(&self) -> &Config;
     fn on_event(&mut self, event: Event<Self::WindowId>) -> RenderingFlow;
     fn on_window(&mut self, id: Self::WindowId, event: WindowEvent<Self::Window>) -> RenderingFlow;
 }
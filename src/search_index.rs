@@ -0,0 +1,526 @@
+use crate::cli::Options;
+use crate::config::{Config, FileExtensions, Markdown, SearchMatcher};
+use crate::fulltext::{FileStamp, FullTextIndex};
+use crate::markdown::{DisplayText, Heading, MarkdownContent, MarkdownParser, ScopedQuery};
+use crate::persistent::DataDir;
+use crate::renderer::{Event, RawMessageWriter as _, RendererHandle};
+use anyhow::Result;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::env;
+use std::fs;
+use std::io::{self, Write as _};
+use std::mem;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{Receiver, Sender, channel};
+use std::thread::spawn;
+
+// Search across all Markdown files in the watched directories. Files are indexed and searched on a worker thread so
+// that the main thread is not blocked by the I/O and the parsing. The result is sent back as `Event::SearchAllResult`.
+
+const MAX_HITS: usize = 500;
+const MAX_HITS_PER_FILE: usize = 20;
+const SNIPPET_BEFORE: usize = 40; // in characters
+const SNIPPET_AFTER: usize = 80; // in characters
+
+#[derive(Debug, Serialize, PartialEq, Eq)]
+pub struct SearchHit {
+    pub line: usize,
+    // The line of the match is split into the text before the match, the matched text, and the text after the match
+    pub before: String,
+    pub matched: String,
+    pub after: String,
+    // Texts of the headings of the sections which contain the match from outer to inner
+    pub headings: Vec<String>,
+}
+
+#[derive(Debug, Serialize, PartialEq, Eq)]
+pub struct FileHits {
+    pub path: PathBuf,
+    pub hits: Vec<SearchHit>,
+    pub total: usize,
+}
+
+#[derive(Debug)]
+pub struct SearchAllResult {
+    pub query: String,
+    pub files: Result<Vec<FileHits>, String>,
+}
+
+struct Document {
+    source: String,
+    text: DisplayText,
+    headings: Vec<Heading>,
+}
+
+impl Document {
+    fn parse(path: &Path, markdown: &Markdown) -> io::Result<Self> {
+        let source = fs::read_to_string(path)?;
+        let content = MarkdownContent::new(source, path.parent(), markdown);
+        let text: DisplayText = MarkdownParser::new(&content, None, ()).write_to(io::sink())?;
+        let headings = content.headings();
+        let source = content.source().to_string();
+        Ok(Self { source, text, headings })
+    }
+
+    fn search(
+        &self,
+        query: &str,
+        matcher: SearchMatcher,
+        limit: usize,
+    ) -> Result<(Vec<SearchHit>, usize)> {
+        let matches = self.text.search(query, matcher)?;
+        let hits = matches
+            .ranges()
+            .iter()
+            .take(limit.min(MAX_HITS_PER_FILE))
+            .map(|range| self.hit(range.start, range.end))
+            .collect();
+        Ok((hits, matches.len()))
+    }
+
+    fn hit(&self, start: usize, end: usize) -> SearchHit {
+        let src = self.source.as_str();
+        let line_start = src[..start].rfind('\n').map(|i| i + 1).unwrap_or(0);
+        let line_end = src[start..].find(['\r', '\n']).map(|i| start + i).unwrap_or(src.len());
+        let end = end.min(line_end); // The match may be across lines
+
+        let before = src[line_start..start].trim_start();
+        let before = match before.char_indices().rev().nth(SNIPPET_BEFORE) {
+            Some((i, _)) => format!("…{}", &before[i + 1..]),
+            None => before.to_string(),
+        };
+        let after = &src[end..line_end];
+        let after = match after.char_indices().nth(SNIPPET_AFTER) {
+            Some((i, _)) => format!("{}…", &after[..i]),
+            None => after.to_string(),
+        };
+
+        let mut headings: Vec<&Heading> = vec![];
+        for heading in self.headings.iter().take_while(|h| h.offset <= start) {
+            while headings.last().is_some_and(|h| h.level >= heading.level) {
+                headings.pop();
+            }
+            headings.push(heading);
+        }
+
+        SearchHit {
+            line: src[..start].matches('\n').count() + 1,
+            before,
+            matched: src[start..end].to_string(),
+            after,
+            headings: headings.into_iter().map(|h| h.text.clone()).collect(),
+        }
+    }
+}
+
+fn is_hidden(path: &Path) -> bool {
+    path.file_name().and_then(|n| n.to_str()).is_some_and(|n| n.starts_with('.'))
+}
+
+fn uses_fulltext_index(matcher: SearchMatcher) -> bool {
+    match matcher {
+        SearchMatcher::SmartCase
+        | SearchMatcher::CaseSensitive
+        | SearchMatcher::CaseInsensitive
+        | SearchMatcher::WholeWord => true,
+        // Characters matched by fuzzy search can be across multiple words
+        SearchMatcher::Fuzzy
+        | SearchMatcher::CaseSensitiveRegex
+        | SearchMatcher::CaseInsensitiveRegex => false,
+    }
+}
+
+struct Index {
+    markdown: Markdown,
+    extensions: FileExtensions,
+    data_dir: DataDir,
+    roots: HashSet<PathBuf>, // Directories or files to be searched
+    fulltext: FullTextIndex,
+}
+
+impl Index {
+    fn new(markdown: Markdown, extensions: FileExtensions, data_dir: DataDir) -> Self {
+        let fulltext = FullTextIndex::load(&data_dir);
+        Self { markdown, extensions, data_dir, roots: HashSet::new(), fulltext }
+    }
+
+    fn update_file(&mut self, path: &Path) {
+        let stamp = match path.metadata() {
+            Ok(md) if md.is_file() => FileStamp::new(&md),
+            _ => {
+                if self.fulltext.remove(path) {
+                    log::debug!("Removed file from search index: {path:?}");
+                }
+                return;
+            }
+        };
+        if self.fulltext.is_fresh(path, stamp) {
+            return;
+        }
+        match Document::parse(path, &self.markdown) {
+            Ok(doc) => self.fulltext.insert(path.to_path_buf(), doc.text.raw_text(), stamp),
+            Err(err) => {
+                log::debug!("Could not index file {path:?}: {err}");
+                self.fulltext.remove(path);
+            }
+        }
+    }
+
+    fn add_file(&mut self, path: PathBuf) {
+        self.update_file(&path);
+        self.roots.insert(path);
+    }
+
+    fn add_dir(&mut self, dir: PathBuf) {
+        if self.roots.iter().any(|d| dir.starts_with(d)) {
+            return;
+        }
+        log::debug!("Indexing Markdown files in directory {dir:?}");
+        let mut found = HashSet::new();
+        let mut stack = vec![dir.clone()];
+        while let Some(dir) = stack.pop() {
+            let entries = match fs::read_dir(&dir) {
+                Ok(entries) => entries,
+                Err(err) => {
+                    log::debug!("Could not read directory {dir:?} for search index: {err}");
+                    continue;
+                }
+            };
+            for entry in entries.flatten() {
+                let path = entry.path();
+                if is_hidden(&path) {
+                    continue;
+                }
+                match entry.file_type() {
+                    Ok(ty) if ty.is_dir() => stack.push(path),
+                    Ok(ty) if ty.is_file() && self.extensions.matches(&path) => {
+                        self.update_file(&path);
+                        found.insert(path);
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        // Remove the files which were deleted while Shiba was not running
+        let deleted: Vec<_> = self
+            .fulltext
+            .paths()
+            .filter(|p| p.starts_with(&dir) && !found.contains(*p))
+            .map(Path::to_path_buf)
+            .collect();
+        for path in deleted {
+            self.fulltext.remove(&path);
+        }
+
+        self.roots.retain(|d| !d.starts_with(&dir));
+        self.roots.insert(dir);
+        log::debug!("{} files are in the search index", self.fulltext.len());
+    }
+
+    fn update_files(&mut self, paths: Vec<PathBuf>) {
+        for path in paths {
+            let indexed = self.fulltext.contains(&path)
+                || self.extensions.matches(&path) && self.roots.iter().any(|d| path.starts_with(d));
+            if indexed {
+                log::debug!("Update search index for file {path:?}");
+                self.update_file(&path);
+            }
+        }
+    }
+
+    fn save(&mut self) {
+        if let Err(err) = self.fulltext.save(&self.data_dir) {
+            log::error!("Could not save the search index: {err:?}");
+        }
+    }
+
+    fn search(&self, query: &str, matcher: SearchMatcher) -> Result<Vec<FileHits>> {
+        let mut files = vec![];
+        if query.is_empty() {
+            return Ok(files);
+        }
+
+        let in_roots = |path: &Path| self.roots.iter().any(|r| path.starts_with(r));
+        // The full-text index narrows down the files to search and ranks them. The matcher finds the exact matches
+        // in the files. Regular expressions cannot be looked up in the index so all files are searched. Qualifiers
+        // in the query are not indexed.
+        let text = ScopedQuery::parse(query).text();
+        let ranked =
+            uses_fulltext_index(matcher).then(|| self.fulltext.query(text, in_roots)).flatten();
+        let candidates = match ranked {
+            Some(ranked) => ranked.into_iter().map(|(path, _)| path).collect(),
+            None => {
+                let mut paths: Vec<_> = self.fulltext.paths().filter(|p| in_roots(p)).collect();
+                paths.sort();
+                paths
+            }
+        };
+
+        let mut limit = MAX_HITS;
+        for path in candidates {
+            if limit == 0 {
+                break;
+            }
+            let doc = match Document::parse(path, &self.markdown) {
+                Ok(doc) => doc,
+                Err(err) => {
+                    log::debug!("Could not search file {path:?}: {err}"); // The file may be deleted after it was indexed
+                    continue;
+                }
+            };
+            let (hits, total) = doc.search(query, matcher, limit)?;
+            if total == 0 {
+                continue;
+            }
+            limit -= hits.len();
+            files.push(FileHits { path: path.to_path_buf(), hits, total });
+        }
+        Ok(files)
+    }
+}
+
+// Search the files in the paths from command line without opening a window
+pub fn search(mut options: Options, query: String) -> Result<()> {
+    let paths = mem::take(&mut options.paths);
+    let mut paths: Vec<_> =
+        paths.init.into_iter().chain(paths.additional_windows).chain(paths.watched).collect();
+    if paths.is_empty() {
+        paths.push(env::current_dir()?.canonicalize()?);
+    }
+
+    let config = Config::load(options)?;
+    let extensions = config.watch().file_extensions.clone();
+    let mut index = Index::new(*config.markdown(), extensions, config.data_dir().clone());
+    for path in paths {
+        if path.is_dir() {
+            index.add_dir(path);
+        } else if path.is_file() {
+            index.add_file(path);
+        } else {
+            anyhow::bail!("Path to search does not exist: {path:?}");
+        }
+    }
+    index.save();
+
+    let files = index.search(&query, config.search().matcher)?;
+    let mut stdout = io::stdout().lock();
+    for FileHits { path, hits, .. } in files {
+        for SearchHit { line, before, matched, after, .. } in hits {
+            writeln!(stdout, "{}:{line}: {before}{matched}{after}", path.display())?;
+        }
+    }
+    Ok(())
+}
+
+enum Request<Id> {
+    WatchDirs(Vec<PathBuf>),
+    UpdateFiles(Vec<PathBuf>),
+    Search { id: Id, query: String, matcher: SearchMatcher },
+}
+
+fn run_worker<H: RendererHandle>(rx: Receiver<Request<H::WindowId>>, handle: H, mut index: Index) {
+    while let Ok(req) = rx.recv() {
+        match req {
+            Request::WatchDirs(dirs) => {
+                for dir in dirs {
+                    index.add_dir(dir);
+                }
+                index.save();
+            }
+            Request::UpdateFiles(paths) => {
+                index.update_files(paths);
+                index.save();
+            }
+            Request::Search { id, query, matcher } => {
+                let files = index.search(&query, matcher).map_err(|err| format!("{err}"));
+                let result = SearchAllResult { query, files };
+                handle.send(Event::SearchAllResult { id, result });
+            }
+        }
+    }
+    log::debug!("Search index worker thread has stopped");
+}
+
+pub struct SearchIndex<Id> {
+    tx: Sender<Request<Id>>,
+}
+
+impl<Id: Send + 'static> SearchIndex<Id> {
+    pub fn new<H>(handle: H, config: &Config) -> Self
+    where
+        H: RendererHandle<WindowId = Id>,
+    {
+        let (tx, rx) = channel();
+        let markdown = *config.markdown();
+        let extensions = config.watch().file_extensions.clone();
+        let data_dir = config.data_dir().clone();
+        // Loading the index from the data directory may take time
+        spawn(move || run_worker(rx, handle, Index::new(markdown, extensions, data_dir)));
+        Self { tx }
+    }
+
+    fn send(&self, req: Request<Id>) {
+        if self.tx.send(req).is_err() {
+            log::error!("Could not send a request to the search index worker since it has stopped");
+        }
+    }
+
+    // Directories which were already indexed are ignored
+    pub fn watch_dirs(&self, dirs: Vec<PathBuf>) {
+        self.send(Request::WatchDirs(dirs));
+    }
+
+    pub fn update_files(&self, paths: Vec<PathBuf>) {
+        self.send(Request::UpdateFiles(paths));
+    }
+
+    pub fn search(&self, id: Id, query: String, matcher: SearchMatcher) {
+        self.send(Request::Search { id, query, matcher });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::UserConfig;
+    use crate::renderer::{Renderer as _, Request as RendererRequest};
+    use crate::test::TestRenderer;
+    use std::time::Duration;
+
+    fn doc(source: &str) -> Document {
+        let content = MarkdownContent::new(source.into(), None, &Markdown::default());
+        let text: DisplayText =
+            MarkdownParser::new(&content, None, ()).write_to(io::sink()).unwrap();
+        Document { source: source.into(), text, headings: content.headings() }
+    }
+
+    #[test]
+    fn search_hits_in_document() {
+        let doc =
+            doc("# Intro\n\nfoo **bar** baz\n\n## Usage\n\n- item with bar\n\n# Other\n\n`bar`\n");
+        let (hits, total) = doc.search("bar", SearchMatcher::CaseSensitive, 10).unwrap();
+        assert_eq!(total, 3);
+        let have: Vec<_> = hits
+            .iter()
+            .map(|h| {
+                (
+                    h.line,
+                    h.before.as_str(),
+                    h.matched.as_str(),
+                    h.after.as_str(),
+                    h.headings.join(" > "),
+                )
+            })
+            .collect();
+        let want = [
+            (3, "foo **", "bar", "** baz", "Intro".to_string()),
+            (7, "- item with ", "bar", "", "Intro > Usage".to_string()),
+            (11, "`", "bar", "`", "Other".to_string()),
+        ];
+        assert_eq!(have, want);
+
+        let (hits, total) = doc.search("bar", SearchMatcher::CaseSensitive, 1).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(total, 3);
+
+        let (hits, total) = doc.search("Usage", SearchMatcher::CaseSensitive, 10).unwrap();
+        assert_eq!(total, 1);
+        assert_eq!(hits[0].headings, ["Intro", "Usage"]);
+
+        assert!(doc.search("(", SearchMatcher::CaseSensitiveRegex, 10).is_err());
+    }
+
+    #[test]
+    fn truncate_long_snippet() {
+        let before = "a".repeat(100);
+        let after = "あ".repeat(100);
+        let doc = doc(&format!("{before}match{after}\n"));
+        let (hits, _) = doc.search("match", SearchMatcher::CaseSensitive, 10).unwrap();
+        let hit = &hits[0];
+        assert_eq!(hit.before, format!("…{}", "a".repeat(SNIPPET_BEFORE)));
+        assert_eq!(hit.matched, "match");
+        assert_eq!(hit.after, format!("{}…", "あ".repeat(SNIPPET_AFTER)));
+    }
+
+    #[test]
+    fn search_files_in_dirs() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        fs::create_dir_all(root.join("sub")).unwrap();
+        fs::create_dir_all(root.join(".hidden")).unwrap();
+        fs::write(root.join("a.md"), "# A\n\nhello world\n").unwrap();
+        fs::write(root.join("sub").join("b.md"), "hello\n").unwrap();
+        fs::write(root.join(".hidden").join("c.md"), "hello\n").unwrap();
+        fs::write(root.join("d.txt"), "hello\n").unwrap();
+
+        let data = tempfile::tempdir().unwrap();
+        let config = Config::new(UserConfig::default(), root.join("config"), data.path());
+        let renderer = TestRenderer::default();
+        let index = SearchIndex::new(renderer.create_handle(), &config);
+        index.watch_dirs(vec![root.clone()]);
+
+        let search = |query: &str| {
+            index.search(42, query.into(), SearchMatcher::SmartCase);
+            match renderer.recv_timeout(Duration::from_secs(5)) {
+                RendererRequest::Emit(Event::SearchAllResult { id: 42, result }) => {
+                    assert_eq!(result.query, query);
+                    result
+                        .files
+                        .unwrap()
+                        .into_iter()
+                        .map(|f| (f.path.strip_prefix(&root).unwrap().to_path_buf(), f.total))
+                        .collect::<Vec<_>>()
+                }
+                req => panic!("unexpected request: {req:?}"),
+            }
+        };
+
+        // Shorter document is ranked higher
+        let want = [(Path::new("sub").join("b.md"), 1), (PathBuf::from("a.md"), 1)];
+        assert_eq!(search("hello"), want);
+        assert_eq!(FullTextIndex::load(config.data_dir()).len(), 2);
+        assert_eq!(search("world"), [(PathBuf::from("a.md"), 1)]);
+        assert_eq!(search(""), []);
+
+        fs::write(root.join("sub").join("b.md"), "world world\n").unwrap();
+        fs::write(root.join("e.md"), "world\n").unwrap();
+        index.update_files(vec![root.join("sub").join("b.md"), root.join("e.md")]);
+        let want = [
+            (Path::new("sub").join("b.md"), 2),
+            (PathBuf::from("e.md"), 1),
+            (PathBuf::from("a.md"), 1),
+        ];
+        assert_eq!(search("world"), want);
+        assert_eq!(search("(world|hello)"), []);
+        assert_eq!(search("world$"), []);
+
+        fs::remove_file(root.join("a.md")).unwrap();
+        assert_eq!(search("hello"), []); // Deleted file is not searched even if the index was not updated
+        index.update_files(vec![root.join("a.md")]);
+        assert_eq!(
+            search("world"),
+            [(Path::new("sub").join("b.md"), 2), (PathBuf::from("e.md"), 1)]
+        );
+
+        // The index is restored from the data directory and stale entries are updated
+        drop(index);
+        fs::write(root.join("e.md"), "hello hello\n").unwrap();
+        let mut index =
+            Index::new(Markdown::default(), FileExtensions::default(), config.data_dir().clone());
+        assert_eq!(index.fulltext.len(), 2);
+        assert!(
+            index.search("hello", SearchMatcher::SmartCase).unwrap().is_empty(),
+            "not searched yet"
+        );
+        index.add_dir(root.clone());
+        let have: Vec<_> = index
+            .search("hello", SearchMatcher::SmartCase)
+            .unwrap()
+            .into_iter()
+            .map(|f| f.path)
+            .collect();
+        assert_eq!(have, [root.join("e.md")]);
+    }
+}
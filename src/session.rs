@@ -0,0 +1,218 @@
+use crate::config::Config;
+use crate::persistent::{DataDir, PersistentData};
+use crate::renderer::{InitFile, InitScroll, WindowState};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::path::PathBuf;
+
+// Session is the snapshot of all windows and their tabs. It is updated when they change and on quit, and it is
+// restored on the next launch to recreate the windows. Named sessions specified by `--session` are saved in separate
+// files so that the window layouts can be switched per project.
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct TabSession {
+    pub path: PathBuf,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub block: Option<usize>, // Index of the top-level block at the top of the viewport
+}
+
+impl TabSession {
+    pub fn init_file(&self) -> InitFile {
+        let scroll = self.block.map_or(InitScroll::Nop, InitScroll::BlockTop);
+        InitFile { path: self.path.clone(), scroll }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct WindowSession {
+    pub state: WindowState,
+    pub tabs: Vec<TabSession>,
+    #[serde(default)]
+    pub current: usize, // Index of the selected tab
+}
+
+impl WindowSession {
+    pub fn init_files(&self) -> Vec<InitFile> {
+        self.tabs.iter().map(TabSession::init_file).collect()
+    }
+}
+
+#[derive(Serialize, Deserialize, Default, Debug)]
+pub struct Session {
+    windows: Vec<WindowSession>,
+}
+
+impl PersistentData for Session {
+    const FILE: &str = "session.json";
+}
+
+impl Session {
+    pub fn new(windows: Vec<WindowSession>) -> Self {
+        Self { windows }
+    }
+
+    // The default session is restored only when restoring the window state is enabled. A named session is always
+    // restored since it is explicitly specified by user.
+    pub fn is_enabled(config: &Config) -> bool {
+        config.session().is_some() || config.window().restore
+    }
+
+    fn file_name(name: Option<&str>) -> Cow<'static, str> {
+        match name {
+            Some(name) => format!("session.{name}.json").into(),
+            None => Self::FILE.into(),
+        }
+    }
+
+    pub fn load(data_dir: &DataDir, name: Option<&str>) -> Self {
+        let session: Self = data_dir.load_from(&Self::file_name(name)).unwrap_or_default();
+        log::debug!("Loaded session {name:?} with {} windows", session.windows.len());
+        session
+    }
+
+    pub fn save(&self, data_dir: &DataDir, name: Option<&str>) -> Result<()> {
+        log::debug!("Saving session {name:?} with {} windows", self.windows.len());
+        data_dir.save_to(self, &Self::file_name(name))
+    }
+
+    pub fn into_windows(self) -> Vec<WindowSession> {
+        self.windows
+    }
+}
+
+// The session is saved when windows or tabs are opened, closed or switched as well as on quit so that it is not lost
+// even if the process is terminated without quitting the application. Scrolling documents does not save the session
+// to avoid writing the file frequently. The scroll positions are saved together at the next save.
+#[derive(Default)]
+pub struct SessionWriter {
+    changed: bool,
+}
+
+impl SessionWriter {
+    pub fn set_changed(&mut self) {
+        self.changed = true;
+    }
+
+    pub fn is_changed(&self) -> bool {
+        self.changed
+    }
+
+    pub fn write(
+        &mut self,
+        session: Session,
+        data_dir: &DataDir,
+        name: Option<&str>,
+    ) -> Result<()> {
+        self.changed = false;
+        session.save(data_dir, name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::UserConfig;
+    use crate::renderer::ZoomLevel;
+
+    fn window(path: &str, x: f64, block: Option<usize>) -> WindowSession {
+        let tabs = vec![TabSession { path: path.into(), block }];
+        let state = WindowState {
+            x,
+            y: 10.0,
+            width: 600.0,
+            height: 400.0,
+            fullscreen: false,
+            zoom_level: ZoomLevel::default(),
+            always_on_top: x > 0.0,
+            maximized: false,
+        };
+        WindowSession { state, tabs, current: 0 }
+    }
+
+    fn paths(session: Session) -> Vec<(PathBuf, f64, Option<usize>)> {
+        session
+            .into_windows()
+            .into_iter()
+            .map(|w| (w.tabs[0].path.clone(), w.state.x, w.tabs[0].block))
+            .collect()
+    }
+
+    #[test]
+    fn save_and_load_named_sessions() {
+        let tmp = tempfile::tempdir().unwrap();
+        let data_dir = DataDir::new(tmp.path());
+        assert_eq!(paths(Session::load(&data_dir, None)), []);
+
+        let session = Session::new(vec![window("a.md", 0.0, None), window("b.md", 20.0, Some(3))]);
+        session.save(&data_dir, None).unwrap();
+        let session = Session::new(vec![window("c.md", 40.0, Some(1))]);
+        session.save(&data_dir, Some("project")).unwrap();
+        assert!(tmp.path().join("session.json").exists());
+        assert!(tmp.path().join("session.project.json").exists());
+
+        let want = [("a.md".into(), 0.0, None), ("b.md".into(), 20.0, Some(3))];
+        assert_eq!(paths(Session::load(&data_dir, None)), want);
+        let want = [("c.md".into(), 40.0, Some(1))];
+        assert_eq!(paths(Session::load(&data_dir, Some("project"))), want);
+        assert_eq!(paths(Session::load(&data_dir, Some("other"))), []);
+    }
+
+    #[test]
+    fn restore_scroll_position() {
+        let file = window("a.md", 0.0, None).init_files().pop().unwrap();
+        assert!(matches!(file.scroll, InitScroll::Nop), "{file:?}");
+        let file = window("a.md", 0.0, Some(5)).init_files().pop().unwrap();
+        assert!(matches!(file.scroll, InitScroll::BlockTop(5)), "{file:?}");
+        assert_eq!(file.path, PathBuf::from("a.md"));
+    }
+
+    #[test]
+    fn save_and_load_tabs() {
+        let tmp = tempfile::tempdir().unwrap();
+        let data_dir = DataDir::new(tmp.path());
+        let mut w = window("a.md", 0.0, Some(2));
+        w.tabs.push(TabSession { path: "b.md".into(), block: None });
+        w.current = 1;
+        Session::new(vec![w]).save(&data_dir, None).unwrap();
+
+        let mut windows = Session::load(&data_dir, None).into_windows();
+        assert_eq!(windows.len(), 1);
+        let w = windows.pop().unwrap();
+        assert_eq!(w.current, 1);
+        let files: Vec<_> = w.init_files().into_iter().map(|f| f.path).collect();
+        assert_eq!(files, [PathBuf::from("a.md"), PathBuf::from("b.md")]);
+    }
+
+    #[test]
+    fn write_changed_session() {
+        let tmp = tempfile::tempdir().unwrap();
+        let data_dir = DataDir::new(tmp.path());
+        let mut writer = SessionWriter::default();
+        assert!(!writer.is_changed());
+
+        writer.set_changed();
+        writer.set_changed();
+        assert!(writer.is_changed());
+        let session = Session::new(vec![window("a.md", 0.0, Some(3))]);
+        writer.write(session, &data_dir, None).unwrap();
+        assert!(!writer.is_changed());
+        assert_eq!(paths(Session::load(&data_dir, None)), [("a.md".into(), 0.0, Some(3))]);
+
+        // Closing the window changes the session
+        writer.set_changed();
+        writer.write(Session::default(), &data_dir, None).unwrap();
+        assert!(!writer.is_changed());
+        assert_eq!(paths(Session::load(&data_dir, None)), []);
+    }
+
+    #[test]
+    fn default_session_is_disabled() {
+        let tmp = tempfile::tempdir().unwrap();
+        let config = Config::new(UserConfig::default(), tmp.path(), tmp.path());
+        assert!(!Session::is_enabled(&config));
+        let mut user = UserConfig::default();
+        user.window.restore = true;
+        assert!(Session::is_enabled(&Config::new(user, tmp.path(), tmp.path())));
+    }
+}
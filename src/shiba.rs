@@ -1,33 +1,74 @@
-use crate::cli::Options;
-use crate::config::{Config, home_dir};
+use crate::bookmarks::{Bookmark, Bookmarks};
+use crate::cli::{Options, PathArgs};
+use crate::config::{Config, SearchMatcher, home_dir};
+use crate::diagram_worker::DiagramWorker;
 use crate::dialog::Dialog;
+use crate::document_state::DocumentStates;
 use crate::history::{Direction, History};
+use crate::markdown::{Replaced, clear_diagram_failures};
 use crate::opener::Opener;
+use crate::preview::Preview;
 use crate::process_singleton::ProcessSingleton;
+use crate::remote::{HeadingTarget, RemoteCall, ZoomDirection};
 use crate::renderer::{
     Event, EventHandler, InitFile, InitScroll, MenuItem, MessageFromWindow, MessageToWindow,
     Renderer, RendererHandle, RenderingFlow, ScrollRequest, Window, WindowEvent, WindowHandles,
 };
 #[cfg(feature = "__sanity")]
 use crate::sanity::SanityTest;
+use crate::search_index::SearchIndex;
+use crate::session::{Session, SessionWriter, TabSession, WindowSession};
+use crate::tabs::Tabs;
 use crate::watcher::{PathFilter, Watcher};
 use crate::window::{Error as WindowError, WindowManager};
 use anyhow::{Context as _, Error, Result};
-use std::collections::VecDeque;
+use serde_json::{Value, json};
+use std::collections::{HashMap, VecDeque};
 use std::mem;
 use std::path::{Path, PathBuf};
 use std::rc::Rc;
 
+fn watch_config_files(watcher: &mut impl Watcher, config: &Config) {
+    for path in config.config_files() {
+        if path.is_file()
+            && let Err(err) = watcher.watch(path)
+        {
+            log::error!("Could not watch the config file {path:?} to reload it: {err}");
+        }
+    }
+}
+
+// Files to be opened in the tabs of a new window. The navigation history of the current tab is inherited when the window
+// is duplicated
+struct WindowInit {
+    tabs: Vec<InitFile>,
+    current: usize,
+    history: Option<History>,
+}
+
+impl From<InitFile> for WindowInit {
+    fn from(file: InitFile) -> Self {
+        Self { tabs: vec![file], current: 0, history: None }
+    }
+}
+
 pub struct Shiba<R: Renderer, O, W, D> {
     renderer: R::Handle,
     windows: WindowManager<R>,
     opener: O,
     history: History,
+    documents: DocumentStates,
+    bookmarks: Bookmarks,
     watcher: W,
     dialog: D,
     config: Rc<Config>,
-    init_files: VecDeque<InitFile>,
+    init_files: VecDeque<WindowInit>,
+    // Files to be opened in the created windows when they are ready
+    opening_files: HashMap<R::WindowId, WindowInit>,
     singleton: ProcessSingleton,
+    search_index: SearchIndex<R::WindowId>,
+    diagrams: DiagramWorker,
+    session_writer: SessionWriter,
     exit_status: i32,
 }
 
@@ -61,20 +102,7 @@ where
         let config = Rc::new(Config::load(options)?);
         log::debug!("Application config: {:?}", config);
 
-        let singleton = if config.process().singleton {
-            #[cfg(not(target_os = "windows"))]
-            {
-                ProcessSingleton::with_socket_file(config.data_dir())
-            }
-            #[cfg(target_os = "windows")]
-            {
-                ProcessSingleton::with_default_namespace()
-            }
-        } else {
-            log::debug!("Disable process singleton due to user's preference");
-            ProcessSingleton::default()
-        };
-
+        let singleton = ProcessSingleton::new(&config);
         if singleton
             .send(&paths)
             .context("Could not connect to IPC socket for process singleton")?
@@ -83,12 +111,13 @@ where
         }
 
         let watch_paths = paths.watched;
-        let mut init_files = paths.additional_windows;
+        let mut init_files: Vec<_> =
+            paths.additional_windows.into_iter().map(InitFile::from).collect();
         if let Some(path) = paths.init {
-            init_files.push(path);
+            init_files.push(InitFile::at_line(path, paths.line));
         }
 
-        let renderer = R::new(config.clone())?;
+        let renderer = R::new()?;
         let dog = Self::new(watch_paths, init_files, config, singleton, &renderer)?;
 
         renderer.start(dog)
@@ -96,35 +125,62 @@ where
 
     pub fn new(
         watch_paths: Vec<PathBuf>,
-        init_files: Vec<PathBuf>,
+        init_files: Vec<InitFile>,
         config: Rc<Config>,
         singleton: ProcessSingleton,
         renderer: &R,
     ) -> Result<Self> {
-        let filter = PathFilter::new(config.watch());
+        let filter = PathFilter::new(config.watch()).with_files(config.config_files().to_vec());
         let mut watcher = W::new(renderer.create_handle(), filter)?;
+        watch_config_files(&mut watcher, &config);
         let mut history = History::load(&config);
         for path in watch_paths {
             log::debug!("Watching initial path: {:?}", path);
             watcher.watch(&path)?;
             history.push(path);
         }
+        let documents = DocumentStates::load(config.data_dir(), config.preview().history_size);
+        let bookmarks = Bookmarks::load(config.data_dir());
         let handle = renderer.create_handle();
-        for _ in 0..init_files.len().max(1) {
+
+        // Windows in the previous session are restored before the windows for the command line arguments
+        let restored = if Session::is_enabled(&config) {
+            Session::load(config.data_dir(), config.session()).into_windows()
+        } else {
+            vec![]
+        };
+        let num_new_windows =
+            if restored.is_empty() { init_files.len().max(1) } else { init_files.len() };
+        let mut files = VecDeque::with_capacity(restored.len() + init_files.len());
+        for window in restored {
+            log::debug!("Restore window in the session: {window:?}");
+            handle.restore_window(window.state.clone());
+            let (tabs, current) = (window.init_files(), window.current);
+            files.push_back(WindowInit { tabs, current, history: None });
+        }
+        for _ in 0..num_new_windows {
             handle.create_window();
         }
-        let init_files = init_files.into_iter().map(|p| p.into()).collect();
+        files.extend(init_files.into_iter().map(WindowInit::from));
+        let search_index = SearchIndex::new(renderer.create_handle(), &config);
+        let diagrams = DiagramWorker::new(renderer.create_handle());
 
         Ok(Self {
             renderer: handle,
             windows: WindowManager::default(),
             opener: O::default(),
             history,
+            documents,
+            bookmarks,
             watcher,
             dialog: D::new(&config)?,
             config,
-            init_files,
+            init_files: files,
+            opening_files: HashMap::new(),
             singleton,
+            search_index,
+            diagrams,
+            session_writer: SessionWriter::default(),
             exit_status: 0,
         })
     }
@@ -132,52 +188,161 @@ where
     fn open_preview(&mut self, id: R::WindowId, file: InitFile) -> Result<&R::Window> {
         let InitFile { path, scroll } = file;
         self.watcher.watch(&path)?; // Watch path at first since the file may not exist yet
-        let (window, preview) = self.windows.get_mut(id)?;
+        let (window, preview, history) = self.windows.get_mut_with_history(id)?;
+
+        if preview.show(&path, window, &self.config, &self.watcher, &self.documents)? {
+            self.diagrams.render(preview.path(), preview.diagram_jobs());
+            // Scroll requests are sent after the content so that they are applied to the new content
+            match scroll {
+                InitScroll::Fragment(hash) => {
+                    let scroll = ScrollRequest::Fragment(&hash);
+                    window.send_message(MessageToWindow::Scroll { scroll })?;
+                }
+                InitScroll::Heading(index) => {
+                    let scroll = ScrollRequest::Heading(index);
+                    window.send_message(MessageToWindow::Scroll { scroll })?;
+                }
+                InitScroll::BlockTop(block) => {
+                    let scroll = ScrollRequest::BlockTop(block);
+                    window.send_message(MessageToWindow::Scroll { scroll })?;
+                }
+                InitScroll::Line(line) => {
+                    if let Some(block) = preview.block_at_line(line) {
+                        log::debug!("Scroll to block {block} at line {line} in {path:?}");
+                        let scroll = ScrollRequest::Block(block);
+                        window.send_message(MessageToWindow::Scroll { scroll })?;
+                    }
+                }
+                InitScroll::Nop => {}
+            }
+            history.push(path.clone());
+            self.history.push(path);
+            self.session_writer.set_changed();
+        }
 
-        match scroll {
-            InitScroll::Fragment(hash) => {
-                let scroll = ScrollRequest::Fragment(&hash);
-                window.send_message(MessageToWindow::Scroll { scroll })?;
+        Ok(window)
+    }
+
+    // Open the files in the tabs of the window. Only the file in the current tab is rendered in the window and the
+    // others are loaded in background.
+    fn open_tabs(&mut self, id: R::WindowId, init: WindowInit) -> Result<()> {
+        let WindowInit { tabs: files, current, .. } = init;
+        let mut previews = Vec::with_capacity(files.len());
+        let mut current_file = None;
+        for (index, file) in files.into_iter().enumerate() {
+            if index == current {
+                current_file = Some((previews.len(), file));
+                previews.push(Preview::default());
+                continue;
             }
-            InitScroll::Heading(index) => {
-                let scroll = ScrollRequest::Heading(index);
-                window.send_message(MessageToWindow::Scroll { scroll })?;
+            self.watcher.watch(&file.path)?;
+            let mut preview = Preview::default();
+            if preview.load_in_background(&file.path, &self.config, &self.watcher)? {
+                if let InitScroll::BlockTop(block) = file.scroll {
+                    preview.set_scroll_block(block);
+                }
+                self.history.push(file.path);
+                previews.push(preview);
             }
-            InitScroll::Nop => {}
         }
 
-        if preview.show(&path, window)? {
-            self.history.push(path);
+        let (window, tabs) = self.windows.tabs_mut(id)?;
+        tabs.reset(previews, current_file.as_ref().map_or(0, |(index, _)| *index));
+        if let Some((_, file)) = current_file {
+            self.open_preview(id, file)?;
+        } else {
+            let preview = tabs.current_mut();
+            preview.activate(window)?;
+            self.diagrams.render(preview.path(), preview.diagram_jobs());
         }
+        self.session_writer.set_changed();
+        let (window, tabs) = self.windows.tabs(id)?;
+        tabs.send(window)
+    }
 
-        Ok(window)
+    fn new_tab(&mut self, id: R::WindowId) -> Result<()> {
+        self.session_writer.set_changed();
+        let (window, tabs) = self.windows.tabs_mut(id)?;
+        tabs.open();
+        tabs.current_mut().activate(window)?;
+        tabs.send(window)
+    }
+
+    fn select_tab(
+        &mut self,
+        id: R::WindowId,
+        select: impl FnOnce(&mut Tabs) -> bool,
+    ) -> Result<()> {
+        let (window, tabs) = self.windows.tabs_mut(id)?;
+        if select(tabs) {
+            self.session_writer.set_changed();
+            let preview = tabs.current_mut();
+            preview.activate(window)?;
+            self.diagrams.render(preview.path(), preview.diagram_jobs());
+            tabs.send(window)?;
+        }
+        Ok(())
+    }
+
+    // Closing the last tab closes the window
+    fn close_tab(&mut self, id: R::WindowId, index: Option<usize>) -> Result<RenderingFlow> {
+        let (window, tabs) = self.windows.tabs_mut(id)?;
+        if tabs.count() == 1 {
+            return Ok(self.close_window(id));
+        }
+        let current = tabs.index();
+        let index = index.unwrap_or(current);
+        if tabs.close(index) {
+            self.session_writer.set_changed();
+            if index == current {
+                let preview = tabs.current_mut();
+                preview.activate(window)?;
+                self.diagrams.render(preview.path(), preview.diagram_jobs());
+            }
+            tabs.send(window)?;
+        }
+        Ok(RenderingFlow::Continue)
     }
 
     fn open_window(&mut self, file: InitFile) {
         log::debug!("Open new window with file: {file:?}");
-        self.init_files.push_back(file);
+        self.init_files.push_back(file.into());
         self.renderer.create_window();
     }
 
     fn navigate(&mut self, id: R::WindowId, dir: Direction) -> Result<()> {
-        let (window, preview) = self.windows.get_mut(id)?;
+        let (window, preview, history) = self.windows.get_mut_with_history(id)?;
 
-        let (mut current, dir) = if preview.is_empty() {
-            // When the welcome page is displayed, the history already indicates the latest history item.
-            match dir {
-                Direction::Forward => (None, dir),
-                Direction::Back | Direction::Top => (self.history.current(), Direction::Back),
+        if preview.is_empty() {
+            // When the welcome page is displayed, the window has no page to navigate yet. The latest item in the
+            // global history is opened instead.
+            let mut current = match dir {
+                Direction::Forward => None,
+                Direction::Back | Direction::Top => self.history.current(),
+            };
+            while let Some(path) = current {
+                log::debug!("Try to open the latest page in history: {path:?}");
+                if preview.show(path, window, &self.config, &self.watcher, &self.documents)? {
+                    self.diagrams.render(preview.path(), preview.diagram_jobs());
+                    history.push(path.to_path_buf());
+                    self.session_writer.set_changed();
+                    return Ok(());
+                }
+                current = self.history.delete(Direction::Back);
             }
-        } else {
-            (self.history.navigate(dir), dir)
-        };
+            log::debug!("No page found in history");
+            return Ok(());
+        }
 
+        let mut current = history.navigate(dir);
         while let Some(path) = current {
             log::debug!("Try to navigate preview page with direction {dir:?}: {path:?}");
-            if preview.show(path, window)? {
+            if preview.show(path, window, &self.config, &self.watcher, &self.documents)? {
+                self.diagrams.render(preview.path(), preview.diagram_jobs());
+                self.session_writer.set_changed();
                 return Ok(());
             }
-            current = self.history.delete(dir);
+            current = history.delete(dir);
         }
 
         log::debug!("No page found in history with direction {dir:?}");
@@ -192,12 +357,11 @@ where
             log::debug!("Skipped to reload empty content");
             return Ok(());
         }
-        if let Some(path) = self.history.current() {
-            log::debug!("Reload current preview page: {:?}", path);
-            preview.show(path, window)?;
-            window.send_message(MessageToWindow::Reload)?;
-        }
-        Ok(())
+        let path = preview.path().to_path_buf();
+        log::debug!("Reload current preview page: {:?}", path);
+        preview.show(&path, window, &self.config, &self.watcher, &self.documents)?;
+        self.diagrams.render(preview.path(), preview.diagram_jobs());
+        window.send_message(MessageToWindow::Reload)
     }
 
     fn pick_files(&mut self, id: R::WindowId) -> Result<()> {
@@ -294,33 +458,146 @@ where
 
     fn open_config(&mut self) -> Result<()> {
         let path = self.config.config_file()?;
+        watch_config_files(&mut self.watcher, &self.config); // The config file may be generated just now
         log::debug!("Opening config file via menu item: {:?}", path);
         self.opener.open(&path).with_context(|| format!("Could not open config file {path:?}"))
     }
 
+    fn open_source(&mut self, id: R::WindowId, offset: usize) -> Result<()> {
+        let editor = self.config.editor();
+        if editor.command.is_empty() {
+            log::debug!("Text editor command is not configured. Ignore opening the source");
+            return Ok(());
+        }
+        let (_, preview) = self.windows.get(id)?;
+        if preview.is_empty() {
+            return Ok(());
+        }
+        let (path, line) = (preview.path(), preview.line_at(offset));
+        log::debug!("Opening source {path:?} at line {line} with text editor");
+        self.opener
+            .open_editor(editor, path, line)
+            .with_context(|| format!("Could not open {path:?} at line {line} with text editor"))
+    }
+
+    fn replace(
+        &mut self,
+        id: R::WindowId,
+        query: &str,
+        matcher: SearchMatcher,
+        replacement: &str,
+    ) -> Result<()> {
+        let (window, preview) = self.windows.get(id)?;
+        if preview.is_empty() || query.is_empty() {
+            return Ok(());
+        }
+        let replaced = preview.replace(query, matcher, replacement)?;
+        if replaced.count == 0 {
+            log::debug!("No match to replace for query {query:?} ({} skipped)", replaced.skipped);
+            return Ok(());
+        }
+
+        // Show the diff in the window while the dialog is opened
+        let path = preview.path();
+        let Replaced { count, skipped, hunks, .. } = &replaced;
+        window.send_message(MessageToWindow::ReplacePreview {
+            path,
+            count: *count,
+            skipped: *skipped,
+            hunks,
+        })?;
+        let confirmed = self.dialog.yes_no(
+            "Replacing text...",
+            format!("Are you sure you want to replace {count} match(es) with {replacement:?} in {path:?}?"),
+            &window.handles(),
+        );
+        window.send_message(MessageToWindow::ReplaceEnd)?;
+
+        if confirmed {
+            // The preview will be updated via the file change event from the watcher
+            preview.write_source(&replaced.source)?;
+        }
+        Ok(())
+    }
+
+    fn add_bookmark(
+        &mut self,
+        id: R::WindowId,
+        label: String,
+        heading: Option<usize>,
+        fragment: Option<String>,
+    ) -> Result<()> {
+        let (_, preview) = self.windows.get(id)?;
+        if preview.is_empty() {
+            return Ok(());
+        }
+        let bookmark = Bookmark { label, path: preview.path().into(), heading, fragment };
+        self.bookmarks.add(bookmark, self.config.data_dir())
+    }
+
+    fn open_bookmark(&mut self, id: R::WindowId, index: usize, new_window: bool) -> Result<()> {
+        let Some(bookmark) = self.bookmarks.get(index) else {
+            anyhow::bail!("Bookmark at index {index} does not exist");
+        };
+        let file = bookmark.init_file();
+        if new_window {
+            self.open_window(file);
+        } else {
+            self.open_preview(id, file)?;
+        }
+        Ok(())
+    }
+
+    fn remove_bookmark(&mut self, id: R::WindowId, index: usize) -> Result<()> {
+        self.bookmarks.remove(index, self.config.data_dir())?;
+        self.bookmarks.send_items(self.windows.get(id)?.0)
+    }
+
+    // Resolve the relative path from the directory of the current file in the window
+    fn absolute_path(&self, id: R::WindowId, path: &Path) -> Option<PathBuf> {
+        self.windows.get(id).ok()?.1.absolute_path(path)
+    }
+
     fn is_markdown_file(&self, path: &Path) -> bool {
         self.config.watch().file_extensions.matches(path)
             && path.metadata().map(|md| !md.is_dir()).unwrap_or(false)
     }
 
     fn duplicate_window(&mut self, id: R::WindowId, scroll: InitScroll) -> Result<()> {
-        let (_, preview) = self.windows.get(id)?;
-        if preview.is_empty() {
+        let (_, tabs) = self.windows.tabs(id)?;
+        let (docs, current) = tabs.documents();
+        if docs.is_empty() {
             self.renderer.create_window();
-        } else {
-            self.open_window(InitFile { path: preview.path().into(), scroll });
+            return Ok(());
         }
+
+        // The scroll position is given only for the current tab
+        let mut scroll = (!tabs.current().is_empty()).then_some(scroll);
+        let mut files = Vec::with_capacity(docs.len());
+        for (index, preview) in docs.into_iter().enumerate() {
+            let scroll = match scroll.take_if(|_| index == current) {
+                Some(scroll) => scroll,
+                None => preview.scroll_block().map_or(InitScroll::Nop, InitScroll::BlockTop),
+            };
+            files.push(InitFile { path: preview.path().into(), scroll });
+        }
+        let history = Some(self.windows.history(id)?.clone());
+        log::debug!("Duplicate window with files: {files:?} (current={current})");
+        self.init_files.push_back(WindowInit { tabs: files, current, history });
+        self.renderer.create_window();
         Ok(())
     }
 
     fn close_window(&mut self, id: R::WindowId) -> RenderingFlow {
         log::debug!("Close window {id:?}");
+        self.opening_files.remove(&id);
         if self.windows.is_last(id) {
             self.quit(id)
         } else {
             if !self.windows.close(id) {
                 log::error!("Window was closed but it was not managed by Shiba: {id:?}");
             }
+            self.session_writer.set_changed();
             RenderingFlow::Continue
         }
     }
@@ -328,6 +605,8 @@ where
     fn close_other_windows(&mut self, id: R::WindowId) {
         log::debug!("Close all windows other than {id:?}");
         self.windows.close_others(id);
+        self.opening_files.retain(|&i, _| i == id);
+        self.session_writer.set_changed();
     }
 
     fn handle_window_message(
@@ -354,8 +633,8 @@ where
                 // Open window when the content is ready. Otherwise a white window flashes when dark theme.
                 window.show();
 
-                if let Some(file) = self.init_files.pop_front() {
-                    self.open_preview(id, file)?;
+                if let Some(init) = self.opening_files.remove(&id) {
+                    self.open_tabs(id, init)?;
                 } else {
                     window.send_message(MessageToWindow::Welcome)?;
                 }
@@ -364,19 +643,37 @@ where
                 SanityTest::new(self.renderer.clone()).run(id);
             }
             Search { query, index, matcher } => {
-                let (window, preview) = self.windows.get(id)?;
+                let (window, preview) = self.windows.get_mut(id)?;
                 preview.search(window, &query, index, matcher)?;
+                self.diagrams.render(preview.path(), preview.diagram_jobs());
+                if !preview.is_empty() {
+                    self.documents.set_search(preview.path(), &query, matcher);
+                }
             }
             GoForward => self.navigate(id, Direction::Forward)?,
             GoBack => self.navigate(id, Direction::Back)?,
             GoTop => self.navigate(id, Direction::Top)?,
             History => self.history.send_paths(self.windows.get(id)?.0)?,
+            Bookmarks => self.bookmarks.send_items(self.windows.get(id)?.0)?,
+            AddBookmark { label, heading, fragment } => {
+                self.add_bookmark(id, label, heading, fragment)?;
+            }
+            OpenBookmark { index, new_window } => self.open_bookmark(id, index, new_window)?,
+            RemoveBookmark { index } => self.remove_bookmark(id, index)?,
             Reload => self.reload(id)?,
             FileDialog => self.pick_files(id)?,
             FileDialogNewWindow => self.pick_files_in_new_window(id)?,
             DirDialog => self.pick_dirs(id)?,
-            OpenFile { path } => {
-                self.open_preview(id, PathBuf::from(path).into())?;
+            SearchAll { query, matcher } => {
+                // Directories may be watched after the previous search
+                self.search_index.watch_dirs(self.watcher.watched_dirs());
+                self.search_index.search(id, query, matcher);
+            }
+            Replace { query, matcher, replacement } => {
+                self.replace(id, &query, matcher, &replacement)?;
+            }
+            OpenFile { path, line } => {
+                self.open_preview(id, InitFile::at_line(PathBuf::from(path), line))?;
             }
             ZoomIn => self.zoom(id, true)?,
             ZoomOut => self.zoom(id, false)?,
@@ -391,11 +688,30 @@ where
             }
             CloseWindow => return Ok(self.close_window(id)),
             CloseAllOtherWindows => self.close_other_windows(id),
+            NewTab => self.new_tab(id)?,
+            NextTab => self.select_tab(id, Tabs::select_next)?,
+            PreviousTab => self.select_tab(id, Tabs::select_previous)?,
+            SelectTab { index } => self.select_tab(id, |tabs| tabs.select(index))?,
+            CloseTab { index } => return self.close_tab(id, index),
             Quit => return Ok(self.quit(id)),
             OpenMenu { position } => self.windows.get(id)?.0.show_menu_at(position),
             ToggleMenuBar => self.windows.get_mut(id)?.0.toggle_menu()?,
             ToggleAlwaysOnTop => self.toggle_always_on_top(id)?,
             EditConfig => self.open_config()?,
+            OpenSource { offset } => self.open_source(id, offset)?,
+            Source => {
+                let (window, preview) = self.windows.get(id)?;
+                if !preview.is_empty() {
+                    preview.send_source(window)?;
+                }
+            }
+            ScrollPosition { block } => {
+                let (_, preview) = self.windows.get_mut(id)?;
+                if !preview.is_empty() {
+                    preview.set_scroll_block(block);
+                    self.documents.set_block(preview.path(), block);
+                }
+            }
             Error { message } => anyhow::bail!("Error reported from renderer: {message}"),
         }
         Ok(RenderingFlow::Continue)
@@ -419,12 +735,20 @@ where
             OpenFiles => self.pick_files(id)?,
             OpenFilesInNewWindow => self.pick_files_in_new_window(id)?,
             WatchDirs => self.pick_dirs(id)?,
-            Search => self.windows.get(id)?.0.send_message(MessageToWindow::Search)?,
+            Search => self
+                .windows
+                .get(id)?
+                .0
+                .send_message(MessageToWindow::Search { query: None, matcher: None })?,
             SearchNext => self.windows.get(id)?.0.send_message(MessageToWindow::SearchNext)?,
             SearchPrevious => {
                 self.windows.get(id)?.0.send_message(MessageToWindow::SearchPrevious)?
             }
+            SearchAll => self.windows.get(id)?.0.send_message(MessageToWindow::SearchAll)?,
             Outline => self.windows.get(id)?.0.send_message(MessageToWindow::Outline)?,
+            ToggleSplitView => {
+                self.windows.get(id)?.0.send_message(MessageToWindow::ToggleSplitView)?;
+            }
             Print => {
                 let (window, preview) = self.windows.get(id)?;
                 if !preview.is_empty() {
@@ -436,6 +760,13 @@ where
             #[cfg(not(target_os = "macos"))]
             ToggleMenuBar => self.windows.get_mut(id)?.0.toggle_menu()?,
             History => self.history.send_paths(self.windows.get(id)?.0)?,
+            Bookmark => {
+                let (window, preview) = self.windows.get(id)?;
+                if !preview.is_empty() {
+                    window.send_message(MessageToWindow::Bookmark)?;
+                }
+            }
+            Bookmarks => self.bookmarks.send_items(self.windows.get(id)?.0)?,
             ToggleAlwaysOnTop => self.toggle_always_on_top(id)?,
             ToggleMinimizeWindow => self.toggle_minimized(id)?,
             ToggleMaximizeWindow => self.toggle_maximized(id)?,
@@ -443,6 +774,10 @@ where
             DuplicateWindow => self.duplicate_window(id, InitScroll::Nop)?,
             CloseWindow => return Ok(self.close_window(id)),
             CloseAllOtherWindows => self.close_other_windows(id),
+            NewTab => self.new_tab(id)?,
+            NextTab => self.select_tab(id, Tabs::select_next)?,
+            PreviousTab => self.select_tab(id, Tabs::select_previous)?,
+            CloseTab => return self.close_tab(id, None),
             Help => self.windows.get(id)?.0.send_message(MessageToWindow::Help)?,
             OpenRepo => self.opener.open("https://github.com/rhysd/Shiba")?,
             EditConfig => self.open_config()?,
@@ -453,6 +788,8 @@ where
                     &self.windows.get(id)?.0.handles(),
                 ) {
                     self.history.clear(&self.config)?;
+                    self.windows.clear_histories();
+                    self.documents.clear(self.config.data_dir())?;
                     self.windows.get_mut(id)?.0.delete_cache()?;
                 }
             }
@@ -460,28 +797,92 @@ where
         Ok(RenderingFlow::Continue)
     }
 
+    fn reload_config(&mut self) -> Result<()> {
+        log::debug!("Reloading the config file due to its change");
+        let config = self.config.reload()?;
+
+        let extensions_changed =
+            config.watch().file_extensions != self.config.watch().file_extensions;
+        if extensions_changed {
+            self.watcher.set_file_extensions(config.watch().file_extensions.clone());
+        }
+        let rerender = config.markdown() != self.config.markdown()
+            || config.preview().diagrams != self.config.preview().diagrams
+            || extensions_changed;
+        self.config = Rc::new(config);
+        log::debug!("Reloaded application config: {:?}", self.config);
+
+        if rerender {
+            // Documents in the index were parsed with the previous config
+            self.search_index = SearchIndex::new(self.renderer.clone(), &self.config);
+        }
+        // Retry the diagram commands which failed since they may have been fixed or installed
+        clear_diagram_failures();
+
+        for (_, window, tabs) in self.windows.iter_mut() {
+            window.send_message(MessageToWindow::Config {
+                keymaps: self.config.keymaps(),
+                search: self.config.search(),
+                home: home_dir(),
+                window: window.appearance(),
+            })?;
+            window.reload_assets(&self.config);
+            window.send_message(MessageToWindow::ReloadStyles)?;
+            if rerender {
+                let current = tabs.index();
+                for (index, preview) in tabs.iter_mut().enumerate() {
+                    if index == current {
+                        preview.refresh(window, &self.config, &self.watcher)?;
+                        self.diagrams.render(preview.path(), preview.diagram_jobs());
+                    } else if !preview.is_empty() {
+                        let path = preview.path().to_path_buf();
+                        preview.load_in_background(&path, &self.config, &self.watcher)?;
+                    }
+                }
+            }
+        }
+
+        self.dialog = D::new(&self.config)?;
+        Ok(())
+    }
+
     fn handle_file_changes(&mut self, mut paths: Vec<PathBuf>) -> Result<()> {
         log::debug!("Files changed: {:?}", paths);
 
+        let num_paths = paths.len();
+        paths.retain(|path| !self.config.is_config_file(path));
+        let config_changed = paths.len() != num_paths;
+        self.search_index.update_files(paths.clone());
+
         let mut updated = vec![];
         let focused_id = self.windows.focused_id()?;
         let mut focused_window_updated = false;
-        for (id, window, preview) in self.windows.iter_mut() {
-            let is_updated = if let Some(idx) = paths.iter().position(|p| p == preview.path()) {
-                let path = paths.swap_remove(idx);
-                log::debug!("Update the preview for the file change: {:?}", path);
-                preview.show(&path, window)?;
-                updated.push(path);
-                true
-            } else if let Some(path) = updated.iter().find(|&p| p == preview.path()) {
-                log::debug!("Update the (duplicate) preview for the file change: {:?}", path);
-                preview.show(path, window)?;
-                true
-            } else {
-                false
-            };
-            if is_updated && id == focused_id {
-                focused_window_updated = true;
+        for (id, window, tabs) in self.windows.iter_mut() {
+            let current = tabs.index();
+            for (index, preview) in tabs.iter_mut().enumerate() {
+                let path = if let Some(idx) = paths.iter().position(|p| p == preview.path()) {
+                    updated.push(paths.swap_remove(idx));
+                    updated.last()
+                } else {
+                    updated.iter().find(|&p| p == preview.path()) // Duplicate preview
+                };
+                let Some(path) = path else {
+                    continue;
+                };
+                if index == current {
+                    log::debug!("Update the preview for the file change: {:?}", path);
+                    preview.show(path, window, &self.config, &self.watcher, &self.documents)?;
+                    self.diagrams.render(preview.path(), preview.diagram_jobs());
+                    if id == focused_id {
+                        focused_window_updated = true;
+                    }
+                } else {
+                    log::debug!(
+                        "Update the background tab {index} for the file change: {:?}",
+                        path
+                    );
+                    preview.load_in_background(path, &self.config, &self.watcher)?;
+                }
             }
         }
 
@@ -489,12 +890,20 @@ where
             log::debug!(
                 "Show the new preview for the file change in window {focused_id:?}: {path:?}",
             );
-            let (window, preview) = self.windows.get_mut(focused_id)?;
-            if preview.show(&path, window)? {
+            let (window, preview, history) = self.windows.get_mut_with_history(focused_id)?;
+            if preview.show(&path, window, &self.config, &self.watcher, &self.documents)? {
+                self.diagrams.render(preview.path(), preview.diagram_jobs());
+                history.push(path.clone());
                 self.history.push(path);
+                self.session_writer.set_changed();
             }
         }
 
+        if config_changed {
+            self.reload_config()
+                .context("Could not reload the config file. The previous config is still used")?;
+        }
+
         Ok(())
     }
 
@@ -505,7 +914,7 @@ where
             Event::FileDrop { mut paths, id } => {
                 log::debug!("Files are dropped to window: {:?}", paths);
                 for path in paths.iter_mut() {
-                    if let Some(p) = self.history.absolute_path(path) {
+                    if let Some(p) = self.absolute_path(id, path) {
                         *path = p;
                     }
                 }
@@ -519,7 +928,7 @@ where
             }
             Event::WatchedFilesChanged(paths) => self.handle_file_changes(paths)?,
             Event::OpenLocalFile { mut file, id } => {
-                if let Some(abs_path) = self.history.absolute_path(&file.path) {
+                if let Some(abs_path) = self.absolute_path(id, &file.path) {
                     file.path = abs_path;
                 }
                 if self.is_markdown_file(&file.path) {
@@ -542,7 +951,7 @@ where
             Event::Menu(item) => return self.handle_menu_item(item),
             Event::NewWindow { init_file: None } => self.renderer.create_window(),
             Event::NewWindow { init_file: Some(mut file) } => {
-                if let Some(abs_path) = self.history.absolute_path(&file.path) {
+                if let Some(abs_path) = self.absolute_path(self.windows.focused_id()?, &file.path) {
                     file.path = abs_path;
                 }
                 if self.is_markdown_file(&file.path) {
@@ -556,24 +965,35 @@ where
                 }
             }
             Event::DuplicateWindow { scroll, id } => self.duplicate_window(id, scroll)?,
-            Event::ProcessSingleton { paths } if paths.is_empty() => self.renderer.create_window(),
-            Event::ProcessSingleton { paths } => {
-                log::debug!("Watch paths via IPC: {:?}", paths.watched);
-                for path in paths.watched {
-                    self.watcher.watch(&path)?;
-                    self.history.push(path);
-                }
-
-                if let Some(path) = paths.init {
-                    log::debug!("Open the initial file via IPC in existing window: {path:?}");
-                    let id = self.windows.focused_id()?;
-                    self.open_preview(id, path.into())?;
-                    self.windows.get(id)?.0.focus();
-                }
-
-                log::debug!("Open additional windows via IPC: {:?}", paths.additional_windows);
-                for path in paths.additional_windows {
-                    self.open_window(path.into());
+            Event::Remote { call, reply } => {
+                let (result, flow) = match self.handle_remote(call) {
+                    Ok((value, flow)) => (Ok(value), flow),
+                    Err(err) => (Err(err), RenderingFlow::Continue),
+                };
+                reply.send(result);
+                return Ok(flow);
+            }
+            Event::SearchAllResult { id, result } => {
+                let Ok((window, _)) = self.windows.get(id) else {
+                    log::debug!("Window {id:?} was closed before the search result arrived");
+                    return Ok(RenderingFlow::Continue);
+                };
+                let (files, error) = match &result.files {
+                    Ok(files) => (files.as_slice(), None),
+                    Err(error) => (&[][..], Some(error.as_str())),
+                };
+                let query = &result.query;
+                window.send_message(MessageToWindow::SearchAllResult { query, files, error })?;
+            }
+            Event::DiagramsRendered(path) => {
+                // Previews in background tabs don't need to be updated because their contents are parsed again with
+                // the rendered diagrams when the tabs are activated
+                for (_, window, tabs) in self.windows.iter_mut() {
+                    let preview = tabs.current_mut();
+                    if preview.path() == path {
+                        preview.show_diagrams(window)?;
+                        self.diagrams.render(preview.path(), preview.diagram_jobs());
+                    }
                 }
             }
             Event::Error(err) => return Err(err),
@@ -581,6 +1001,121 @@ where
         Ok(RenderingFlow::Continue)
     }
 
+    fn open_paths(&mut self, paths: PathArgs) -> Result<()> {
+        if paths.is_empty() {
+            self.renderer.create_window();
+            return Ok(());
+        }
+
+        log::debug!("Watch paths via IPC: {:?}", paths.watched);
+        for path in paths.watched {
+            self.watcher.watch(&path)?;
+            self.history.push(path);
+        }
+
+        if let Some(path) = paths.init {
+            log::debug!("Open the initial file via IPC in existing window: {path:?}");
+            let id = self.windows.focused_id()?;
+            self.open_preview(id, InitFile::at_line(path, paths.line))?;
+            self.windows.get(id)?.0.focus();
+        }
+
+        log::debug!("Open additional windows via IPC: {:?}", paths.additional_windows);
+        for path in paths.additional_windows {
+            self.open_window(path.into());
+        }
+
+        Ok(())
+    }
+
+    fn remote_window(&self, number: Option<u32>) -> Result<R::WindowId> {
+        let id = match number {
+            Some(number) => self.windows.id_by_number(number)?,
+            None => self.windows.focused_id()?,
+        };
+        Ok(id)
+    }
+
+    fn remote_document(&self, number: Option<u32>) -> Result<(&R::Window, &Preview)> {
+        let (window, preview) = self.windows.get(self.remote_window(number)?)?;
+        if preview.is_empty() {
+            anyhow::bail!("No document is opened in the window");
+        }
+        Ok((window, preview))
+    }
+
+    fn handle_remote(&mut self, call: RemoteCall) -> Result<(Value, RenderingFlow)> {
+        log::debug!("Handling remote call: {call:?}");
+        let value = match call {
+            RemoteCall::Open(paths) => {
+                // Relative paths cannot be resolved since the client's working directory is unknown
+                let init = paths.init.iter();
+                if let Some(path) = init
+                    .chain(&paths.additional_windows)
+                    .chain(&paths.watched)
+                    .find(|p| !p.is_absolute())
+                {
+                    anyhow::bail!("Path must be absolute: {path:?}");
+                }
+                self.open_paths(paths)?;
+                Value::Null
+            }
+            RemoteCall::CloseWindow { window } => {
+                let id = self.remote_window(window)?;
+                return Ok((Value::Null, self.close_window(id)));
+            }
+            RemoteCall::Reload { window } => {
+                self.reload(self.remote_window(window)?)?;
+                Value::Null
+            }
+            RemoteCall::ScrollToHeading { window, heading } => {
+                let (window, _) = self.remote_document(window)?;
+                let scroll = match &heading {
+                    HeadingTarget::Index(index) => ScrollRequest::Heading(*index),
+                    HeadingTarget::Id(id) => ScrollRequest::Fragment(id),
+                };
+                window.send_message(MessageToWindow::Scroll { scroll })?;
+                Value::Null
+            }
+            RemoteCall::Search { window, query } => {
+                let (window, _) = self.remote_document(window)?;
+                window
+                    .send_message(MessageToWindow::Search { query: Some(&query), matcher: None })?;
+                Value::Null
+            }
+            RemoteCall::Zoom { window, direction } => {
+                let id = self.remote_window(window)?;
+                self.zoom(id, direction == ZoomDirection::In)?;
+                let percent = self.windows.get(id)?.0.zoom_level().percent();
+                json!({ "percent": percent })
+            }
+            RemoteCall::ListWindows {} => {
+                let focused = self.windows.focused_id().ok();
+                let mut windows: Vec<_> = self
+                    .windows
+                    .iter()
+                    .filter_map(|(id, _, tabs)| {
+                        let number = self.windows.number(id)?;
+                        let preview = tabs.current();
+                        let path = (!preview.is_empty()).then(|| preview.path());
+                        Some((number, path, focused == Some(id)))
+                    })
+                    .collect();
+                windows.sort_by_key(|(number, _, _)| *number);
+                let windows = windows
+                    .into_iter()
+                    .map(|(window, path, focused)| json!({ "window": window, "path": path, "focused": focused }))
+                    .collect();
+                Value::Array(windows)
+            }
+            RemoteCall::CurrentPath { window } => {
+                let (_, preview) = self.windows.get(self.remote_window(window)?)?;
+                if preview.is_empty() { Value::Null } else { json!(preview.path()) }
+            }
+        };
+        Ok((value, RenderingFlow::Continue))
+    }
+
     fn save(&self, last_window: &R::Window) -> Result<()> {
         log::debug!("Save the persistent data before quit with window {:?}", last_window.id());
 
@@ -594,7 +1129,55 @@ where
             result = self.config.data_dir().save(&state);
         }
 
-        result.or(self.history.save(&self.config))
+        if Session::is_enabled(&self.config) {
+            result = result.and(self.session().save(self.config.data_dir(), self.config.session()));
+        }
+
+        result.or(self.history.save(&self.config)).and(self.documents.save(self.config.data_dir()))
+    }
+
+    // Snapshot of all windows showing documents in their tabs in the order of their window numbers
+    fn session(&self) -> Session {
+        let mut windows: Vec<_> = self
+            .windows
+            .iter()
+            .filter_map(|(id, window, tabs)| {
+                let (docs, current) = tabs.documents();
+                if docs.is_empty() {
+                    return None;
+                }
+                let state = window.state().filter(|s| s.height > 0.0 && s.width > 0.0)?;
+                let tabs = docs
+                    .into_iter()
+                    .map(|p| TabSession { path: p.path().to_path_buf(), block: p.scroll_block() })
+                    .collect();
+                let session = WindowSession { state, tabs, current };
+                Some((self.windows.number(id)?, session))
+            })
+            .collect();
+        windows.sort_by_key(|(number, _)| *number);
+        Session::new(windows.into_iter().map(|(_, w)| w).collect())
+    }
+
+    // Save the snapshot of the windows when the windows or the tabs were changed by the event so that the session is
+    // not lost even if the process is terminated without quitting the application
+    fn update_session(&mut self, flow: RenderingFlow) -> RenderingFlow {
+        // While the windows are being created, the snapshot lacks some of the windows to be restored
+        if matches!(flow, RenderingFlow::Exit(_))
+            || !self.session_writer.is_changed()
+            || !Session::is_enabled(&self.config)
+            || !self.init_files.is_empty()
+            || !self.opening_files.is_empty()
+        {
+            return flow;
+        }
+        let session = self.session();
+        if let Err(err) =
+            self.session_writer.write(session, self.config.data_dir(), self.config.session())
+        {
+            log::error!("Could not save the session: {err}");
+        }
+        flow
     }
 
     fn quit(&mut self, id: R::WindowId) -> RenderingFlow {
@@ -615,7 +1198,18 @@ where
     ) -> Result<RenderingFlow> {
         match event {
             WindowEvent::Created(window) => {
-                self.windows.add(id, window);
+                // Windows are created in the order of the requests. The file is opened when the window is ready
+                let history = match self.init_files.pop_front() {
+                    Some(mut init) => {
+                        let history = init.history.take();
+                        self.opening_files.insert(id, init);
+                        history
+                    }
+                    None => None,
+                };
+                let history =
+                    history.unwrap_or_else(|| History::new(self.config.preview().history_size));
+                self.windows.add(id, window, history);
                 // Ensure IPC messages are received after the first window is created
                 if self.singleton.can_listen() {
                     self.singleton.listen(self.renderer.clone())?;
@@ -657,17 +1251,23 @@ where
     type Window = R::Window;
     type WindowId = R::WindowId;
 
+    fn config(&self) -> &Config {
+        &self.config
+    }
+
     fn on_event(&mut self, event: Event<Self::WindowId>) -> RenderingFlow {
-        self.handle_event(event).unwrap_or_else(|err| {
+        let flow = self.handle_event(event).unwrap_or_else(|err| {
             self.alert("Could not handle application event", err);
             RenderingFlow::Continue
-        })
+        });
+        self.update_session(flow)
     }
 
     fn on_window(&mut self, id: Self::WindowId, event: WindowEvent<Self::Window>) -> RenderingFlow {
-        self.handle_window_event(id, event).unwrap_or_else(|err| {
+        let flow = self.handle_window_event(id, event).unwrap_or_else(|err| {
             self.alert("Could not handle window event", err);
             RenderingFlow::Continue
-        })
+        });
+        self.update_session(flow)
     }
 }
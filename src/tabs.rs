@@ -0,0 +1,306 @@
+use crate::history::History;
+use crate::preview::Preview;
+use crate::renderer::{MessageToWindow, Window};
+use anyhow::Result;
+use std::mem;
+
+// Each tab has its own navigation history so that going back and forward never jumps to the document in other tabs
+struct Tab {
+    preview: Preview,
+    history: History,
+}
+
+// Documents opened in one window. Only the preview of the current tab is rendered in the window. The other tabs keep
+// their contents and they are rendered again when the tab is selected. A window always has at least one tab.
+pub struct Tabs {
+    tabs: Vec<Tab>,
+    current: usize,
+}
+
+impl Tabs {
+    // A window starts with one empty tab. The history is inherited when the window is duplicated.
+    pub fn new(history: History) -> Self {
+        Self { tabs: vec![Tab { preview: Preview::default(), history }], current: 0 }
+    }
+
+    // Replace the tabs with the previews. The current tab keeps its history and the other tabs start their histories
+    // with their documents.
+    pub fn reset(&mut self, mut previews: Vec<Preview>, current: usize) {
+        if previews.is_empty() {
+            previews.push(Preview::default());
+        }
+        let current = current.min(previews.len() - 1);
+        let history = &mut self.tabs[self.current].history;
+        let max_items = history.max_items();
+        let mut history = Some(mem::replace(history, History::new(max_items)));
+        self.tabs = previews
+            .into_iter()
+            .enumerate()
+            .map(|(index, preview)| {
+                let history = history.take_if(|_| index == current).unwrap_or_else(|| {
+                    let mut history = History::new(max_items);
+                    if !preview.is_empty() {
+                        history.push(preview.path().to_path_buf());
+                    }
+                    history
+                });
+                Tab { preview, history }
+            })
+            .collect();
+        self.current = current;
+    }
+
+    pub fn current(&self) -> &Preview {
+        &self.tabs[self.current].preview
+    }
+
+    pub fn current_mut(&mut self) -> &mut Preview {
+        &mut self.tabs[self.current].preview
+    }
+
+    pub fn history(&self) -> &History {
+        &self.tabs[self.current].history
+    }
+
+    pub fn current_with_history(&mut self) -> (&mut Preview, &mut History) {
+        let Tab { preview, history } = &mut self.tabs[self.current];
+        (preview, history)
+    }
+
+    pub fn clear_histories(&mut self) {
+        for tab in &mut self.tabs {
+            tab.history.clear_items();
+        }
+    }
+
+    pub fn index(&self) -> usize {
+        self.current
+    }
+
+    pub fn count(&self) -> usize {
+        self.tabs.len()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Preview> {
+        self.tabs.iter().map(|tab| &tab.preview)
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut Preview> {
+        self.tabs.iter_mut().map(|tab| &mut tab.preview)
+    }
+
+    // Open a new empty tab next to the current tab and select it
+    pub fn open(&mut self) {
+        let history = History::new(self.history().max_items());
+        self.current += 1;
+        self.tabs.insert(self.current, Tab { preview: Preview::default(), history });
+        log::debug!("Opened new tab at {} ({} tabs)", self.current, self.tabs.len());
+    }
+
+    // Returns whether the current tab was changed
+    pub fn select(&mut self, index: usize) -> bool {
+        if index >= self.tabs.len() || index == self.current {
+            return false;
+        }
+        log::debug!("Select tab {index} (previous={})", self.current);
+        self.current = index;
+        true
+    }
+
+    pub fn select_next(&mut self) -> bool {
+        self.select((self.current + 1) % self.tabs.len())
+    }
+
+    pub fn select_previous(&mut self) -> bool {
+        let len = self.tabs.len();
+        self.select((self.current + len - 1) % len)
+    }
+
+    // Returns whether the tab was closed. The last tab is never closed since it is the window itself.
+    pub fn close(&mut self, index: usize) -> bool {
+        if index >= self.tabs.len() || self.tabs.len() == 1 {
+            return false;
+        }
+        self.tabs.remove(index);
+        if index < self.current || self.current == self.tabs.len() {
+            self.current -= 1;
+        }
+        log::debug!("Closed tab {index} (current={})", self.current);
+        true
+    }
+
+    // Tabs showing documents and the index of the current tab in them. Empty tabs are omitted.
+    pub fn documents(&self) -> (Vec<&Preview>, usize) {
+        let docs: Vec<_> = self.iter().filter(|p| !p.is_empty()).collect();
+        let current = self.iter().take(self.current).filter(|p| !p.is_empty()).count();
+        let current = current.min(docs.len().saturating_sub(1));
+        (docs, current)
+    }
+
+    pub fn send<W: Window>(&self, window: &W) -> Result<()> {
+        let paths: Vec<_> = self.iter().map(|p| (!p.is_empty()).then(|| p.path())).collect();
+        log::debug!("Send {} tabs to window (current={})", paths.len(), self.current);
+        window.send_message(MessageToWindow::Tabs { paths: &paths, current: self.current })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::history::Direction;
+    use crate::test::TestWindow;
+    use crate::watcher::NopWatcher;
+    use std::fs;
+    use std::path::Path;
+
+    fn preview(path: &Path) -> Preview {
+        let config = Config::default();
+        let mut preview = Preview::default();
+        assert!(preview.load_in_background(path, &config, &NopWatcher).unwrap());
+        preview
+    }
+
+    fn new_tabs(previews: Vec<Preview>, current: usize) -> Tabs {
+        let mut tabs = Tabs::new(History::new(10));
+        tabs.reset(previews, current);
+        tabs
+    }
+
+    #[test]
+    fn open_select_and_close_tabs() {
+        let mut tabs = Tabs::new(History::new(10));
+        assert_eq!(tabs.count(), 1);
+        assert!(!tabs.close(0));
+        assert!(!tabs.select_next());
+
+        tabs.open();
+        tabs.open();
+        assert_eq!((tabs.count(), tabs.index()), (3, 2));
+        assert!(tabs.select_next());
+        assert_eq!(tabs.index(), 0);
+        assert!(tabs.select_previous());
+        assert_eq!(tabs.index(), 2);
+        assert!(tabs.select(1));
+        assert!(!tabs.select(1));
+        assert!(!tabs.select(3));
+
+        // New tab is opened next to the current tab
+        tabs.open();
+        assert_eq!((tabs.count(), tabs.index()), (4, 2));
+
+        assert!(tabs.close(0));
+        assert_eq!((tabs.count(), tabs.index()), (3, 1));
+        assert!(tabs.close(1));
+        assert_eq!((tabs.count(), tabs.index()), (2, 1));
+        assert!(tabs.close(1));
+        assert_eq!((tabs.count(), tabs.index()), (1, 0));
+        assert!(!tabs.close(5));
+    }
+
+    #[test]
+    fn documents_in_tabs() {
+        let tmp = tempfile::tempdir().unwrap();
+        let (a, b) = (tmp.path().join("a.md"), tmp.path().join("b.md"));
+        fs::write(&a, "# A").unwrap();
+        fs::write(&b, "# B").unwrap();
+
+        let tabs = new_tabs(vec![preview(&a), Preview::default(), preview(&b)], 2);
+        let (docs, current) = tabs.documents();
+        let paths: Vec<_> = docs.iter().map(|p| p.path()).collect();
+        assert_eq!(paths, [a.as_path(), b.as_path()]);
+        assert_eq!(current, 1);
+
+        let tabs = new_tabs(vec![Preview::default()], 0);
+        let (docs, current) = tabs.documents();
+        assert!(docs.is_empty());
+        assert_eq!(current, 0);
+
+        let tabs = new_tabs(vec![], 3);
+        assert_eq!((tabs.count(), tabs.index()), (1, 0));
+    }
+
+    #[test]
+    fn history_and_path_per_tab() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path().canonicalize().unwrap();
+        let (x, y) = (root.join("x"), root.join("y"));
+        for dir in [&x, &y] {
+            fs::create_dir(dir).unwrap();
+            fs::write(dir.join("sibling.md"), "# Sibling").unwrap();
+        }
+        let (a, b) = (x.join("a.md"), y.join("b.md"));
+        fs::write(&a, "# A").unwrap();
+        fs::write(&b, "# B").unwrap();
+
+        // The current tab inherits the history of the window
+        let mut history = History::new(10);
+        history.push(root.join("prev.md"));
+        let mut tabs = Tabs::new(history);
+        tabs.reset(vec![preview(&a), preview(&b)], 0);
+        tabs.current_with_history().1.push(a.clone());
+        assert_eq!(tabs.history().current(), Some(a.as_path()));
+
+        // The relative link is resolved against the document in the selected tab. Reloading the tab shows its path.
+        assert!(tabs.select(1));
+        assert_eq!(tabs.current().path(), b);
+        assert_eq!(tabs.history().current(), Some(b.as_path()));
+        let sibling = Path::new("sibling.md");
+        assert_eq!(tabs.current().absolute_path(sibling), Some(y.join("sibling.md")));
+        assert_eq!(tabs.current().absolute_path(Path::new("missing.md")), None);
+        // Going back does not jump to the document in the other tab
+        assert_eq!(tabs.current_with_history().1.navigate(Direction::Back), None);
+
+        assert!(tabs.select(0));
+        assert_eq!(tabs.current().path(), a);
+        assert_eq!(tabs.current().absolute_path(sibling), Some(x.join("sibling.md")));
+        let back = tabs.current_with_history().1.navigate(Direction::Back);
+        assert_eq!(back, Some(root.join("prev.md").as_path()));
+
+        // The history of the closed tab is removed with the tab
+        assert!(tabs.close(0));
+        assert_eq!(tabs.current().path(), b);
+        assert_eq!(tabs.history().current(), Some(b.as_path()));
+
+        // New tab has empty history
+        tabs.open();
+        assert_eq!(tabs.history().current(), None);
+        assert_eq!(tabs.current().absolute_path(sibling), None);
+
+        tabs.clear_histories();
+        assert!(tabs.select(0));
+        assert_eq!(tabs.history().current(), None);
+    }
+
+    #[test]
+    fn activate_background_tab() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("a.md");
+        fs::write(&path, "# Hello").unwrap();
+
+        let mut tabs = new_tabs(vec![Preview::default(), preview(&path)], 0);
+        tabs.current_mut().set_scroll_block(0);
+        let window = TestWindow::default();
+        tabs.send(&window).unwrap();
+        let msg = window.messages.take().pop().unwrap();
+        let json: serde_json::Value = serde_json::from_str(&msg).unwrap();
+        let want = serde_json::json!({ "kind": "tabs", "paths": [null, path], "current": 0 });
+        assert_eq!(json, want);
+
+        tabs.current_mut().activate(&window).unwrap();
+        let msgs = window.messages.take();
+        assert_eq!(msgs.len(), 1, "{msgs:?}");
+        assert!(msgs[0].contains(r#""kind": "welcome""#), "{msgs:?}");
+
+        assert!(tabs.select(1));
+        tabs.current_mut().set_scroll_block(3);
+        tabs.current_mut().activate(&window).unwrap();
+        let msgs = window.messages.take();
+        assert_eq!(msgs.len(), 4, "{msgs:?}");
+        assert!(msgs[0].contains("render_tree"), "{msgs:?}");
+        assert!(msgs[1].contains(r#""kind": "metadata""#), "{msgs:?}");
+        assert!(msgs[2].contains(r#""kind": "path""#), "{msgs:?}");
+        assert!(msgs[3].contains(r#""blockTop": 3"#), "{msgs:?}");
+        assert!(window.title.borrow().starts_with("Shiba: "), "{}", window.title.borrow());
+    }
+}
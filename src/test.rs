@@ -1,15 +1,33 @@
-use crate::config::Config;
+use crate::config::{Config, Markdown};
 use crate::renderer::{
     Event, EventHandler, MessageToWindow, RawMessageWriter, Renderer, RendererHandle, Request,
     Window, WindowAppearance, WindowHandles, WindowState, ZoomLevel,
 };
 use anyhow::Result;
 use std::cell::RefCell;
-use std::rc::Rc;
 use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
 use std::sync::mpsc::{Receiver, Sender, channel};
 use std::time::Duration;
 
+// All Markdown extensions are disabled. Only the syntax defined in CommonMark spec is available.
+pub fn commonmark() -> Markdown {
+    Markdown {
+        tables: false,
+        footnotes: false,
+        strikethrough: false,
+        task_lists: false,
+        math: false,
+        alerts: false,
+        definition_lists: false,
+        superscript: false,
+        subscript: false,
+        front_matter: false,
+        smart_punctuation: false,
+        heading_attributes: false,
+        wikilinks: false,
+    }
+}
+
 #[derive(Default)]
 pub struct TestWindow {
     pub messages: RefCell<Vec<String>>,
@@ -27,6 +45,7 @@ pub struct TestWindow {
     pub menu_visible: bool,
     pub is_low_memory: bool,
     pub cache_deleted: bool,
+    pub assets_reloaded: bool,
     pub window_handles_requested: AtomicBool,
     pub window_id: u32,
     pub is_focused: AtomicBool,
@@ -141,6 +160,10 @@ impl Window for TestWindow {
         Ok(())
     }
 
+    fn reload_assets(&mut self, _config: &Config) {
+        self.assets_reloaded = true;
+    }
+
     fn handles(&self) -> WindowHandles<'_> {
         self.window_handles_requested.store(true, Ordering::Relaxed);
         WindowHandles::unavailable()
@@ -170,6 +193,10 @@ impl RendererHandle for TestRendererHandle {
     fn create_window(&self) {
         self.tx.send(Request::CreateWindow).unwrap();
     }
+
+    fn restore_window(&self, state: WindowState) {
+        self.tx.send(Request::RestoreWindow(state)).unwrap();
+    }
 }
 
 pub struct TestRenderer {
@@ -185,6 +212,10 @@ impl TestRenderer {
     pub fn recv_timeout(&self, timeout: Duration) -> Request<u32> {
         self.rx.recv_timeout(timeout).unwrap()
     }
+
+    pub fn try_recv(&self) -> Option<Request<u32>> {
+        self.rx.try_recv().ok()
+    }
 }
 
 impl Renderer for TestRenderer {
@@ -192,7 +223,7 @@ impl Renderer for TestRenderer {
     type Window = TestWindow;
     type Handle = TestRendererHandle;
 
-    fn new(_: Rc<Config>) -> Result<Self> {
+    fn new() -> Result<Self> {
         Ok(Self::default())
     }
 
@@ -217,7 +248,7 @@ impl Default for TestRenderer {
 
 #[test]
 fn test_renderer_create_window() {
-    let renderer = TestRenderer::new(Rc::new(Config::default())).unwrap();
+    let renderer = TestRenderer::new().unwrap();
     let handle = renderer.create_handle();
     handle.create_window();
     let req = renderer.recv();
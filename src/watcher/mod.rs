@@ -14,6 +14,7 @@ use anyhow::Result;
 use notify::event::{CreateKind, DataChange, EventKind, ModifyKind};
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 
 fn find_watch_path_fallback(path: &Path) -> Result<&Path> {
@@ -37,17 +38,32 @@ fn should_watch_event(kind: EventKind) -> bool {
     )
 }
 
+// File extensions are shared with the watcher since the filter is moved to the watcher's callback and they can be
+// changed after the config file is reloaded.
+pub type SharedFileExtensions = Arc<Mutex<FileExtensions>>;
+
 pub struct PathFilter {
-    extensions: FileExtensions,
+    extensions: SharedFileExtensions,
+    files: Vec<PathBuf>,
     last_changed: HashMap<PathBuf, Instant>,
     debounce_throttle: Duration,
 }
 
 impl PathFilter {
     pub fn new(config: &Config) -> Self {
-        let extensions = config.file_extensions.clone();
+        let extensions = Arc::new(Mutex::new(config.file_extensions.clone()));
         let debounce_throttle = config.debounce_throttle();
-        Self { extensions, last_changed: HashMap::new(), debounce_throttle }
+        Self { extensions, files: vec![], last_changed: HashMap::new(), debounce_throttle }
+    }
+
+    // Files which are not Markdown but their changes should be notified (e.g. config file)
+    pub fn with_files(mut self, files: Vec<PathBuf>) -> Self {
+        self.files = files;
+        self
+    }
+
+    pub fn file_extensions(&self) -> SharedFileExtensions {
+        self.extensions.clone()
     }
 
     // XXX: Watcher sends the event at the first file-changed event durating debounce throttle.
@@ -68,7 +84,9 @@ impl PathFilter {
     }
 
     fn should_retain(&mut self, path: &Path) -> bool {
-        self.extensions.matches(path) && path.is_file() && self.debounce(path)
+        (self.extensions.lock().unwrap().matches(path) || self.files.iter().any(|f| f == path))
+            && path.is_file()
+            && self.debounce(path)
     }
 
     fn cleanup_debouncer(&mut self) {
@@ -86,6 +104,10 @@ impl PathFilter {
 pub trait Watcher: Sized {
     fn new<H: RendererHandle>(handle: H, filter: PathFilter) -> Result<Self>;
     fn watch(&mut self, path: &Path) -> Result<()>;
+    // Directories watched recursively. Files in them are also watched.
+    fn watched_dirs(&self) -> Vec<PathBuf>;
+    // Update the file extensions of the filter on reloading the config file
+    fn set_file_extensions(&mut self, extensions: FileExtensions);
 }
 
 pub struct NopWatcher;
@@ -97,6 +119,10 @@ impl Watcher for NopWatcher {
     fn watch(&mut self, _path: &Path) -> Result<()> {
         Ok(())
     }
+    fn watched_dirs(&self) -> Vec<PathBuf> {
+        vec![]
+    }
+    fn set_file_extensions(&mut self, _extensions: FileExtensions) {}
 }
 
 #[cfg(test)]
@@ -122,5 +148,21 @@ mod tests {
         assert!(!filter.should_retain(Path::new("README.md"))); // Debounced
         assert!(!filter.should_retain(Path::new("Cargo.toml")));
         assert!(!filter.should_retain(Path::new("this-file-does-not-exist.md")));
+
+        let mut filter = PathFilter::new(&Config::default()).with_files(vec!["Cargo.toml".into()]);
+        assert!(filter.should_retain(Path::new("Cargo.toml")));
+        assert!(filter.should_retain(Path::new("README.md")));
+        assert!(!filter.should_retain(Path::new("Cargo.lock")));
+    }
+
+    #[test]
+    fn path_filter_update_file_extensions() {
+        let mut filter = PathFilter::new(&Config::default());
+        let extensions = filter.file_extensions();
+        assert!(!filter.should_retain(Path::new("Cargo.toml")));
+
+        *extensions.lock().unwrap() = FileExtensions::new(["toml"]);
+        assert!(filter.should_retain(Path::new("Cargo.toml")));
+        assert!(!filter.should_retain(Path::new("README.md")));
     }
 }
@@ -1,4 +1,7 @@
-use super::{PathFilter, Watcher, find_watch_path_fallback, should_watch_event};
+use super::{
+    PathFilter, SharedFileExtensions, Watcher, find_watch_path_fallback, should_watch_event,
+};
+use crate::config::FileExtensions;
 use crate::renderer::{Event, RendererHandle};
 use anyhow::{Context as _, Result};
 use notify::{RecommendedWatcher, RecursiveMode, Watcher as NotifyWatcher, recommended_watcher};
@@ -8,10 +11,12 @@ use std::path::{Path, PathBuf};
 pub struct SystemWatcher {
     inner: RecommendedWatcher,
     watching: HashMap<PathBuf, RecursiveMode>,
+    extensions: SharedFileExtensions,
 }
 
 impl Watcher for SystemWatcher {
     fn new<H: RendererHandle>(handle: H, mut filter: PathFilter) -> Result<Self> {
+        let extensions = filter.file_extensions();
         let inner = recommended_watcher(move |res: notify::Result<notify::Event>| match res {
             Ok(event) if should_watch_event(event.kind) => {
                 log::debug!("Caught filesystem event: {:?}", event);
@@ -33,7 +38,7 @@ impl Watcher for SystemWatcher {
             }
         })?;
 
-        Ok(Self { inner, watching: HashMap::new() })
+        Ok(Self { inner, watching: HashMap::new(), extensions })
     }
 
     fn watch(&mut self, path: &Path) -> Result<()> {
@@ -63,4 +68,16 @@ impl Watcher for SystemWatcher {
             }
         }
     }
+
+    fn watched_dirs(&self) -> Vec<PathBuf> {
+        self.watching
+            .iter()
+            .filter(|(_, mode)| **mode == RecursiveMode::Recursive)
+            .map(|(path, _)| path.clone())
+            .collect()
+    }
+
+    fn set_file_extensions(&mut self, extensions: FileExtensions) {
+        *self.extensions.lock().unwrap() = extensions;
+    }
 }
@@ -1,4 +1,7 @@
-use super::{PathFilter, Watcher, find_watch_path_fallback, should_watch_event};
+use super::{
+    PathFilter, SharedFileExtensions, Watcher, find_watch_path_fallback, should_watch_event,
+};
+use crate::config::FileExtensions;
 use crate::renderer::{Event, RendererHandle};
 use anyhow::{Context as _, Result};
 use notify::{RecommendedWatcher, RecursiveMode, Watcher as _, recommended_watcher};
@@ -63,10 +66,12 @@ impl WatchingPaths {
 pub struct SystemWatcher {
     inner: RecommendedWatcher,
     watching: Arc<Mutex<WatchingPaths>>,
+    extensions: SharedFileExtensions,
 }
 
 impl Watcher for SystemWatcher {
     fn new<H: RendererHandle>(handle: H, mut filter: PathFilter) -> Result<Self> {
+        let extensions = filter.file_extensions();
         let watching = Arc::new(Mutex::new(WatchingPaths::default()));
         let inner = {
             let watching = watching.clone();
@@ -94,7 +99,7 @@ impl Watcher for SystemWatcher {
                 }
             })?
         };
-        Ok(Self { inner, watching })
+        Ok(Self { inner, watching, extensions })
     }
 
     fn watch(&mut self, path: &Path) -> Result<()> {
@@ -114,6 +119,14 @@ impl Watcher for SystemWatcher {
 
         Ok(())
     }
+
+    fn watched_dirs(&self) -> Vec<PathBuf> {
+        self.watching.lock().unwrap().dirs.iter().cloned().collect()
+    }
+
+    fn set_file_extensions(&mut self, extensions: FileExtensions) {
+        *self.extensions.lock().unwrap() = extensions;
+    }
 }
 
 #[cfg(test)]
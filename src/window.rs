@@ -1,11 +1,14 @@
+use crate::history::History;
 use crate::preview::Preview;
 use crate::renderer::Renderer;
+use crate::tabs::Tabs;
 use std::collections::HashMap;
 use std::fmt;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Error<Id> {
     UnknownWindow(Id),
+    UnknownWindowNumber(u32),
     NoWindowAvailable,
 }
 
@@ -13,6 +16,7 @@ impl<Id: fmt::Debug> fmt::Display for Error<Id> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Self::UnknownWindow(id) => write!(f, "unknown window ID: {id:?}"),
+            Self::UnknownWindowNumber(num) => write!(f, "unknown window number: {num}"),
             Self::NoWindowAvailable => write!(f, "no window is available"),
         }
     }
@@ -20,14 +24,20 @@ impl<Id: fmt::Debug> fmt::Display for Error<Id> {
 
 impl<Id: fmt::Debug> std::error::Error for Error<Id> {}
 
+type EntryMut<'a, R> = (&'a mut <R as Renderer>::Window, &'a mut Preview, &'a mut History);
+
 pub struct WindowManager<R: Renderer> {
-    windows: HashMap<R::WindowId, (R::Window, Preview)>,
+    // Each window has its own tabs. Each tab has its own navigation history for going back and forward
+    windows: HashMap<R::WindowId, (R::Window, Tabs)>,
     focused: Option<R::WindowId>,
+    // Window IDs are opaque so windows are numbered to be specified from outside of the process
+    numbers: HashMap<R::WindowId, u32>,
+    next_number: u32,
 }
 
 impl<R: Renderer> Default for WindowManager<R> {
     fn default() -> Self {
-        Self { windows: HashMap::new(), focused: None }
+        Self { windows: HashMap::new(), focused: None, numbers: HashMap::new(), next_number: 1 }
     }
 }
 
@@ -35,42 +45,88 @@ impl<R: Renderer> WindowManager<R> {
     pub fn focused(&self) -> Result<(&R::Window, &Preview), Error<R::WindowId>> {
         if let Some(id) = self.focused {
             self.get(id)
-        } else if let Some((win, prev)) = self.windows.values().next() {
-            Ok((win, prev)) // Fallback
+        } else if let Some((win, tabs)) = self.windows.values().next() {
+            Ok((win, tabs.current())) // Fallback
         } else {
             Err(Error::NoWindowAvailable)
         }
     }
 
     pub fn get(&self, id: R::WindowId) -> Result<(&R::Window, &Preview), Error<R::WindowId>> {
-        self.windows.get(&id).map(|(win, prev)| (win, prev)).ok_or(Error::UnknownWindow(id))
+        self.windows
+            .get(&id)
+            .map(|(win, tabs)| (win, tabs.current()))
+            .ok_or(Error::UnknownWindow(id))
     }
 
     pub fn get_mut(
         &mut self,
         id: R::WindowId,
     ) -> Result<(&mut R::Window, &mut Preview), Error<R::WindowId>> {
-        self.windows.get_mut(&id).map(|(win, prev)| (win, prev)).ok_or(Error::UnknownWindow(id))
+        self.windows
+            .get_mut(&id)
+            .map(|(win, tabs)| (win, tabs.current_mut()))
+            .ok_or(Error::UnknownWindow(id))
+    }
+
+    pub fn tabs(&self, id: R::WindowId) -> Result<(&R::Window, &Tabs), Error<R::WindowId>> {
+        self.windows.get(&id).map(|(win, tabs)| (win, tabs)).ok_or(Error::UnknownWindow(id))
     }
 
-    pub fn iter_mut(
+    pub fn tabs_mut(
         &mut self,
-    ) -> impl Iterator<Item = (R::WindowId, &mut R::Window, &mut Preview)> {
-        self.windows.iter_mut().map(|(&i, (w, p))| (i, w, p))
+        id: R::WindowId,
+    ) -> Result<(&mut R::Window, &mut Tabs), Error<R::WindowId>> {
+        self.windows.get_mut(&id).map(|(win, tabs)| (win, tabs)).ok_or(Error::UnknownWindow(id))
+    }
+
+    // Navigation history of the current tab
+    pub fn history(&self, id: R::WindowId) -> Result<&History, Error<R::WindowId>> {
+        self.windows.get(&id).map(|(_, tabs)| tabs.history()).ok_or(Error::UnknownWindow(id))
+    }
+
+    pub fn get_mut_with_history(
+        &mut self,
+        id: R::WindowId,
+    ) -> Result<EntryMut<'_, R>, Error<R::WindowId>> {
+        self.windows
+            .get_mut(&id)
+            .map(|(win, tabs)| {
+                let (preview, hist) = tabs.current_with_history();
+                (win, preview, hist)
+            })
+            .ok_or(Error::UnknownWindow(id))
+    }
+
+    pub fn clear_histories(&mut self) {
+        for (_, tabs) in self.windows.values_mut() {
+            tabs.clear_histories();
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (R::WindowId, &R::Window, &Tabs)> {
+        self.windows.iter().map(|(&i, (w, t))| (i, w, t))
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (R::WindowId, &mut R::Window, &mut Tabs)> {
+        self.windows.iter_mut().map(|(&i, (w, t))| (i, w, t))
     }
 
     pub fn is_last(&self, id: R::WindowId) -> bool {
         self.windows.len() == 1 && self.windows.contains_key(&id)
     }
 
-    pub fn add(&mut self, id: R::WindowId, window: R::Window) {
+    pub fn add(&mut self, id: R::WindowId, window: R::Window, history: History) {
         log::debug!("Add new window: {id:?}");
-        self.windows.insert(id, (window, Preview::default()));
+        self.windows.insert(id, (window, Tabs::new(history)));
+        self.numbers.insert(id, self.next_number);
+        self.next_number += 1;
         self.set_focus(id);
     }
 
     pub fn close(&mut self, id: R::WindowId) -> bool {
         let removed = self.windows.remove(&id).is_some();
+        self.numbers.remove(&id);
         log::debug!("Closed window {id:?} (removed={removed})");
         if self.focused == Some(id) {
             log::debug!("Focus was lost because the window was removed");
@@ -82,6 +138,7 @@ impl<R: Renderer> WindowManager<R> {
     pub fn close_others(&mut self, id: R::WindowId) {
         log::debug!("Close all windows other than window {id:?}");
         self.windows.retain(|&i, _| i == id);
+        self.numbers.retain(|&i, _| i == id);
         self.focused = self.windows.contains_key(&id).then_some(id);
     }
 
@@ -97,6 +154,17 @@ impl<R: Renderer> WindowManager<R> {
         self.focused = Some(id);
     }
 
+    pub fn number(&self, id: R::WindowId) -> Option<u32> {
+        self.numbers.get(&id).copied()
+    }
+
+    pub fn id_by_number(&self, number: u32) -> Result<R::WindowId, Error<R::WindowId>> {
+        self.numbers
+            .iter()
+            .find_map(|(&id, &n)| (n == number).then_some(id))
+            .ok_or(Error::UnknownWindowNumber(number))
+    }
+
     pub fn focused_id(&self) -> Result<R::WindowId, Error<R::WindowId>> {
         if let Some(id) = self.focused {
             Ok(id)
@@ -110,11 +178,12 @@ impl<R: Renderer> WindowManager<R> {
 mod tests {
     use super::*;
     use crate::test::{TestRenderer, TestWindow};
+    use std::path::Path;
 
     fn add(wm: &mut WindowManager<TestRenderer>) -> u32 {
         let w = TestWindow::new();
         let id = w.window_id;
-        wm.add(id, w);
+        wm.add(id, w, History::new(10));
         id
     }
 
@@ -222,6 +291,43 @@ mod tests {
         assert_eq!(wm.focused_id().unwrap(), id);
     }
 
+    #[test]
+    fn window_numbers() {
+        let wm = &mut WindowManager::<TestRenderer>::default();
+        let (id1, id2, id3) = (add(wm), add(wm), add(wm));
+        assert_eq!(wm.number(id1), Some(1));
+        assert_eq!(wm.number(id2), Some(2));
+        assert_eq!(wm.number(id3), Some(3));
+        assert_eq!(wm.id_by_number(2).unwrap(), id2);
+
+        // Numbers are not reused after closing windows
+        assert!(wm.close(id2));
+        assert_eq!(wm.number(id2), None);
+        assert!(matches!(wm.id_by_number(2), Err(Error::UnknownWindowNumber(2))));
+        let id4 = add(wm);
+        assert_eq!(wm.number(id4), Some(4));
+
+        wm.close_others(id3);
+        assert_eq!(wm.number(id1), None);
+        assert_eq!(wm.number(id4), None);
+        assert_eq!(wm.id_by_number(3).unwrap(), id3);
+    }
+
+    #[test]
+    fn history_per_window() {
+        let wm = &mut WindowManager::<TestRenderer>::default();
+        let (id1, id2) = (add(wm), add(wm));
+        wm.get_mut_with_history(id1).unwrap().2.push("a.md".into());
+        wm.get_mut_with_history(id2).unwrap().2.push("b.md".into());
+        assert_eq!(wm.history(id1).unwrap().current(), Some(Path::new("a.md")));
+        assert_eq!(wm.history(id2).unwrap().current(), Some(Path::new("b.md")));
+        assert!(matches!(wm.history(100), Err(Error::UnknownWindow(100))));
+
+        wm.clear_histories();
+        assert_eq!(wm.history(id1).unwrap().current(), None);
+        assert_eq!(wm.history(id2).unwrap().current(), None);
+    }
+
     #[test]
     fn check_last_window() {
         let invalid_id = 100;
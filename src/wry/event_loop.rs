@@ -1,14 +1,13 @@
 #[cfg(target_os = "macos")]
 use crate::assets::set_app_icon_to_dock;
-use crate::config::Config;
 use crate::renderer::{
     EventHandler, Renderer, RendererHandle, RenderingFlow, Window, WindowEvent as AppWindowEvent,
+    WindowState,
 };
 use crate::wry::menu::Menu;
 use crate::wry::types::{Event as AppEvent, Proxy, Request};
 use crate::wry::webview::WebViewWindow;
 use anyhow::Result;
-use std::rc::Rc;
 use tao::event::{Event, StartCause, WindowEvent};
 use tao::event_loop::EventLoop;
 use tao::event_loop::{ControlFlow, EventLoopBuilder};
@@ -19,7 +18,6 @@ use tao::window::WindowId;
 pub struct Wry {
     event_loop: EventLoop<Request>,
     menu: Menu,
-    config: Rc<Config>,
 }
 
 impl RendererHandle for Proxy {
@@ -36,6 +34,12 @@ impl RendererHandle for Proxy {
             log::error!("Could not send window creation request: {}", err);
         }
     }
+
+    fn restore_window(&self, state: WindowState) {
+        if let Err(err) = self.send_event(Request::RestoreWindow(state)) {
+            log::error!("Could not send window restoration request: {}", err);
+        }
+    }
 }
 
 impl Renderer for Wry {
@@ -43,7 +47,7 @@ impl Renderer for Wry {
     type WindowId = WindowId;
     type Window = WebViewWindow;
 
-    fn new(config: Rc<Config>) -> Result<Self> {
+    fn new() -> Result<Self> {
         // `EventLoopBuilder::with_app_id` on Linux is not usable because it can cause SEGV.
         // See https://github.com/tauri-apps/tao/issues/1186
 
@@ -61,7 +65,7 @@ impl Renderer for Wry {
         };
 
         menu.create(event_loop.create_proxy())?;
-        Ok(Self { event_loop, menu, config })
+        Ok(Self { event_loop, menu })
     }
 
     fn create_handle(&self) -> Self::Handle {
@@ -90,23 +94,26 @@ impl Renderer for Wry {
                     log::debug!("Closing window was requested: {window_id:?}");
                     handler.on_window(window_id, AppWindowEvent::Closed)
                 }
-                Event::UserEvent(request) => match request {
-                    Request::Emit(event) => handler.on_event(event),
-                    Request::CreateWindow => {
-                        let created = WebViewWindow::new(
-                            &self.config,
-                            event_loop,
-                            proxy.clone(),
-                            self.menu.window_menu(),
-                        );
-                        match created {
-                            Ok(window) => {
-                                handler.on_window(window.id(), AppWindowEvent::Created(window))
-                            }
-                            Err(err) => handler.on_event(AppEvent::Error(err)),
+                Event::UserEvent(Request::Emit(event)) => handler.on_event(event),
+                Event::UserEvent(request) => {
+                    let state = match request {
+                        Request::RestoreWindow(state) => Some(state),
+                        _ => None,
+                    };
+                    let created = WebViewWindow::new(
+                        handler.config(),
+                        event_loop,
+                        proxy.clone(),
+                        self.menu.window_menu(),
+                        state,
+                    );
+                    match created {
+                        Ok(window) => {
+                            handler.on_window(window.id(), AppWindowEvent::Created(window))
                         }
+                        Err(err) => handler.on_event(AppEvent::Error(err)),
                     }
-                },
+                }
                 Event::WindowEvent { event: WindowEvent::Resized(size), window_id, .. } => {
                     let next_minimized = size.height == 0 || size.width == 0;
                     if next_minimized != is_minimized {
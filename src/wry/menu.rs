@@ -80,7 +80,9 @@ impl Menu {
         let search = item("Search…");
         let search_next = item("Search Next");
         let search_prev = item("Search Previous");
+        let search_all = item("Search All Files…");
         let outline = item("Section Outline…");
+        let split_view = item("Toggle Split View");
         let reload = item("Reload");
         let zoom_in = item("Zoom In");
         let zoom_out = item("Zoom Out");
@@ -91,10 +93,16 @@ impl Menu {
         let back = item("Back");
         let top = item("Latest");
         let history = item("History…");
+        let bookmark = item("Bookmark This Position…");
+        let bookmarks = item("Bookmarks…");
         let new_window = item("New Window");
         let dup_window = item("Duplicate Window");
         let close_window = item("Close Window");
         let close_other_wins = item("Close All Other Windows");
+        let new_tab = item("New Tab");
+        let next_tab = item("Next Tab");
+        let prev_tab = item("Previous Tab");
+        let close_tab = item("Close Tab");
         let minimize = item("Minimize");
         let maximize = item("Maximize");
         let always_on_top = item("Pin/Unpin On Top");
@@ -112,6 +120,11 @@ impl Menu {
                 &close_window,
                 &close_other_wins,
                 &PredefinedMenuItem::separator(),
+                &new_tab,
+                &next_tab,
+                &prev_tab,
+                &close_tab,
+                &PredefinedMenuItem::separator(),
                 &minimize,
                 &maximize,
                 #[cfg(target_os = "macos")]
@@ -197,7 +210,10 @@ impl Menu {
                     &search,
                     &search_next,
                     &search_prev,
+                    &search_all,
                     &outline,
+                    &PredefinedMenuItem::separator(),
+                    &split_view,
                 ],
             )?,
             &Submenu::with_items(
@@ -210,6 +226,9 @@ impl Menu {
                     &PredefinedMenuItem::separator(),
                     &history,
                     &delete_history,
+                    &PredefinedMenuItem::separator(),
+                    &bookmark,
+                    &bookmarks,
                 ],
             )?,
             &window_menu,
@@ -231,16 +250,24 @@ impl Menu {
                 (search.into_id(),           Search),
                 (search_next.into_id(),      SearchNext),
                 (search_prev.into_id(),      SearchPrevious),
+                (search_all.into_id(),       SearchAll),
                 (outline.into_id(),          Outline),
+                (split_view.into_id(),       ToggleSplitView),
                 (print.into_id(),            Print),
                 (zoom_in.into_id(),          ZoomIn),
                 (zoom_out.into_id(),         ZoomOut),
                 (history.into_id(),          History),
+                (bookmark.into_id(),         Bookmark),
+                (bookmarks.into_id(),        Bookmarks),
                 (always_on_top.into_id(),    ToggleAlwaysOnTop),
                 (new_window.into_id(),       NewWindow),
                 (dup_window.into_id(),       DuplicateWindow),
                 (close_window.into_id(),     CloseWindow),
                 (close_other_wins.into_id(), CloseAllOtherWindows),
+                (new_tab.into_id(),          NewTab),
+                (next_tab.into_id(),         NextTab),
+                (prev_tab.into_id(),         PreviousTab),
+                (close_tab.into_id(),        CloseTab),
                 (minimize.into_id(),         ToggleMinimizeWindow),
                 (maximize.into_id(),         ToggleMaximizeWindow),
                 (guide.into_id(),            Help),
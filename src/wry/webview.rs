@@ -8,7 +8,9 @@ use crate::wry::menu::WindowMenu;
 use crate::wry::monitor::MonitorExtWorkArea as _;
 use crate::wry::types::{EventLoop, Proxy};
 use anyhow::{Context as _, Error, Result};
+use std::cell::RefCell;
 use std::num::NonZeroU32;
+use std::rc::Rc;
 use tao::dpi::{LogicalPosition, LogicalSize, PhysicalPosition, PhysicalSize};
 #[cfg(target_os = "macos")]
 use tao::platform::macos::WindowBuilderExtMacOS as _;
@@ -75,13 +77,18 @@ impl MaximizeWindow {
     }
 }
 
-fn create_window(event_loop: &EventLoop, config: &Config) -> Result<(Window, ZoomLevel, bool)> {
+fn create_window(
+    event_loop: &EventLoop,
+    config: &Config,
+    state: Option<WindowState>,
+) -> Result<(Window, ZoomLevel, bool)> {
     let mut builder = WindowBuilder::new()
         .with_title("Shiba")
         .with_visible(false)
         .with_min_inner_size(LogicalSize { width: 100.0, height: 100.0 });
 
-    let window_state = if config.window().restore { config.data_dir().load() } else { None };
+    let window_state =
+        state.or_else(|| config.window().restore.then(|| config.data_dir().load()).flatten());
     let (zoom_level, always_on_top, delayed_maximize) = if let Some(state) = window_state {
         log::debug!("Restoring window state: {state:?}");
         let WindowState { height, width, x, y, fullscreen, zoom_level, always_on_top, maximized } =
@@ -189,6 +196,28 @@ fn create_window(event_loop: &EventLoop, config: &Config) -> Result<(Window, Zoo
     Ok((window, zoom_level, always_on_top))
 }
 
+// Heading IDs may contain non-ASCII characters (e.g. "#日本語") and file names may contain white spaces (e.g.
+// "Other Note.md"). They are percent-encoded in URLs.
+fn percent_decode(frag: &str) -> String {
+    let bytes = frag.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%'
+            && let Some(hex) = frag.get(i + 1..i + 3)
+            && hex.bytes().all(|b| b.is_ascii_hexdigit())
+            && let Ok(b) = u8::from_str_radix(hex, 16)
+        {
+            decoded.push(b);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(decoded).unwrap_or_else(|_| frag.to_string())
+}
+
 fn parse_local_path_from_url(mut url: String) -> Result<InitFile, String> {
     // Custom protocol URLs are different for each platform
     //   macOS, Linux → <scheme_name>://<path>
@@ -210,13 +239,14 @@ fn parse_local_path_from_url(mut url: String) -> Result<InitFile, String> {
     let scroll = if let Some(idx) = url.rfind('#')
         && !url[idx..].contains('/')
     {
-        let frag = url[idx + 1..].to_string(); // Get hash: /a/b#foo -> foo
+        let frag = percent_decode(&url[idx + 1..]); // Get hash: /a/b#foo -> foo
         url.truncate(idx); // Remove hash link: /a/b#foo -> /a/b
         if frag.is_empty() { InitScroll::Nop } else { InitScroll::Fragment(frag) }
     } else {
         InitScroll::Nop
     };
 
+    let url = percent_decode(&url);
     #[cfg(not(target_os = "windows"))]
     let path = url.into();
     #[cfg(target_os = "windows")]
@@ -224,11 +254,15 @@ fn parse_local_path_from_url(mut url: String) -> Result<InitFile, String> {
     Ok(InitFile { path, scroll })
 }
 
-fn create_webview(window: &Window, ipc_proxy: Proxy, config: &Config) -> Result<WebView> {
+fn create_webview(
+    window: &Window,
+    ipc_proxy: Proxy,
+    config: &Config,
+    assets: Rc<RefCell<Assets>>,
+) -> Result<WebView> {
     let dnd_proxy = ipc_proxy.clone();
     let navigation_proxy = ipc_proxy.clone();
     let new_window_proxy = ipc_proxy.clone();
-    let loader = Assets::new(config);
 
     let user_dir = config.data_dir().path().map(|dir| dir.join("WebView"));
     let id = window.id();
@@ -299,7 +333,7 @@ fn create_webview(window: &Window, ipc_proxy: Proxy, config: &Config) -> Result<
             let uri = request.uri();
             log::debug!("Handling custom protocol: {:?}", uri);
             let path = uri.path();
-            let (content, mime) = loader.load(path);
+            let (content, mime) = assets.borrow().load(path);
             let (body, status) =
                 if let Some(content) = content { (content, 200) } else { (vec![].into(), 404) };
             // The header and status are never invalid so `.unwrap()` call never panics
@@ -356,6 +390,7 @@ pub struct WebViewWindow {
     always_on_top: bool,
     menu: WindowMenu,
     is_vibrant: bool,
+    assets: Rc<RefCell<Assets>>, // Shared with the custom protocol handler to replace them on reloading config
 }
 
 impl WebViewWindow {
@@ -364,14 +399,16 @@ impl WebViewWindow {
         event_loop: &EventLoop,
         proxy: Proxy,
         mut menu: WindowMenu,
+        state: Option<WindowState>,
     ) -> Result<Self> {
-        let (window, zoom_level, always_on_top) = create_window(event_loop, config)?;
+        let (window, zoom_level, always_on_top) = create_window(event_loop, config, state)?;
 
         if config.window().menu_bar != menu.is_visible() {
             menu.toggle(&window)?;
         }
 
-        let webview = create_webview(&window, proxy, config)?;
+        let assets = Rc::new(RefCell::new(Assets::new(config)));
+        let webview = create_webview(&window, proxy, config, assets.clone())?;
         log::debug!("WebView was created successfully");
 
         let zoom_factor = zoom_level.factor();
@@ -381,7 +418,7 @@ impl WebViewWindow {
         }
 
         let is_vibrant = config.window().is_vibrant();
-        Ok(Self { webview, window, zoom_level, always_on_top, menu, is_vibrant })
+        Ok(Self { webview, window, zoom_level, always_on_top, menu, is_vibrant, assets })
     }
 }
 
@@ -532,6 +569,11 @@ impl RendererWindow for WebViewWindow {
         Ok(())
     }
 
+    fn reload_assets(&mut self, config: &Config) {
+        log::debug!("Reloading assets for window {:?}", self.window.id());
+        *self.assets.borrow_mut() = Assets::new(config);
+    }
+
     fn handles(&self) -> WindowHandles<'_> {
         WindowHandles::new(&self.window)
     }
@@ -544,3 +586,31 @@ impl RendererWindow for WebViewWindow {
         self.window.set_focus();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_percent_encoded() {
+        for (input, want) in [
+            ("heading", "heading"),
+            ("%E6%97%A5%E6%9C%AC%E8%AA%9E", "日本語"),
+            ("foo%20bar", "foo bar"),
+            ("100%", "100%"),
+            ("%zz%4", "%zz%4"),
+            ("%FF", "%FF"),
+        ] {
+            assert_eq!(percent_decode(input), want, "input={input:?}");
+        }
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    #[test]
+    fn parse_local_path_with_spaces() {
+        let url = "shiba://localhost/path/to/Other%20Note.md#some-heading".to_string();
+        let InitFile { path, scroll } = parse_local_path_from_url(url).unwrap();
+        assert_eq!(path, std::path::Path::new("/path/to/Other Note.md"));
+        assert!(matches!(&scroll, InitScroll::Fragment(f) if f == "some-heading"), "{scroll:?}");
+    }
+}
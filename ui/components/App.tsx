@@ -3,9 +3,13 @@ import { useReducer, useEffect } from 'react';
 import { ThemeProvider, createTheme } from '@mui/material/styles';
 import { Preview } from './Preview';
 import { Search } from './Search';
+import { SearchAll } from './SearchAll';
+import { ReplacePreview } from './ReplacePreview';
 import { Welcome } from './Welcome';
 import { Outline } from './Outline';
 import { History } from './History';
+import { Bookmarks } from './Bookmarks';
+import { BookmarkInput } from './BookmarkInput';
 import { Guide } from './Guide';
 import { Notification } from './Notification';
 import { ConfigContext } from './ConfigContext';
@@ -28,12 +32,21 @@ export const App: React.FC<Props> = ({ dispatcher }) => {
     const {
         previewTree,
         path,
+        metadata,
         searching,
+        searchQuery,
         searchIndex,
         matcher,
+        searchAll,
+        replacePreview,
         outline,
         config,
         history,
+        bookmarks,
+        bookmarking,
+        tabs,
+        currentTab,
+        source,
         help,
         notifying,
         notification,
@@ -44,7 +57,14 @@ export const App: React.FC<Props> = ({ dispatcher }) => {
     let searchInput;
     if (searching && !welcome) {
         searchInput = (
-            <Search index={searchIndex} total={previewTree.matchCount} matcher={matcher} dispatch={dispatch} />
+            <Search
+                key={searchQuery ?? ''}
+                query={searchQuery}
+                index={searchIndex}
+                total={previewTree.matchCount}
+                matcher={matcher}
+                dispatch={dispatch}
+            />
         );
     }
 
@@ -52,7 +72,28 @@ export const App: React.FC<Props> = ({ dispatcher }) => {
     if (welcome) {
         main = <Welcome />;
     } else {
-        main = <Preview tree={previewTree} headings={headings} path={path} dispatch={dispatch} />;
+        main = (
+            <Preview
+                tree={previewTree}
+                headings={headings}
+                path={path}
+                metadata={metadata}
+                tabs={tabs}
+                currentTab={currentTab}
+                source={source}
+                dispatch={dispatch}
+            />
+        );
+    }
+
+    let searchAllDialog;
+    if (searchAll !== null) {
+        searchAllDialog = <SearchAll searchAll={searchAll} matcher={matcher} dispatch={dispatch} />;
+    }
+
+    let replaceDialog;
+    if (replacePreview !== null) {
+        replaceDialog = <ReplacePreview preview={replacePreview} />;
     }
 
     let outlineDialog;
@@ -65,6 +106,16 @@ export const App: React.FC<Props> = ({ dispatcher }) => {
         historyDialog = <History history={history} dispatch={dispatch} />;
     }
 
+    let bookmarksDialog;
+    if (bookmarks.length > 0) {
+        bookmarksDialog = <Bookmarks bookmarks={bookmarks} dispatch={dispatch} />;
+    }
+
+    let bookmarkInput;
+    if (bookmarking !== null && !welcome) {
+        bookmarkInput = <BookmarkInput target={bookmarking} dispatch={dispatch} />;
+    }
+
     let guideDialog;
     if (help) {
         guideDialog = <Guide shortcuts={dispatcher.keymap.shortcuts} dispatcher={dispatcher} />;
@@ -82,8 +133,12 @@ export const App: React.FC<Props> = ({ dispatcher }) => {
             <ConfigContext.Provider value={config}>
                 {main}
                 {searchInput}
+                {searchAllDialog}
+                {replaceDialog}
                 {outlineDialog}
                 {historyDialog}
+                {bookmarksDialog}
+                {bookmarkInput}
                 {guideDialog}
                 <Notification open={notifying} content={notification} dispatch={dispatch} />
             </ConfigContext.Provider>
@@ -1,7 +1,10 @@
 import * as React from 'react';
 import { useEffect, useRef } from 'react';
+import { MetadataTable } from './MetadataTable';
 import type { MarkdownReactTree } from '../markdown';
+import { type Metadata, sendMessage } from '../ipc';
 import { type Dispatch, type Heading, updateHeadings } from '../reducer';
+import { setActivePane, syncSource, topBlockIndex, topLevelBlocks } from '../split';
 import * as log from '../log';
 
 function appearInViewport(elem: Element): boolean {
@@ -56,6 +59,27 @@ function collectHeadings(root: HTMLElement): Heading[] {
     return headings;
 }
 
+const SCROLL_POSITION_TIMEOUT = 300; // 300ms
+
+// Report the top-level block at the top of the viewport to restore the scroll position when the document is reopened
+let scrollPositionId: number | null = null;
+function cancelScrollPosition(): void {
+    if (scrollPositionId !== null) {
+        clearTimeout(scrollPositionId);
+        scrollPositionId = null;
+    }
+}
+function sendScrollPosition(root: HTMLElement): void {
+    cancelScrollPosition();
+    scrollPositionId = setTimeout(() => {
+        scrollPositionId = null;
+        const block = topBlockIndex(root);
+        if (block !== null) {
+            sendMessage({ kind: 'scroll_position', block });
+        }
+    }, SCROLL_POSITION_TIMEOUT);
+}
+
 let currentId: number | null = null;
 function dispatchHeadings(root: HTMLElement, dispatch: Dispatch): void {
     if (currentId !== null) {
@@ -71,12 +95,29 @@ function dispatchHeadings(root: HTMLElement, dispatch: Dispatch): void {
     currentId = setTimeout(callback, 100);
 }
 
+// Open the source of the double-clicked block with the text editor configured by user
+function onDoubleClick(event: React.MouseEvent<HTMLElement>): void {
+    if (!(event.target instanceof Element)) {
+        return;
+    }
+    const offset = event.target.closest<HTMLElement>('[data-offset]')?.dataset.offset;
+    if (offset !== undefined) {
+        log.debug('Opening source at offset', offset);
+        sendMessage({ kind: 'open_source', offset: parseInt(offset, 10) });
+    }
+}
+
+function onMouseEnter(): void {
+    setActivePane('preview');
+}
+
 export interface Props {
     tree: MarkdownReactTree;
+    metadata: Metadata | null;
     dispatch: Dispatch;
 }
 
-export const Article: React.FC<Props> = ({ tree, dispatch }) => {
+export const Article: React.FC<Props> = ({ tree, metadata, dispatch }) => {
     const { root, lastModified, scroll } = tree;
     const ref = useRef<HTMLElement>(null);
 
@@ -107,6 +148,24 @@ export const Article: React.FC<Props> = ({ tree, dispatch }) => {
                         inline: 'start',
                     });
                 }
+            } else if ('block' in scroll) {
+                const block = ref.current && topLevelBlocks(ref.current).item(scroll.block);
+                if (block && !appearInViewport(block)) {
+                    block.scrollIntoView({
+                        behavior: 'instant',
+                        block: 'center',
+                        inline: 'start',
+                    });
+                }
+            } else if ('blockTop' in scroll) {
+                const block = ref.current && topLevelBlocks(ref.current).item(scroll.blockTop);
+                if (block) {
+                    block.scrollIntoView({
+                        behavior: 'instant',
+                        block: 'start',
+                        inline: 'start',
+                    });
+                }
             }
         }
     }, [lastModified, scroll]);
@@ -115,6 +174,8 @@ export const Article: React.FC<Props> = ({ tree, dispatch }) => {
         if (root && ref.current) {
             dispatchHeadings(ref.current, dispatch);
         }
+        // The pending scroll position was for the previous content. It may be for another document.
+        return cancelScrollPosition;
     }, [root, dispatch]);
 
     useEffect(() => {
@@ -124,6 +185,8 @@ export const Article: React.FC<Props> = ({ tree, dispatch }) => {
                 'scroll',
                 () => {
                     dispatchHeadings(elem, dispatch);
+                    sendScrollPosition(elem);
+                    syncSource(elem);
                 },
                 { passive: true },
             );
@@ -131,7 +194,8 @@ export const Article: React.FC<Props> = ({ tree, dispatch }) => {
     }, [dispatch]);
 
     return (
-        <article className="markdown-body" ref={ref}>
+        <article className="markdown-body" ref={ref} onDoubleClick={onDoubleClick} onMouseEnter={onMouseEnter}>
+            {metadata && <MetadataTable metadata={metadata} />}
             {root}
         </article>
     );
@@ -0,0 +1,57 @@
+import * as React from 'react';
+import { useState, useCallback } from 'react';
+import Dialog from '@mui/material/Dialog';
+import DialogActions from '@mui/material/DialogActions';
+import DialogContent from '@mui/material/DialogContent';
+import DialogTitle from '@mui/material/DialogTitle';
+import Button from '@mui/material/Button';
+import TextField from '@mui/material/TextField';
+import { type Dispatch, type BookmarkTarget, closeBookmarkInput } from '../reducer';
+import { sendMessage } from '../ipc';
+
+export interface Props {
+    target: BookmarkTarget;
+    dispatch: Dispatch;
+}
+
+export const BookmarkInput: React.FC<Props> = ({ target, dispatch }) => {
+    const [label, setLabel] = useState(target.label);
+
+    const handleClose = useCallback(() => {
+        dispatch(closeBookmarkInput());
+    }, [dispatch]);
+
+    const handleSubmit = (e: React.FormEvent): void => {
+        e.preventDefault();
+        const { heading, fragment } = target;
+        sendMessage({ kind: 'add_bookmark', label: label.trim() || target.label, heading, fragment });
+        dispatch(closeBookmarkInput());
+    };
+
+    const handleChange = (e: React.ChangeEvent<HTMLInputElement>): void => {
+        setLabel(e.currentTarget.value);
+    };
+
+    return (
+        <Dialog open onClose={handleClose} fullWidth maxWidth="sm">
+            <form onSubmit={handleSubmit}>
+                <DialogTitle>Add bookmark</DialogTitle>
+                <DialogContent>
+                    <TextField
+                        value={label}
+                        onChange={handleChange}
+                        label="Label"
+                        variant="standard"
+                        margin="dense"
+                        autoFocus
+                        fullWidth
+                    />
+                </DialogContent>
+                <DialogActions>
+                    <Button onClick={handleClose}>Cancel</Button>
+                    <Button type="submit">Add</Button>
+                </DialogActions>
+            </form>
+        </Dialog>
+    );
+};
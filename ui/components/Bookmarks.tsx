@@ -0,0 +1,68 @@
+import * as React from 'react';
+import { useMemo, useCallback, useContext } from 'react';
+import IconButton from '@mui/material/IconButton';
+import ListItemText from '@mui/material/ListItemText';
+import DeleteIcon from '@mui/icons-material/Delete';
+import { Palette } from './Palette';
+import { ConfigContext } from './ConfigContext';
+import { type Dispatch, closeBookmarks } from '../reducer';
+import { type Bookmark, sendMessage } from '../ipc';
+import * as log from '../log';
+import { displayPath } from '../path';
+
+interface BookmarkItem {
+    text: string;
+    path: string;
+    index: number;
+}
+
+function renderBookmarkItem({ text, path, index }: BookmarkItem): React.ReactNode {
+    const handleRemove = (e: React.MouseEvent): void => {
+        e.stopPropagation(); // Do not open the bookmark
+        sendMessage({ kind: 'remove_bookmark', index });
+    };
+    return (
+        <>
+            <ListItemText primary={text} secondary={path} />
+            <IconButton size="small" title="Remove bookmark" aria-label="remove bookmark" onClick={handleRemove}>
+                <DeleteIcon fontSize="small" />
+            </IconButton>
+        </>
+    );
+}
+
+export interface Props {
+    bookmarks: Bookmark[];
+    dispatch: Dispatch;
+}
+
+export const Bookmarks: React.FC<Props> = ({ bookmarks, dispatch }) => {
+    const { homeDir } = useContext(ConfigContext);
+    const items = useMemo(
+        () => bookmarks.map(({ label, path }, index) => ({ text: label, path: displayPath(path, homeDir), index })),
+        [bookmarks, homeDir],
+    );
+
+    const handleClose = useCallback(() => {
+        dispatch(closeBookmarks());
+    }, [dispatch]);
+
+    const handleSelect = useCallback(
+        ({ index }: BookmarkItem, shiftKey: boolean) => {
+            log.debug('Opening bookmark:', index, shiftKey);
+            sendMessage({ kind: 'open_bookmark', index, new_window: shiftKey });
+            dispatch(closeBookmarks());
+        },
+        [dispatch],
+    );
+
+    return (
+        <Palette
+            items={items}
+            placeholder="Search bookmarks…"
+            onClose={handleClose}
+            onSelect={handleSelect}
+            renderItem={renderBookmarkItem}
+        />
+    );
+};
@@ -17,7 +17,10 @@ const ALL_MATCHERS: [SearchMatcher, string][] = [
     ['SmartCase', 'smart case'],
     ['CaseSensitive', 'case sensitive'],
     ['CaseInsensitive', 'case insensitive'],
+    ['WholeWord', 'whole word'],
+    ['Fuzzy', 'fuzzy'],
     ['CaseSensitiveRegex', 'regular expression'],
+    ['CaseInsensitiveRegex', 'case insensitive regular expression'],
 ];
 
 interface Props {
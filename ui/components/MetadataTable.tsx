@@ -0,0 +1,43 @@
+import * as React from 'react';
+import type { Metadata } from '../ipc';
+
+function metadataValue(value: unknown): string {
+    if (typeof value === 'string') {
+        return value;
+    }
+    if (Array.isArray(value) && value.every(v => typeof v !== 'object' || v === null)) {
+        return value.map(String).join(', ');
+    }
+    return JSON.stringify(value);
+}
+
+export interface Props {
+    metadata: Metadata;
+}
+
+// Front matter of the document is rendered as a table in the same way as GitHub
+export const MetadataTable: React.FC<Props> = ({ metadata }) => {
+    const entries = Object.entries(metadata);
+    if (entries.length === 0) {
+        return null;
+    }
+
+    return (
+        <table className="front-matter">
+            <thead>
+                <tr>
+                    {entries.map(([key]) => (
+                        <th key={key}>{key}</th>
+                    ))}
+                </tr>
+            </thead>
+            <tbody>
+                <tr>
+                    {entries.map(([key, value]) => (
+                        <td key={key}>{metadataValue(value)}</td>
+                    ))}
+                </tr>
+            </tbody>
+        </table>
+    );
+};
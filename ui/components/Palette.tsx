@@ -43,6 +43,8 @@ export interface Props<T extends Item> {
     onClose: () => void;
     onSelect: (item: T, shiftKey: boolean) => void;
     renderItem: (item: T) => React.ReactNode;
+    // When this callback is set, items are not filtered by the palette. The caller updates items with the query instead
+    onInput?: (query: string) => void;
 }
 
 export function Palette<T extends Item>({
@@ -51,12 +53,15 @@ export function Palette<T extends Item>({
     onClose,
     onSelect,
     renderItem,
+    onInput,
 }: Props<T>): React.ReactElement {
     const theme: Theme = useTheme();
     const [query, setQuery] = useState('');
     const [unadjustedIndex, setIndex] = useState(0);
     const focusedItemRef = useRef<HTMLDivElement>(null);
-    items = query === '' ? items : items.filter(h => h.text.toLowerCase().includes(query));
+    if (onInput === undefined && query !== '') {
+        items = items.filter(h => h.text.toLowerCase().includes(query));
+    }
     const index = unadjustedIndex < items.length ? unadjustedIndex : items.length > 0 ? items.length - 1 : 0;
 
     useEffect(() => {
@@ -72,7 +77,12 @@ export function Palette<T extends Item>({
     }, [index, items]);
 
     const handleInput = (e: React.ChangeEvent<HTMLInputElement>): void => {
-        setQuery(e.currentTarget.value.toLowerCase());
+        const input = e.currentTarget.value;
+        if (onInput === undefined) {
+            setQuery(input.toLowerCase());
+        } else {
+            onInput(input);
+        }
         e.preventDefault();
     };
 
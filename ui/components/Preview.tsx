@@ -6,10 +6,12 @@ import { Resizable } from 're-resizable';
 import { WindowBar } from './WindowBar';
 import { SideBar } from './SideBar';
 import { Article } from './Article';
+import { SourceView } from './SourceView';
 import { ConfigContext } from './ConfigContext';
 import { colorScheme } from '../css';
 import type { MarkdownReactTree } from '../markdown';
-import type { Dispatch, Heading } from '../reducer';
+import type { Dispatch, Heading, Source } from '../reducer';
+import type { Metadata } from '../ipc';
 
 const NAV_RESIZE_DIRECTION = {
     top: false,
@@ -31,10 +33,14 @@ export interface Props {
     tree: MarkdownReactTree;
     headings: Heading[];
     path: string | null;
+    metadata: Metadata | null;
+    tabs: (string | null)[];
+    currentTab: number;
+    source: Source | null; // Source shown side by side in split view
     dispatch: Dispatch;
 }
 
-export const Preview: React.FC<Props> = ({ tree, headings, path, dispatch }) => {
+export const Preview: React.FC<Props> = ({ tree, headings, path, metadata, tabs, currentTab, source, dispatch }) => {
     const { titleBar, vibrant, borderTop } = useContext(ConfigContext);
 
     // Note: `SxProps` type is useless here
@@ -61,10 +67,16 @@ export const Preview: React.FC<Props> = ({ tree, headings, path, dispatch }) =>
         <Box component="main" sx={sx}>
             <Resizable defaultSize={NAV_DEFAULT_SIZE} minWidth="200px" enable={NAV_RESIZE_DIRECTION} as="nav">
                 {titleBar && <WindowBar />}
-                <SideBar headings={headings} path={path} />
+                <SideBar headings={headings} path={path} tabs={tabs} currentTab={currentTab} />
             </Resizable>
             <Divider id="preview-separator" orientation="vertical" />
-            <Article tree={tree} dispatch={dispatch} />
+            <Article tree={tree} metadata={metadata} dispatch={dispatch} />
+            {source && (
+                <>
+                    <Divider id="source-separator" orientation="vertical" />
+                    <SourceView source={source} />
+                </>
+            )}
         </Box>
     );
 };
@@ -0,0 +1,67 @@
+import * as React from 'react';
+import { useContext } from 'react';
+import Dialog from '@mui/material/Dialog';
+import DialogContent from '@mui/material/DialogContent';
+import DialogTitle from '@mui/material/DialogTitle';
+import Typography from '@mui/material/Typography';
+import { useTheme } from '@mui/material/styles';
+import { ConfigContext } from './ConfigContext';
+import type { ReplacePreview as ReplacePreviewState } from '../reducer';
+import type { ReplacedHunk } from '../ipc';
+import { displayPath } from '../path';
+
+const HUNK_STYLE: React.CSSProperties = {
+    margin: '0 0 1em 0',
+    fontSize: '0.8rem',
+    whiteSpace: 'pre-wrap',
+    wordBreak: 'break-all',
+};
+const LINE_NUMBER_STYLE: React.CSSProperties = {
+    opacity: 0.7,
+};
+
+function prefixLines(text: string, prefix: string): string {
+    return text.replace(/^/gm, prefix);
+}
+
+interface HunkProps {
+    hunk: ReplacedHunk;
+}
+
+const Hunk: React.FC<HunkProps> = ({ hunk }) => {
+    const { palette } = useTheme();
+    return (
+        <pre style={HUNK_STYLE}>
+            <div style={LINE_NUMBER_STYLE}>@@ line {hunk.line} @@</div>
+            <div style={{ color: palette.error.main }}>{prefixLines(hunk.old, '-')}</div>
+            <div style={{ color: palette.success.main }}>{prefixLines(hunk.new, '+')}</div>
+        </pre>
+    );
+};
+
+export interface Props {
+    preview: ReplacePreviewState;
+}
+
+// Diff of the replacement shown while the confirmation dialog is opened by the main
+export const ReplacePreview: React.FC<Props> = ({ preview }) => {
+    const { homeDir } = useContext(ConfigContext);
+    const { path, count, skipped, hunks } = preview;
+    let note = `${count} match(es) will be replaced in ${displayPath(path, homeDir)}`;
+    if (skipped > 0) {
+        note += ` (${skipped} match(es) across markups are skipped)`;
+    }
+    return (
+        <Dialog open scroll="paper" fullWidth maxWidth="md">
+            <DialogTitle>Replace preview</DialogTitle>
+            <DialogContent dividers>
+                <Typography variant="body2" color="text.secondary" gutterBottom>
+                    {note}
+                </Typography>
+                {hunks.map((hunk, i) => (
+                    <Hunk hunk={hunk} key={i} />
+                ))}
+            </DialogContent>
+        </Dialog>
+    );
+};
@@ -1,5 +1,5 @@
 import * as React from 'react';
-import { useEffect, useRef } from 'react';
+import { useEffect, useRef, useState } from 'react';
 import Paper from '@mui/material/Paper';
 import IconButton from '@mui/material/IconButton';
 import InputBase from '@mui/material/InputBase';
@@ -7,6 +7,8 @@ import Divider from '@mui/material/Divider';
 import KeyboardArrowUpIcon from '@mui/icons-material/KeyboardArrowUp';
 import KeyboardArrowDownIcon from '@mui/icons-material/KeyboardArrowDown';
 import CloseIcon from '@mui/icons-material/Close';
+import FindReplaceIcon from '@mui/icons-material/FindReplace';
+import DoneAllIcon from '@mui/icons-material/DoneAll';
 import Typography from '@mui/material/Typography';
 import { MatcherSelect } from './MatcherSelect';
 import { type Dispatch, searchNext, searchPrevious, closeSearch } from '../reducer';
@@ -21,8 +23,15 @@ const PAPER_STYLE: React.CSSProperties = {
     margin: '8px',
     padding: '8px',
     display: 'flex',
+    flexWrap: 'wrap',
     alignItems: 'center',
 };
+const REPLACE_ROW_STYLE: React.CSSProperties = {
+    display: 'flex',
+    alignItems: 'center',
+    width: '100%',
+    marginTop: '4px',
+};
 const COUNTER_STYLE: React.CSSProperties = {
     maxWidth: '200px',
     cursor: 'default',
@@ -40,17 +49,21 @@ const DIVIDER_STYLE: React.CSSProperties = {
 };
 
 interface Props {
+    query: string | null;
     index: number | null;
     total: number;
     matcher: SearchMatcher;
     dispatch: Dispatch;
 }
 
-export const Search: React.FC<Props> = ({ index, matcher, dispatch, total }) => {
+export const Search: React.FC<Props> = ({ query, index, matcher, dispatch, total }) => {
     const counterElem = useRef<HTMLDivElement>(null);
     const inputElem = useRef<HTMLInputElement>(null);
+    const replaceElem = useRef<HTMLInputElement>(null);
+    const [replacing, setReplacing] = useState(false);
     const debId = useRef<number | null>(null);
     const matching = useRef<SearchMatcher | null>(null);
+    const initial = useRef({ query, matcher });
 
     const clearPendingSearch = (): void => {
         if (debId.current !== null) {
@@ -59,15 +72,20 @@ export const Search: React.FC<Props> = ({ index, matcher, dispatch, total }) =>
         }
     };
 
-    useEffect(
-        () => () => {
+    useEffect(() => {
+        // Start searching the query which was given when opening the search
+        const { query, matcher } = initial.current;
+        if (query !== null && query !== '') {
+            sendMessage({ kind: 'search', query, index: null, matcher });
+            matching.current = matcher;
+        }
+        return () => {
             clearPendingSearch();
             if (matching.current !== null) {
                 sendMessage({ kind: 'search', query: '', index: null, matcher: matching.current });
             }
-        },
-        [],
-    );
+        };
+    }, []);
 
     useEffect(() => {
         if (counterElem.current !== null) {
@@ -120,6 +138,31 @@ export const Search: React.FC<Props> = ({ index, matcher, dispatch, total }) =>
         }
         e.preventDefault();
     };
+    const handleReplace = (): void => {
+        const query = inputElem.current?.value ?? '';
+        const replacement = replaceElem.current?.value ?? '';
+        if (query !== '') {
+            // The main shows the diff and asks confirmation before writing the file
+            sendMessage({ kind: 'replace', query, matcher, replacement });
+        }
+    };
+    const handleReplaceKeydown = (e: React.KeyboardEvent<HTMLInputElement>): void => {
+        switch (e.key) {
+            case 'Enter':
+                handleReplace();
+                break;
+            case 'Escape':
+                e.currentTarget.blur(); // Workaround for Safari. See `handleKeydown`
+                handleClose();
+                break;
+            default:
+                return;
+        }
+        e.preventDefault();
+    };
+    const toggleReplacing = (): void => {
+        setReplacing(!replacing);
+    };
     const focusInputElem = (): void => {
         // Focus <input> at next tick since re-render will happen after this callback and it will blur the element again
         window.setTimeout(() => inputElem.current?.focus(), 0);
@@ -139,6 +182,7 @@ export const Search: React.FC<Props> = ({ index, matcher, dispatch, total }) =>
                 }}
                 type="search"
                 placeholder="Search…"
+                defaultValue={query ?? ''}
                 autoFocus
             />
             <Typography color="text.secondary" style={COUNTER_STYLE} ref={counterElem}></Typography>
@@ -149,9 +193,30 @@ export const Search: React.FC<Props> = ({ index, matcher, dispatch, total }) =>
             <IconButton size="small" title="Find forward" aria-label="find forward" onClick={handleNext}>
                 <KeyboardArrowDownIcon fontSize="small" />
             </IconButton>
+            <IconButton size="small" title="Toggle replace" aria-label="toggle replace" onClick={toggleReplacing}>
+                <FindReplaceIcon fontSize="small" />
+            </IconButton>
             <IconButton size="small" title="Close search" aria-label="close search" onClick={handleClose}>
                 <CloseIcon fontSize="small" />
             </IconButton>
+            {replacing && (
+                <div style={REPLACE_ROW_STYLE}>
+                    <InputBase
+                        style={INPUT_STYLE}
+                        inputProps={{
+                            'aria-label': 'replace input',
+                            onKeyDown: handleReplaceKeydown,
+                            style: { padding: 0 },
+                            ref: replaceElem,
+                        }}
+                        placeholder="Replace…"
+                        autoFocus
+                    />
+                    <IconButton size="small" title="Replace all" aria-label="replace all" onClick={handleReplace}>
+                        <DoneAllIcon fontSize="small" />
+                    </IconButton>
+                </div>
+            )}
         </Paper>
     );
 };
@@ -0,0 +1,124 @@
+import * as React from 'react';
+import { useMemo, useCallback, useContext, useEffect, useRef } from 'react';
+import { Palette } from './Palette';
+import { ConfigContext } from './ConfigContext';
+import { type Dispatch, type SearchAll as SearchAllState, closeSearchAll } from '../reducer';
+import { type SearchMatcher, type SearchAllHit, sendMessage } from '../ipc';
+import * as log from '../log';
+import { displayPath } from '../path';
+
+const DEBOUNCE_TIMEOUT = 200; // 200ms
+const LOCATION_STYLE: React.CSSProperties = {
+    fontSize: '0.8em',
+    opacity: 0.7,
+};
+const SNIPPET_STYLE: React.CSSProperties = {
+    whiteSpace: 'pre-wrap',
+    wordBreak: 'break-all',
+};
+
+interface SearchAllItem {
+    text: string;
+    path: string | null; // `null` means this item is an error message
+    hit: SearchAllHit | null;
+}
+
+function renderItem(item: SearchAllItem): React.ReactNode {
+    const { text, hit } = item;
+    if (hit === null) {
+        return text;
+    }
+    let location = `${text}:${hit.line}`;
+    if (hit.headings.length > 0) {
+        location += ` — ${hit.headings.join(' › ')}`;
+    }
+    return (
+        <div>
+            <div style={LOCATION_STYLE}>{location}</div>
+            <div style={SNIPPET_STYLE}>
+                {hit.before}
+                <mark>{hit.matched}</mark>
+                {hit.after}
+            </div>
+        </div>
+    );
+}
+
+export interface Props {
+    searchAll: SearchAllState;
+    matcher: SearchMatcher;
+    dispatch: Dispatch;
+}
+
+export const SearchAll: React.FC<Props> = ({ searchAll, matcher, dispatch }) => {
+    const { homeDir } = useContext(ConfigContext);
+    const debId = useRef<number | null>(null);
+    const { files, error } = searchAll;
+
+    const items = useMemo(() => {
+        if (error !== null) {
+            return [{ text: error, path: null, hit: null }];
+        }
+        const items: SearchAllItem[] = [];
+        for (const { path, hits } of files) {
+            const text = displayPath(path, homeDir);
+            for (const hit of hits) {
+                items.push({ text, path, hit });
+            }
+        }
+        return items;
+    }, [files, error, homeDir]);
+
+    useEffect(
+        () => () => {
+            if (debId.current !== null) {
+                window.clearTimeout(debId.current);
+            }
+        },
+        [],
+    );
+
+    const handleInput = useCallback(
+        (query: string) => {
+            if (debId.current !== null) {
+                window.clearTimeout(debId.current);
+            }
+            debId.current = window.setTimeout(() => {
+                sendMessage({ kind: 'search_all', query, matcher });
+                debId.current = null;
+            }, DEBOUNCE_TIMEOUT);
+        },
+        [matcher],
+    );
+
+    const handleClose = useCallback(() => {
+        dispatch(closeSearchAll());
+    }, [dispatch]);
+
+    const handleSelect = useCallback(
+        ({ path, hit }: SearchAllItem, shiftKey: boolean) => {
+            if (path === null || hit === null) {
+                return;
+            }
+            if (shiftKey) {
+                sendMessage({ kind: 'new_window', path });
+            } else {
+                log.debug('Opening file via search all:', path, hit.line);
+                sendMessage({ kind: 'open_file', path, line: hit.line });
+            }
+            dispatch(closeSearchAll());
+        },
+        [dispatch],
+    );
+
+    return (
+        <Palette
+            items={items}
+            placeholder="Search all files…"
+            onClose={handleClose}
+            onSelect={handleSelect}
+            renderItem={renderItem}
+            onInput={handleInput}
+        />
+    );
+};
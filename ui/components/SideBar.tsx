@@ -12,6 +12,7 @@ import Box from '@mui/material/Box';
 import Typography from '@mui/material/Typography';
 import { ConfigContext } from './ConfigContext';
 import { MenuButton } from './MenuButton';
+import { TabList } from './TabList';
 import type { Heading } from '../reducer';
 import { sendMessage } from '../ipc';
 import { fileName } from '../path';
@@ -92,9 +93,11 @@ const LIST_SX = {
 interface Props {
     headings: Heading[];
     path: string | null;
+    tabs: (string | null)[];
+    currentTab: number;
 }
 
-export const SideBar: React.FC<Props> = ({ headings, path }) => {
+export const SideBar: React.FC<Props> = ({ headings, path, tabs, currentTab }) => {
     const { hideScrollBar } = useContext(ConfigContext);
 
     const focusedRef = useRef<HTMLLIElement>(null);
@@ -158,6 +161,12 @@ export const SideBar: React.FC<Props> = ({ headings, path }) => {
         <>
             <ListHeader path={path} />
             <Divider />
+            {tabs.length > 1 && (
+                <>
+                    <TabList tabs={tabs} current={currentTab} path={path} />
+                    <Divider />
+                </>
+            )}
             <List className={className} sx={LIST_SX} ref={listRef}>
                 {children}
             </List>
@@ -0,0 +1,31 @@
+import * as React from 'react';
+import { useEffect, useRef } from 'react';
+import type { Source } from '../reducer';
+import { registerSource, setActivePane } from '../split';
+
+function onMouseEnter(): void {
+    setActivePane('source');
+}
+
+export interface Props {
+    source: Source;
+}
+
+export const SourceView: React.FC<Props> = ({ source }) => {
+    const ref = useRef<HTMLPreElement>(null);
+
+    useEffect(() => {
+        const elem = ref.current;
+        if (elem === null) {
+            return undefined;
+        }
+        return registerSource(elem, source.blocks);
+    }, [source]);
+
+    const html = { __html: source.html }; // eslint-disable-line @typescript-eslint/naming-convention
+    return (
+        <pre className="source-view" ref={ref} onMouseEnter={onMouseEnter}>
+            <code className="hljs language-markdown" dangerouslySetInnerHTML={html} />
+        </pre>
+    );
+};
@@ -0,0 +1,61 @@
+import * as React from 'react';
+import List from '@mui/material/List';
+import ListItem from '@mui/material/ListItem';
+import ListItemButton from '@mui/material/ListItemButton';
+import ListItemText from '@mui/material/ListItemText';
+import IconButton from '@mui/material/IconButton';
+import CloseIcon from '@mui/icons-material/Close';
+import { sendMessage } from '../ipc';
+import { fileName } from '../path';
+
+const LIST_SX = {
+    flexShrink: 0,
+    maxHeight: '30%',
+    overflowY: 'auto',
+    fontSize: '0.875rem',
+};
+const TEXT_SX = {
+    overflowX: 'hidden',
+    textOverflow: 'ellipsis',
+    whiteSpace: 'nowrap',
+};
+
+export interface Props {
+    tabs: (string | null)[];
+    current: number;
+    path: string | null; // The current tab may navigate to another file after the tabs were sent
+}
+
+export const TabList: React.FC<Props> = ({ tabs, current, path }) => {
+    const items = tabs.map((tab, index) => {
+        const selected = index === current;
+        const label = fileName(selected ? path : tab) || 'New Tab';
+        const handleClose = (e: React.MouseEvent): void => {
+            e.stopPropagation(); // Do not select the tab
+            sendMessage({ kind: 'close_tab', index });
+        };
+        const closeButton = (
+            <IconButton edge="end" size="small" title="Close tab" aria-label="close tab" onClick={handleClose}>
+                <CloseIcon fontSize="small" />
+            </IconButton>
+        );
+        return (
+            <ListItem disablePadding secondaryAction={closeButton} key={index}>
+                <ListItemButton
+                    selected={selected}
+                    dense
+                    onClick={() => {
+                        sendMessage({ kind: 'select_tab', index });
+                    }}
+                >
+                    <ListItemText primary={label} disableTypography sx={TEXT_SX} />
+                </ListItemButton>
+            </ListItem>
+        );
+    });
+    return (
+        <List dense sx={LIST_SX}>
+            {items}
+        </List>
+    );
+};
@@ -36,6 +36,16 @@ class ColorScheme {
 
 export const colorScheme = new ColorScheme();
 
+// Load the stylesheets again since they may be changed by reloading the config
+export function reloadStyleSheets(): void {
+    for (const link of document.querySelectorAll<HTMLLinkElement>('link[rel="stylesheet"]')) {
+        const url = new URL(link.href);
+        url.searchParams.set('t', Date.now().toString());
+        link.href = url.toString();
+    }
+    log.debug('Reloaded stylesheets');
+}
+
 export function parseColor(color: string): [number, number, number] | null {
     if (!color.startsWith('#')) {
         return null;
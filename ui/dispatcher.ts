@@ -2,24 +2,41 @@ import {
     type Dispatch,
     type State,
     type Action,
+    type BookmarkTarget,
     INITIAL_STATE,
     initialize,
     notifyAlwaysOnTop,
     notifyReload,
     notifyZoom,
+    openBookmarkInput,
+    openBookmarks,
     openHelp,
+    openSplitView,
+    closeSplitView,
     openHistory,
     openOutline,
+    openReplacePreview,
+    closeReplacePreview,
     openSearch,
+    openSearchAll,
     previewContent,
+    scrollPreview,
+    searchAllResult,
     searchNext,
     setPath,
+    setMetadata,
+    setTabs,
+    setSource,
+    setSearchMatcher,
     searchPrevious,
     welcome,
 } from './reducer';
-import type { MessageFromMain, InitScroll } from './ipc';
+import hljs from 'highlight.js';
+import { type MessageFromMain, type InitScroll, sendMessage } from './ipc';
 import { ReactMarkdownRenderer } from './markdown';
 import { KeyMapping } from './keymaps';
+import { reloadStyleSheets } from './css';
+import { fileName } from './path';
 import * as log from './log';
 
 // Global action dispatcher to handle IPC messages from the main and key shortcuts
@@ -30,6 +47,7 @@ export class GlobalDispatcher {
     public readonly keymap: KeyMapping;
     public readonly markdown: ReactMarkdownRenderer;
     private initScroll: InitScroll | null;
+    private rendering: number;
 
     constructor() {
         this.dispatch = (action: Action) => {
@@ -39,6 +57,7 @@ export class GlobalDispatcher {
         this.keymap = new KeyMapping();
         this.markdown = new ReactMarkdownRenderer();
         this.initScroll = null;
+        this.rendering = 0;
     }
 
     setDispatch(dispatch: Dispatch, state: State): void {
@@ -46,8 +65,8 @@ export class GlobalDispatcher {
         this.state = state;
     }
 
-    openSearch(): void {
-        this.dispatch(openSearch());
+    openSearch(query: string | null = null): void {
+        this.dispatch(openSearch(query));
     }
 
     searchNext(): void {
@@ -64,22 +83,58 @@ export class GlobalDispatcher {
         }
     }
 
+    // Open the input of the bookmark label for the heading at the current scroll position
+    bookmark(): void {
+        const { path, headings, welcome } = this.state;
+        if (welcome || path === null) {
+            return;
+        }
+        const index = headings.findIndex(h => h.current);
+        let target: BookmarkTarget = { label: fileName(path) };
+        if (index >= 0) {
+            const { text, elem } = headings[index];
+            target = { label: text, heading: index };
+            if (elem.id) {
+                target.fragment = elem.id;
+            }
+        }
+        this.dispatch(openBookmarkInput(target));
+    }
+
+    toggleSplitView(): void {
+        if (this.state.split) {
+            this.dispatch(closeSplitView());
+        } else {
+            this.dispatch(openSplitView());
+            sendMessage({ kind: 'source' });
+        }
+    }
+
     async handleIpcMessage(msg: MessageFromMain): Promise<void> {
         log.debug('Received IPC message from main:', msg.kind, msg);
         // This method must not throw exception since the main process call this method like `window.postShibaMessageFromMain(msg)`.
         try {
             switch (msg.kind) {
                 case 'render_tree': {
-                    const tree = await this.markdown.render(msg.tree);
+                    this.rendering++;
+                    const tree = await this.markdown.render(msg.tree).finally(() => {
+                        this.rendering--;
+                    });
                     tree.scroll = this.initScroll;
                     this.initScroll = null;
                     this.dispatch(previewContent(tree));
+                    if (this.state.split) {
+                        sendMessage({ kind: 'source' }); // Keep the source in split view up-to-date
+                    }
                     break;
                 }
                 case 'path': {
                     this.dispatch(setPath(msg.path));
                     break;
                 }
+                case 'metadata':
+                    this.dispatch(setMetadata(msg.metadata));
+                    break;
                 case 'config':
                     this.keymap.register(msg.keymaps, this);
                     this.dispatch(
@@ -96,7 +151,10 @@ export class GlobalDispatcher {
                     );
                     break;
                 case 'search':
-                    this.openSearch();
+                    if (msg.matcher !== undefined) {
+                        this.dispatch(setSearchMatcher(msg.matcher));
+                    }
+                    this.openSearch(msg.query ?? null);
                     break;
                 case 'search_next':
                     this.searchNext();
@@ -104,12 +162,43 @@ export class GlobalDispatcher {
                 case 'search_previous':
                     this.searchPrev();
                     break;
+                case 'search_all':
+                    this.dispatch(openSearchAll());
+                    break;
+                case 'search_all_result':
+                    this.dispatch(searchAllResult(msg.query, msg.files, msg.error ?? null));
+                    break;
+                case 'replace_preview': {
+                    const { path, count, skipped, hunks } = msg;
+                    this.dispatch(openReplacePreview({ path, count, skipped, hunks }));
+                    break;
+                }
+                case 'replace_end':
+                    this.dispatch(closeReplacePreview());
+                    break;
                 case 'outline':
                     this.dispatch(openOutline());
                     break;
                 case 'history':
                     this.dispatch(openHistory(msg.paths));
                     break;
+                case 'bookmark':
+                    this.bookmark();
+                    break;
+                case 'bookmarks':
+                    this.dispatch(openBookmarks(msg.bookmarks));
+                    break;
+                case 'tabs':
+                    this.dispatch(setTabs(msg.paths, msg.current));
+                    break;
+                case 'toggle_split_view':
+                    this.toggleSplitView();
+                    break;
+                case 'source': {
+                    const { value } = hljs.highlight(msg.source, { language: 'markdown' });
+                    this.dispatch(setSource({ html: value, blocks: msg.blocks }));
+                    break;
+                }
                 case 'welcome':
                     this.dispatch(welcome());
                     break;
@@ -122,6 +211,9 @@ export class GlobalDispatcher {
                 case 'reload':
                     this.dispatch(notifyReload());
                     break;
+                case 'reload_styles':
+                    reloadStyleSheets();
+                    break;
                 case 'always_on_top':
                     this.dispatch(notifyAlwaysOnTop(msg.pinned));
                     break;
@@ -130,7 +222,13 @@ export class GlobalDispatcher {
                     log.debug('Debug log is enabled');
                     break;
                 case 'scroll':
-                    this.initScroll = msg.scroll;
+                    // Scrolling is requested after the content was sent. When no content is being rendered, the content
+                    // in the preview is not changed so scroll it immediately.
+                    if (this.rendering === 0) {
+                        this.dispatch(scrollPreview(msg.scroll));
+                    } else {
+                        this.initScroll = msg.scroll;
+                    }
                     break;
                 default:
                     log.error('Unknown message:', msg);
@@ -27,10 +27,14 @@ export type KeyAction =
     | 'Search'
     | 'SearchNext'
     | 'SearchPrev'
+    | 'SearchAll'
     | 'ScrollNextSection'
     | 'ScrollPrevSection'
     | 'Outline'
+    | 'ToggleSplitView'
     | 'History'
+    | 'Bookmark'
+    | 'Bookmarks'
     | 'Help'
     | 'ZoomIn'
     | 'ZoomOut'
@@ -43,11 +47,22 @@ export type KeyAction =
     | 'DuplicateWindow'
     | 'CloseWindow'
     | 'CloseAllOtherWindows'
+    | 'NewTab'
+    | 'NextTab'
+    | 'PreviousTab'
+    | 'CloseTab'
     | 'EditConfig'
     | 'Quit';
 
 export type KeyMaps = Record<string, KeyAction>;
-export type SearchMatcher = 'SmartCase' | 'CaseSensitive' | 'CaseInsensitive' | 'CaseSensitiveRegex';
+export type SearchMatcher =
+    | 'SmartCase'
+    | 'CaseSensitive'
+    | 'CaseInsensitive'
+    | 'WholeWord'
+    | 'Fuzzy'
+    | 'CaseSensitiveRegex'
+    | 'CaseInsensitiveRegex';
 export type Platform = 'Mac' | 'Windows' | 'Linux';
 export type RenderTreeTableAlign = 'left' | 'center' | 'right' | null;
 export type AlertKind = 'warning' | 'important' | 'caution' | 'note' | 'tip';
@@ -62,11 +77,13 @@ export interface RenderTreeCodeFence {
     lang?: string;
     c: RenderTreeElem[];
 }
-// Note: 't' is abbreviation of 'tag', 'c' is abbreviation of 'children' for saving spaces
+// Note: 't' is abbreviation of 'tag', 'c' is abbreviation of 'children' for saving spaces. 'o' is abbreviation of
+// 'offset' which is the byte offset of the block element in the Markdown source
 export type RenderTreeElem =
     | string // Text node
     | {
           t: 'html';
+          o?: number; // Only HTML blocks have the offset
           raw: string;
       }
     | {
@@ -74,6 +91,7 @@ export type RenderTreeElem =
       }
     | {
           t: 'hr';
+          o: number;
       }
     | {
           t: 'fn-ref';
@@ -85,16 +103,20 @@ export type RenderTreeElem =
       }
     | {
           t: 'p';
+          o: number;
           c: RenderTreeElem[];
       }
     | {
           t: 'h';
+          o: number;
           level: number;
-          id?: string;
+          id: string;
+          class?: string;
           c: RenderTreeElem[];
       }
     | {
           t: 'table';
+          o: number;
           align: RenderTreeTableAlign[];
           c: RenderTreeElem[];
       }
@@ -108,6 +130,7 @@ export type RenderTreeElem =
       }
     | {
           t: 'tr';
+          o: number;
           c: RenderTreeElem[];
       }
     | {
@@ -120,28 +143,34 @@ export type RenderTreeElem =
       }
     | {
           t: 'blockquote';
+          o: number;
           c: RenderTreeElem[];
       }
     | {
           t: 'pre';
+          o: number;
           c: RenderTreeElem[];
       }
     | RenderTreeCodeFence
     | {
           t: 'ol';
+          o: number;
           start?: number;
           c: RenderTreeElem[];
       }
     | {
           t: 'ul';
+          o: number;
           c: RenderTreeElem[];
       }
     | {
           t: 'li';
+          o: number;
           c: RenderTreeElem[];
       }
     | {
           t: 'task-list';
+          o: number;
           c: RenderTreeElem[];
       }
     | {
@@ -156,6 +185,29 @@ export type RenderTreeElem =
           t: 'del';
           c: RenderTreeElem[];
       }
+    | {
+          t: 'sup';
+          c: RenderTreeElem[];
+      }
+    | {
+          t: 'sub';
+          c: RenderTreeElem[];
+      }
+    | {
+          t: 'dl';
+          o: number;
+          c: RenderTreeElem[];
+      }
+    | {
+          t: 'dt';
+          o: number;
+          c: RenderTreeElem[];
+      }
+    | {
+          t: 'dd';
+          o: number;
+          c: RenderTreeElem[];
+      }
     | {
           t: 'a';
           href: string;
@@ -163,6 +215,24 @@ export type RenderTreeElem =
           auto?: boolean; // Autolink
           c: RenderTreeElem[];
       }
+    | {
+          t: 'diagram'; // Diagram rendered by the local command
+          o: number;
+          lang: string;
+          src: string;
+      }
+    | {
+          t: 'diagram-error'; // Error while rendering diagram. Children are the source of the diagram
+          o: number;
+          lang: string;
+          message: string;
+          c: RenderTreeElem[];
+      }
+    | {
+          t: 'missing-page'; // Wikilink to the page which does not exist
+          page: string;
+          c: RenderTreeElem[];
+      }
     | {
           t: 'img';
           src: string;
@@ -182,6 +252,7 @@ export type RenderTreeElem =
       }
     | {
           t: 'alert';
+          o: number;
           kind: AlertKind;
           c: RenderTreeElem[];
       }
@@ -204,7 +275,31 @@ export type RenderTreeElem =
           t: 'match-current-start'; // First current text search match token
           c: RenderTreeElem[];
       };
-export type InitScroll = { fragment: string } | { heading: number };
+export type InitScroll = { fragment: string } | { heading: number } | { block: number } | { blockTop: number };
+export interface SearchAllHit {
+    line: number;
+    before: string;
+    matched: string;
+    after: string;
+    headings: string[];
+}
+export interface SearchAllFile {
+    path: string;
+    hits: SearchAllHit[];
+    total: number;
+}
+export interface ReplacedHunk {
+    line: number;
+    old: string;
+    new: string;
+}
+export interface Bookmark {
+    label: string;
+    path: string;
+    heading?: number;
+    fragment?: string;
+}
+export type Metadata = Record<string, unknown>;
 
 export type MessageFromMain =
     | {
@@ -215,6 +310,10 @@ export type MessageFromMain =
           kind: 'path';
           path: string;
       }
+    | {
+          kind: 'metadata';
+          metadata: Metadata | null;
+      }
     | {
           kind: 'config';
           keymaps: KeyMaps;
@@ -231,6 +330,8 @@ export type MessageFromMain =
       }
     | {
           kind: 'search';
+          query?: string;
+          matcher?: SearchMatcher;
       }
     | {
           kind: 'search_next';
@@ -238,6 +339,25 @@ export type MessageFromMain =
     | {
           kind: 'search_previous';
       }
+    | {
+          kind: 'search_all';
+      }
+    | {
+          kind: 'search_all_result';
+          query: string;
+          files: SearchAllFile[];
+          error?: string;
+      }
+    | {
+          kind: 'replace_preview';
+          path: string;
+          count: number;
+          skipped: number;
+          hunks: ReplacedHunk[];
+      }
+    | {
+          kind: 'replace_end';
+      }
     | {
           kind: 'outline';
       }
@@ -248,6 +368,26 @@ export type MessageFromMain =
           kind: 'history';
           paths: string[];
       }
+    | {
+          kind: 'bookmark';
+      }
+    | {
+          kind: 'bookmarks';
+          bookmarks: Bookmark[];
+      }
+    | {
+          kind: 'tabs';
+          paths: (string | null)[]; // null means an empty tab
+          current: number;
+      }
+    | {
+          kind: 'toggle_split_view';
+      }
+    | {
+          kind: 'source';
+          source: string;
+          blocks: [number, number][]; // 1-based start and end lines of the top-level blocks
+      }
     | {
           kind: 'help';
       }
@@ -258,6 +398,9 @@ export type MessageFromMain =
     | {
           kind: 'reload';
       }
+    | {
+          kind: 'reload_styles';
+      }
     | {
           kind: 'always_on_top';
           pinned: boolean;
@@ -289,6 +432,24 @@ export type MessageToMain =
     | {
           kind: 'history';
       }
+    | {
+          kind: 'bookmarks';
+      }
+    | {
+          kind: 'add_bookmark';
+          label: string;
+          heading?: number;
+          fragment?: string;
+      }
+    | {
+          kind: 'open_bookmark';
+          index: number;
+          new_window: boolean;
+      }
+    | {
+          kind: 'remove_bookmark';
+          index: number;
+      }
     | {
           kind: 'reload';
       }
@@ -307,9 +468,21 @@ export type MessageToMain =
           index: number | null;
           matcher: SearchMatcher;
       }
+    | {
+          kind: 'search_all';
+          query: string;
+          matcher: SearchMatcher;
+      }
+    | {
+          kind: 'replace';
+          query: string;
+          matcher: SearchMatcher;
+          replacement: string;
+      }
     | {
           kind: 'open_file';
           path: string;
+          line?: number;
       }
     | {
           kind: 'zoom_in';
@@ -350,9 +523,37 @@ export type MessageToMain =
     | {
           kind: 'close_all_other_windows';
       }
+    | {
+          kind: 'new_tab';
+      }
+    | {
+          kind: 'next_tab';
+      }
+    | {
+          kind: 'previous_tab';
+      }
+    | {
+          kind: 'select_tab';
+          index: number;
+      }
+    | {
+          kind: 'close_tab';
+          index?: number;
+      }
     | {
           kind: 'edit_config';
       }
+    | {
+          kind: 'open_source';
+          offset: number;
+      }
+    | {
+          kind: 'source';
+      }
+    | {
+          kind: 'scroll_position';
+          block: number;
+      }
     | {
           kind: 'error';
           message: string;
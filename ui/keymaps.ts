@@ -1,5 +1,5 @@
-import { bind as bindKey } from 'mousetrap';
-import { openOutline, openHelp } from './reducer';
+import { bind as bindKey, reset as resetKeys } from 'mousetrap';
+import { openOutline, openHelp, openSearchAll } from './reducer';
 import type { GlobalDispatcher } from './dispatcher';
 import { sendMessage, type KeyMaps, type KeyAction } from './ipc';
 import * as log from './log';
@@ -199,6 +199,13 @@ const KeyShortcuts: Record<KeyAction, KeyShortcut> = {
         },
     },
 
+    SearchAll: {
+        description: 'Open a palette to search text in all Markdown files under the watched directories.',
+        dispatch(dispatcher: GlobalDispatcher): void {
+            dispatcher.dispatch(openSearchAll());
+        },
+    },
+
     Outline: {
         description: 'Open a palette to incrementally search the section outline.',
         dispatch(dispatcher: GlobalDispatcher): void {
@@ -206,6 +213,13 @@ const KeyShortcuts: Record<KeyAction, KeyShortcut> = {
         },
     },
 
+    ToggleSplitView: {
+        description: 'Show/Hide the Markdown source side by side with the preview.',
+        dispatch(dispatcher: GlobalDispatcher): void {
+            dispatcher.toggleSplitView();
+        },
+    },
+
     History: {
         description: 'Open a palette to incrementally search files in history.',
         dispatch(): void {
@@ -213,6 +227,20 @@ const KeyShortcuts: Record<KeyAction, KeyShortcut> = {
         },
     },
 
+    Bookmark: {
+        description: 'Bookmark the current file or the heading at the current scroll position with a label.',
+        dispatch(dispatcher: GlobalDispatcher): void {
+            dispatcher.bookmark();
+        },
+    },
+
+    Bookmarks: {
+        description: 'Open a palette to incrementally search bookmarks.',
+        dispatch(): void {
+            sendMessage({ kind: 'bookmarks' });
+        },
+    },
+
     Help: {
         description: 'Show this help.',
         dispatch(dispatcher: GlobalDispatcher): void {
@@ -302,6 +330,34 @@ const KeyShortcuts: Record<KeyAction, KeyShortcut> = {
         },
     },
 
+    NewTab: {
+        description: 'Open a new tab in the current window.',
+        dispatch(): void {
+            sendMessage({ kind: 'new_tab' });
+        },
+    },
+
+    NextTab: {
+        description: 'Switch to the next tab.',
+        dispatch(): void {
+            sendMessage({ kind: 'next_tab' });
+        },
+    },
+
+    PreviousTab: {
+        description: 'Switch to the previous tab.',
+        dispatch(): void {
+            sendMessage({ kind: 'previous_tab' });
+        },
+    },
+
+    CloseTab: {
+        description: 'Close the current tab. Closing the last tab closes the window.',
+        dispatch(): void {
+            sendMessage({ kind: 'close_tab' });
+        },
+    },
+
     EditConfig: {
         description: 'Edit configuration with a default text editor.',
         dispatch(): void {
@@ -330,6 +386,7 @@ export class KeyMapping {
     }
 
     register(maps: KeyMaps, dispatcher: GlobalDispatcher): void {
+        resetKeys(); // Remove the previous key mappings on reloading the config
         const bounds = new Map<KeyAction, BoundShortcut>();
 
         for (const keybind of Object.keys(maps)) {
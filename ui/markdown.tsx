@@ -344,12 +344,16 @@ class RenderTreeToReact {
 
         switch (elem.t) {
             case 'p':
-                return <p key={key}>{await this.renderAll(elem.c)}</p>;
+                return <p key={key} data-offset={elem.o}>{await this.renderAll(elem.c)}</p>;
             case 'h': {
                 const tag = `h${elem.level}`;
-                const props: React.JSX.IntrinsicElements['h1'] = { key };
-                if (elem.id) {
-                    props.id = elem.id; // TODO?: Clobber IDs
+                const props: React.JSX.IntrinsicElements['h1'] & { 'data-offset': number } = {
+                    key,
+                    id: elem.id,
+                    'data-offset': elem.o,
+                };
+                if (elem.class) {
+                    props.className = elem.class;
                 }
                 const children = await this.renderAll(elem.c);
                 return React.createElement(tag, props, ...children);
@@ -373,21 +377,37 @@ class RenderTreeToReact {
                         </a>
                     );
                 }
+            case 'missing-page':
+                return (
+                    <span key={key} className="missing-page" title={`Page not found: ${elem.page}`}>
+                        {await this.renderAll(elem.c)}
+                    </span>
+                );
             case 'img': {
                 return <img key={key} src={elem.src} alt={rawText(elem)} title={elem.title} />;
             }
             case 'br':
                 return <br key={key} />;
             case 'blockquote':
-                return <blockquote key={key}>{await this.renderAll(elem.c)}</blockquote>;
+                return <blockquote key={key} data-offset={elem.o}>{await this.renderAll(elem.c)}</blockquote>;
             case 'em':
                 return <em key={key}>{await this.renderAll(elem.c)}</em>;
             case 'strong':
                 return <strong key={key}>{await this.renderAll(elem.c)}</strong>;
             case 'del':
                 return <del key={key}>{await this.renderAll(elem.c)}</del>;
+            case 'sup':
+                return <sup key={key}>{await this.renderAll(elem.c)}</sup>;
+            case 'sub':
+                return <sub key={key}>{await this.renderAll(elem.c)}</sub>;
+            case 'dl':
+                return <dl key={key} data-offset={elem.o}>{await this.renderAll(elem.c)}</dl>;
+            case 'dt':
+                return <dt key={key} data-offset={elem.o}>{await this.renderAll(elem.c)}</dt>;
+            case 'dd':
+                return <dd key={key} data-offset={elem.o}>{await this.renderAll(elem.c)}</dd>;
             case 'pre':
-                return <pre key={key}>{await this.renderAll(elem.c)}</pre>;
+                return <pre key={key} data-offset={elem.o}>{await this.renderAll(elem.c)}</pre>;
             case 'code': {
                 const rendered = await this.fence.render(elem, key);
                 if (rendered === null) {
@@ -404,19 +424,34 @@ class RenderTreeToReact {
                     </React.Fragment>
                 );
             }
+            case 'diagram':
+                return (
+                    <p key={key} data-offset={elem.o}>
+                        <img className="diagram" alt={`${elem.lang} diagram`} src={elem.src} />
+                    </p>
+                );
+            case 'diagram-error':
+                return (
+                    <div key={key} className="diagram-error" data-offset={elem.o}>
+                        <p>{elem.message}</p>
+                        <pre>
+                            <code className={`language-${elem.lang}`}>{await this.renderAll(elem.c)}</code>
+                        </pre>
+                    </div>
+                );
             case 'ol':
                 return (
-                    <ol key={key} start={elem.start}>
+                    <ol key={key} start={elem.start} data-offset={elem.o}>
                         {await this.renderAll(elem.c)}
                     </ol>
                 );
             case 'ul':
-                return <ul key={key}>{await this.renderAll(elem.c)}</ul>;
+                return <ul key={key} data-offset={elem.o}>{await this.renderAll(elem.c)}</ul>;
             case 'li':
-                return <li key={key}>{await this.renderAll(elem.c)}</li>;
+                return <li key={key} data-offset={elem.o}>{await this.renderAll(elem.c)}</li>;
             case 'task-list':
                 return (
-                    <li key={key} className="task-list-item">
+                    <li key={key} className="task-list-item" data-offset={elem.o}>
                         {await this.renderAll(elem.c)}
                     </li>
                 );
@@ -431,7 +466,7 @@ class RenderTreeToReact {
                     aligns: elem.align,
                     index: 0,
                 };
-                return <table key={key}>{await this.renderAll(elem.c)}</table>;
+                return <table key={key} data-offset={elem.o}>{await this.renderAll(elem.c)}</table>;
             case 'thead':
                 return <thead key={key}>{await this.renderAll(elem.c)}</thead>;
             case 'tbody':
@@ -440,7 +475,7 @@ class RenderTreeToReact {
                 if (this.table) {
                     this.table.index = 0;
                 }
-                return <tr key={key}>{await this.renderAll(elem.c)}</tr>;
+                return <tr key={key} data-offset={elem.o}>{await this.renderAll(elem.c)}</tr>;
             case 'th':
                 if (this.table) {
                     const style = tableAlignStyle(this.table);
@@ -479,7 +514,7 @@ class RenderTreeToReact {
                 );
             }
             case 'hr':
-                return <hr key={key} />;
+                return <hr key={key} data-offset={elem.o} />;
             case 'fn-ref':
                 return (
                     <sup key={key}>
@@ -503,7 +538,7 @@ class RenderTreeToReact {
                 const className = `markdown-alert markdown-alert-${elem.kind}`;
                 const title = elem.kind.charAt(0).toUpperCase() + elem.kind.slice(1);
                 return (
-                    <div className={className} key={key}>
+                    <div className={className} key={key} data-offset={elem.o}>
                         <p className="markdown-alert-title">
                             {alertIcon(elem.kind)}
                             {title}
@@ -520,7 +555,7 @@ class RenderTreeToReact {
                 // XXX: This <span> element is necessary because React cannot render inner HTML under fragment
                 // https://github.com/reactjs/rfcs/pull/129
                 // XXX: Relative paths don't work because they need to be adjusted with base directory path
-                return <span key={key} dangerouslySetInnerHTML={{ __html: elem.raw }} />; // eslint-disable-line @typescript-eslint/naming-convention
+                return <span key={key} data-offset={elem.o} dangerouslySetInnerHTML={{ __html: elem.raw }} />; // eslint-disable-line @typescript-eslint/naming-convention
             case 'modified':
                 return this.lastModified(key);
             case 'match':
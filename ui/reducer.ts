@@ -1,5 +1,5 @@
 import * as log from './log';
-import type { SearchMatcher } from './ipc';
+import type { SearchMatcher, Metadata, InitScroll, SearchAllFile, ReplacedHunk, Bookmark } from './ipc';
 import { searchNextIndex, searchPreviousIndex } from './search';
 import type { MarkdownReactTree } from './markdown';
 import { displayPath } from './path';
@@ -26,6 +26,32 @@ export interface Heading {
     current?: boolean;
 }
 
+export interface SearchAll {
+    query: string;
+    files: SearchAllFile[];
+    error: string | null;
+}
+
+export interface ReplacePreview {
+    path: string;
+    count: number;
+    skipped: number;
+    hunks: ReplacedHunk[];
+}
+
+// Markdown source shown in split view. `blocks` are the line ranges of the top-level blocks in the preview
+export interface Source {
+    html: string;
+    blocks: [number, number][];
+}
+
+// Position to be bookmarked with the label input by user
+export interface BookmarkTarget {
+    label: string;
+    heading?: number;
+    fragment?: string;
+}
+
 export interface Config {
     titleBar: boolean;
     vibrant: boolean;
@@ -37,12 +63,22 @@ export interface Config {
 export interface State {
     previewTree: MarkdownReactTree;
     path: string | null;
+    metadata: Metadata | null;
     searching: boolean;
+    searchQuery: string | null;
     searchIndex: number | null;
     matcher: SearchMatcher;
+    searchAll: SearchAll | null;
+    replacePreview: ReplacePreview | null;
     outline: boolean;
     config: Config;
     history: string[];
+    bookmarks: Bookmark[];
+    bookmarking: BookmarkTarget | null;
+    tabs: (string | null)[];
+    currentTab: number;
+    split: boolean;
+    source: Source | null;
     help: boolean;
     notifying: boolean;
     notification: NotificationContent;
@@ -66,12 +102,22 @@ export const INITIAL_STATE: State = {
         scroll: null,
     },
     path: null,
+    metadata: null,
     searching: false,
+    searchQuery: null,
     searchIndex: null,
     matcher: 'SmartCase',
+    searchAll: null,
+    replacePreview: null,
     outline: false,
     config: INITIAL_CONFIG,
     history: [],
+    bookmarks: [],
+    bookmarking: null,
+    tabs: [],
+    currentTab: 0,
+    split: false,
+    source: null,
     help: false,
     notifying: false,
     notification: { kind: 'reload' },
@@ -84,12 +130,21 @@ export type Action =
           kind: 'preview_content';
           tree: MarkdownReactTree;
       }
+    | {
+          kind: 'scroll_preview';
+          scroll: InitScroll;
+      }
     | {
           kind: 'set_path';
           path: string;
       }
+    | {
+          kind: 'metadata';
+          metadata: Metadata | null;
+      }
     | {
           kind: 'open_search';
+          query: string | null;
       }
     | {
           kind: 'close_search';
@@ -102,6 +157,20 @@ export type Action =
           kind: 'search_matcher';
           matcher: SearchMatcher;
       }
+    | {
+          kind: 'search_all';
+          open: boolean;
+      }
+    | {
+          kind: 'search_all_result';
+          query: string;
+          files: SearchAllFile[];
+          error: string | null;
+      }
+    | {
+          kind: 'replace_preview';
+          preview: ReplacePreview | null;
+      }
     | {
           kind: 'outline';
           open: boolean;
@@ -110,6 +179,27 @@ export type Action =
           kind: 'history';
           paths: string[];
       }
+    | {
+          kind: 'bookmarks';
+          bookmarks: Bookmark[];
+      }
+    | {
+          kind: 'bookmarking';
+          target: BookmarkTarget | null;
+      }
+    | {
+          kind: 'tabs';
+          paths: (string | null)[];
+          current: number;
+      }
+    | {
+          kind: 'split_view';
+          open: boolean;
+      }
+    | {
+          kind: 'source';
+          source: Source;
+      }
     | {
           kind: 'help';
           open: boolean;
@@ -137,15 +227,29 @@ export function reducer(state: State, action: Action): State {
     switch (action.kind) {
         case 'preview_content':
             return { ...state, previewTree: action.tree, welcome: false };
+        case 'scroll_preview':
+            return { ...state, previewTree: { ...state.previewTree, lastModified: null, scroll: action.scroll } };
         case 'set_path':
             return { ...state, path: displayPath(action.path, state.config.homeDir) };
+        case 'metadata':
+            return { ...state, metadata: action.metadata };
         case 'headings':
             return { ...state, headings: action.headings };
         case 'open_search':
-            if (state.searching) {
+            if (state.searching && action.query === null) {
                 return state;
             }
-            return { ...state, searching: true, searchIndex: null, outline: false, history: [], help: false };
+            return {
+                ...state,
+                searching: true,
+                searchQuery: action.query,
+                searchIndex: null,
+                searchAll: null,
+                outline: false,
+                history: [],
+                bookmarks: [],
+                help: false,
+            };
         case 'close_search':
             return { ...state, searching: false, searchIndex: null };
         case 'search_index':
@@ -158,12 +262,82 @@ export function reducer(state: State, action: Action): State {
             };
         case 'search_matcher':
             return { ...state, matcher: action.matcher };
+        case 'search_all':
+            if (!action.open) {
+                return { ...state, searchAll: null };
+            }
+            if (state.searchAll !== null) {
+                return state;
+            }
+            return {
+                ...state,
+                searchAll: { query: '', files: [], error: null },
+                searching: false,
+                outline: false,
+                history: [],
+                bookmarks: [],
+                help: false,
+            };
+        case 'search_all_result': {
+            const { query, files, error } = action;
+            if (state.searchAll === null) {
+                return state;
+            }
+            return { ...state, searchAll: { query, files, error } };
+        }
+        case 'replace_preview':
+            return { ...state, replacePreview: action.preview };
         case 'outline':
-            return { ...state, outline: action.open, searching: false, history: [], help: false };
+            return {
+                ...state,
+                outline: action.open,
+                searching: false,
+                searchAll: null,
+                history: [],
+                bookmarks: [],
+                help: false,
+            };
         case 'history':
-            return { ...state, history: action.paths, searching: false, outline: false, help: false };
+            return {
+                ...state,
+                history: action.paths,
+                searching: false,
+                searchAll: null,
+                outline: false,
+                bookmarks: [],
+                help: false,
+            };
+        case 'bookmarks':
+            return {
+                ...state,
+                bookmarks: action.bookmarks,
+                searching: false,
+                searchAll: null,
+                outline: false,
+                history: [],
+                help: false,
+            };
+        case 'bookmarking':
+            return { ...state, bookmarking: action.target };
+        case 'tabs':
+            return { ...state, tabs: action.paths, currentTab: action.current };
+        case 'split_view':
+            return { ...state, split: action.open, source: action.open ? state.source : null };
+        case 'source':
+            if (!state.split) {
+                return state; // Split view was closed before the source arrived
+            }
+            return { ...state, source: action.source };
         case 'help':
-            return { ...state, help: action.open, searching: false, outline: false, history: [] };
+            return {
+                ...state,
+                help: action.open,
+                searching: false,
+                searchAll: null,
+                outline: false,
+                history: [],
+                bookmarks: [],
+            };
         case 'notification':
             if (action.notification === null) {
                 return { ...state, notifying: false };
@@ -185,12 +359,20 @@ export function previewContent(tree: MarkdownReactTree): Action {
     return { kind: 'preview_content', tree };
 }
 
+export function scrollPreview(scroll: InitScroll): Action {
+    return { kind: 'scroll_preview', scroll };
+}
+
 export function setPath(path: string): Action {
     return { kind: 'set_path', path };
 }
 
-export function openSearch(): Action {
-    return { kind: 'open_search' };
+export function setMetadata(metadata: Metadata | null): Action {
+    return { kind: 'metadata', metadata };
+}
+
+export function openSearch(query: string | null = null): Action {
+    return { kind: 'open_search', query };
 }
 
 export function closeSearch(): Action {
@@ -213,6 +395,26 @@ export function setSearchMatcher(matcher: SearchMatcher): Action {
     return { kind: 'search_matcher', matcher };
 }
 
+export function openSearchAll(): Action {
+    return { kind: 'search_all', open: true };
+}
+
+export function closeSearchAll(): Action {
+    return { kind: 'search_all', open: false };
+}
+
+export function searchAllResult(query: string, files: SearchAllFile[], error: string | null): Action {
+    return { kind: 'search_all_result', query, files, error };
+}
+
+export function openReplacePreview(preview: ReplacePreview): Action {
+    return { kind: 'replace_preview', preview };
+}
+
+export function closeReplacePreview(): Action {
+    return { kind: 'replace_preview', preview: null };
+}
+
 export function openOutline(): Action {
     return { kind: 'outline', open: true };
 }
@@ -229,6 +431,38 @@ export function closeHistory(): Action {
     return { kind: 'history', paths: [] };
 }
 
+export function openBookmarks(bookmarks: Bookmark[]): Action {
+    return { kind: 'bookmarks', bookmarks };
+}
+
+export function closeBookmarks(): Action {
+    return { kind: 'bookmarks', bookmarks: [] };
+}
+
+export function openBookmarkInput(target: BookmarkTarget): Action {
+    return { kind: 'bookmarking', target };
+}
+
+export function closeBookmarkInput(): Action {
+    return { kind: 'bookmarking', target: null };
+}
+
+export function setTabs(paths: (string | null)[], current: number): Action {
+    return { kind: 'tabs', paths, current };
+}
+
+export function openSplitView(): Action {
+    return { kind: 'split_view', open: true };
+}
+
+export function closeSplitView(): Action {
+    return { kind: 'split_view', open: false };
+}
+
+export function setSource(source: Source): Action {
+    return { kind: 'source', source };
+}
+
 export function openHelp(): Action {
     return { kind: 'help', open: true };
 }
@@ -0,0 +1,106 @@
+// Synchronize the scroll positions of the preview and the source in split view. The top-level blocks in the preview
+// are associated with the lines of the source sent from the main. Only the pane under the mouse cursor drives the other
+// pane so that the scroll events do not bounce between them.
+
+export type Pane = 'preview' | 'source';
+
+interface SourcePane {
+    elem: HTMLElement;
+    blocks: [number, number][];
+}
+
+let source: SourcePane | null = null;
+let activePane: Pane = 'preview';
+
+export function setActivePane(pane: Pane): void {
+    activePane = pane;
+}
+
+// Front matter and footnotes are not rendered from the top-level blocks in the source
+export function topLevelBlocks(root: HTMLElement): NodeListOf<HTMLElement> {
+    return root.querySelectorAll<HTMLElement>('article > :not(.front-matter, .footnotes)');
+}
+
+// Index of the top-level block at the top of the viewport
+export function topBlockIndex(root: HTMLElement): number | null {
+    const blocks = topLevelBlocks(root);
+    for (let i = 0; i < blocks.length; i++) {
+        const { offsetTop, offsetHeight } = blocks[i];
+        if (offsetTop + offsetHeight > root.scrollTop) {
+            return i;
+        }
+    }
+    return null;
+}
+
+// Lines are not wrapped in the source pane so each line has the same height
+function sourceLines(elem: HTMLElement): { top: number; height: number } {
+    const code = elem.firstElementChild as HTMLElement | null;
+    const target = code ?? elem;
+    const height = parseFloat(getComputedStyle(target).lineHeight) || 16;
+    return { top: code?.offsetTop ?? 0, height };
+}
+
+function scrollSource(article: HTMLElement, pane: SourcePane): void {
+    const index = topBlockIndex(article);
+    if (index === null || index >= pane.blocks.length) {
+        return;
+    }
+    const block = topLevelBlocks(article).item(index);
+    const [start, end] = pane.blocks[index];
+    const ratio = Math.min(Math.max((article.scrollTop - block.offsetTop) / block.offsetHeight, 0), 1);
+    const line = start + ratio * (end - start + 1);
+    const { top, height } = sourceLines(pane.elem);
+    pane.elem.scrollTop = top + (line - 1) * height;
+}
+
+function scrollPreview(pane: SourcePane): void {
+    const article = document.querySelector<HTMLElement>('article');
+    if (article === null || pane.blocks.length === 0) {
+        return;
+    }
+    const { top, height } = sourceLines(pane.elem);
+    const line = (pane.elem.scrollTop - top) / height + 1;
+    let index = pane.blocks.findIndex(([, end]) => line < end + 1);
+    if (index < 0) {
+        index = pane.blocks.length - 1;
+    }
+    const block = topLevelBlocks(article).item(index);
+    if (!block) {
+        return;
+    }
+    const [start, end] = pane.blocks[index];
+    const ratio = Math.min(Math.max((line - start) / (end - start + 1), 0), 1);
+    article.scrollTop = block.offsetTop + ratio * block.offsetHeight;
+}
+
+// Called when the preview is scrolled
+export function syncSource(article: HTMLElement): void {
+    if (source !== null && activePane === 'preview') {
+        scrollSource(article, source);
+    }
+}
+
+// Register the mounted source pane. The returned function unregisters it.
+export function registerSource(elem: HTMLElement, blocks: [number, number][]): () => void {
+    const pane = { elem, blocks };
+    source = pane;
+
+    const article = document.querySelector<HTMLElement>('article');
+    if (article !== null) {
+        scrollSource(article, pane); // Start from the current position of the preview
+    }
+
+    const onScroll = (): void => {
+        if (activePane === 'source') {
+            scrollPreview(pane);
+        }
+    };
+    elem.addEventListener('scroll', onScroll, { passive: true });
+    return () => {
+        elem.removeEventListener('scroll', onScroll);
+        if (source === pane) {
+            source = null;
+        }
+    };
+}